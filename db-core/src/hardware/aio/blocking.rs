@@ -0,0 +1,56 @@
+use super::op::*;
+use crate::utilities::PosIo;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::io::Result;
+use std::sync::atomic::{AtomicU64, Ordering::*};
+
+/// run an operation in the calling thread
+fn run(op: Op) -> Result<Vec<u8>> {
+    match op {
+        Op::Read(file, at, len) => {
+            let mut buf = vec![0u8; len];
+            file.read_exact_at(&mut buf, at)?;
+            Ok(buf)
+        }
+        Op::Write(file, at, buf) => {
+            file.write_all_at(&buf, at)?;
+            Ok(buf)
+        }
+        Op::Sync(file) => {
+            file.sync_data()?;
+            Ok(vec![])
+        }
+    }
+}
+
+/// the fallback backend, every operation runs with positioned reads / writes and fsync at submission
+#[derive(Debug, Default)]
+pub struct Blocking {
+    next: AtomicU64,
+    done: Mutex<HashMap<u64, Result<Vec<u8>>>>,
+}
+
+impl Blocking {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Aio for Blocking {
+    fn submit(&self, op: Op) -> Result<Ticket> {
+        let id = self.next.fetch_add(1, Relaxed);
+        let res = run(op);
+        self.done.lock().insert(id, res);
+        Ok(Ticket(id))
+    }
+    fn poll(&self, ticket: &Ticket) -> Option<Result<Vec<u8>>> {
+        self.done.lock().remove(&ticket.0)
+    }
+    fn wait(&self, ticket: Ticket) -> Result<Vec<u8>> {
+        self.poll(&ticket).expect("a blocking operation completes at submission")
+    }
+    fn name(&self) -> &'static str {
+        "blocking"
+    }
+}
@@ -0,0 +1,71 @@
+use super::*;
+use crate::rw_durable::check_util::*;
+use std::fs::OpenOptions;
+use std::sync::Arc;
+
+// every backend available on this machine
+fn backends() -> Vec<Arc<dyn Aio>> {
+    let mut all: Vec<Arc<dyn Aio>> = vec![Arc::new(Blocking::new())];
+    #[cfg(target_os = "linux")]
+    if let Ok(uring) = Uring::new(8) { all.push(Arc::new(uring)) }
+    all
+}
+
+#[test]
+fn is_in_flight_io_complete() {
+    for aio in backends() {
+        let path = tmp_path("aio");
+        let file = Arc::new(OpenOptions::new().read(true).write(true).create(true).truncate(true).open(&path).unwrap());
+        // more writes in flight than submission entries, out of offset order
+        let tickets = (0..64u64).rev()
+            .map(|n| aio.submit(Op::Write(file.clone(), n * 100, vec![n as u8; 100])).unwrap())
+            .collect::<Vec<_>>();
+        for (n, ticket) in (0..64u64).rev().zip(tickets) {
+            assert!(aio.wait(ticket).unwrap() == vec![n as u8; 100], "{}", aio.name());
+        }
+        aio.wait(aio.submit(Op::Sync(file.clone())).unwrap()).unwrap();
+        assert!(std::fs::metadata(&path).unwrap().len() == 6400);
+        let tickets = (0..64u64)
+            .map(|n| (n, aio.submit(Op::Read(file.clone(), n * 100 + 50, 100)).unwrap()))
+            .collect::<Vec<_>>();
+        for (n, ticket) in tickets {
+            let mut expect = vec![n as u8; 50];
+            expect.extend(vec![n as u8 + 1; 50]);
+            if n == 63 {
+                assert!(aio.wait(ticket).is_err(), "{}: reads past the end fail", aio.name());
+            } else {
+                assert!(aio.wait(ticket).unwrap() == expect, "{}", aio.name());
+            }
+        }
+        // a result is taken once
+        let ticket = aio.submit(Op::Read(file.clone(), 0, 10)).unwrap();
+        let res = loop {
+            if let Some(res) = aio.poll(&ticket) { break res }
+            std::thread::yield_now();
+        };
+        assert!(res.unwrap() == vec![0u8; 10] && aio.poll(&ticket).is_none());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
+
+#[test]
+fn is_shared_across_threads() {
+    let path = tmp_path("aio");
+    let file = Arc::new(OpenOptions::new().read(true).write(true).create(true).truncate(true).open(&path).unwrap());
+    let aio = backend(16);
+    std::thread::scope(|s| for t in 0..8u64 {
+        let (aio, file) = (aio.clone(), file.clone());
+        s.spawn(move || {
+            let tickets = (0..32u64)
+                .map(|n| aio.submit(Op::Write(file.clone(), (t * 32 + n) * 8, (t * 32 + n).to_le_bytes().to_vec())).unwrap())
+                .collect::<Vec<_>>();
+            for ticket in tickets { aio.wait(ticket).unwrap(); }
+        });
+    });
+    let bytes = std::fs::read(&path).unwrap();
+    assert!(bytes.len() == 256 * 8, "{}", aio.name());
+    for (n, chunk) in bytes.chunks(8).enumerate() {
+        assert!(u64::from_le_bytes(chunk.try_into().unwrap()) == n as u64);
+    }
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,25 @@
+//! ## Asynchronous File I/O
+//!
+//! > Axboe, Jens. "Efficient IO with io_uring." (2019).
+//!
+//! A submission/completion interface for positioned file I/O, so an engine can keep many reads and writes in flight and collect them later.
+//! An op is submitted for a ticket, and the ticket is polled or waited on for its buffer: the bytes read, or the buffer that was written.
+//! On Linux, Uring pushes ops to an io_uring submission queue, a reaper thread collects completions and wakes the waiters.
+//! Blocking is the fallback everywhere: it runs an op with positioned reads / writes (pread / pwrite, or seek_read / seek_write on windows) and fsync at submission, so a ticket is complete once it is handed out.
+//! An engine holds an `Arc<dyn Aio>`, so the same engine runs on either backend, and `backend` picks io_uring when the kernel allows it.
+
+// operations, tickets and the backend trait
+mod op;
+// positioned read / write fallback
+mod blocking;
+// io_uring backend
+#[cfg(target_os = "linux")]
+mod uring;
+
+pub use op::*;
+pub use blocking::*;
+#[cfg(target_os = "linux")]
+pub use uring::*;
+
+#[cfg(test)]
+mod check; // backend checks
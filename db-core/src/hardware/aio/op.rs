@@ -0,0 +1,40 @@
+use std::fs::File;
+use std::io::Result;
+use std::sync::Arc;
+
+/// a positioned file operation, the file is held until the operation completes
+#[derive(Debug)]
+pub enum Op {
+    // read len bytes at an offset
+    Read(Arc<File>, u64, usize),
+    // write a whole buffer at an offset
+    Write(Arc<File>, u64, Vec<u8>),
+    // sync file data to disk
+    Sync(Arc<File>),
+}
+
+/// a handle to a submitted operation
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub struct Ticket(pub(super) u64);
+
+/// an asynchronous i/o backend
+pub trait Aio: Send + Sync {
+    /// submit an operation, it may not start before this returns
+    fn submit(&self, op: Op) -> Result<Ticket>;
+    /// take the result of an operation if it is complete, the bytes read or the buffer written
+    fn poll(&self, ticket: &Ticket) -> Option<Result<Vec<u8>>>;
+    /// wait until an operation completes and take its result
+    fn wait(&self, ticket: Ticket) -> Result<Vec<u8>>;
+    /// the name of this backend
+    fn name(&self) -> &'static str;
+}
+
+/// io_uring with a given number of submission entries if the kernel allows it, otherwise blocking i/o
+pub fn backend(entries: u32) -> Arc<dyn Aio> {
+    #[cfg(target_os = "linux")]
+    if let Ok(uring) = super::Uring::new(entries) {
+        return Arc::new(uring)
+    }
+    let _ = entries;
+    Arc::new(super::Blocking::new())
+}
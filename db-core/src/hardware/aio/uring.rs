@@ -0,0 +1,172 @@
+use super::op::*;
+use io_uring::{opcode, squeue, types, IoUring};
+use parking_lot::{Condvar, Mutex};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Error, ErrorKind, Result};
+use std::os::unix::io::AsRawFd;
+use std::sync::atomic::{AtomicU64, Ordering::*};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+// the user data of the entry that stops the reaper
+const STOP: u64 = u64::MAX;
+
+// an operation in flight, the kernel owns its buffer until it completes
+struct Flight {
+    _file: Arc<File>,
+    // the buffer read into or written, empty for a sync
+    buf: Vec<u8>,
+}
+
+// a push error, the entry may be in the submission queue already
+enum PushErr {
+    Full(Error),
+    Submit(Error),
+}
+
+impl PushErr {
+    fn is_pushed(&self) -> bool {
+        matches!(self, PushErr::Submit(_))
+    }
+}
+
+impl From<PushErr> for Error {
+    fn from(e: PushErr) -> Self {
+        match e { PushErr::Full(e) | PushErr::Submit(e) => e }
+    }
+}
+
+struct Ring {
+    ring: IoUring,
+    // only one thread pushes to the submission queue at a time
+    sq: Mutex<()>,
+    next: AtomicU64,
+    // lock order: done, inflight
+    inflight: Mutex<HashMap<u64, Flight>>,
+    done: Mutex<HashMap<u64, Result<Vec<u8>>>>,
+    cond: Condvar,
+}
+
+impl Ring {
+    fn push(&self, entry: &squeue::Entry) -> std::result::Result<(), PushErr> {
+        let _sq = self.sq.lock();
+        // safety: the submission queue is only touched with the sq lock held,
+        // and buffers stay in the inflight map until their completions are reaped
+        while unsafe { self.ring.submission_shared().push(entry) }.is_err() {
+            // the submission queue is full, hand its entries to the kernel
+            self.ring.submit().map_err(PushErr::Full)?;
+        }
+        loop {
+            match self.ring.submit() {
+                Err(e) if matches!(e.kind(), ErrorKind::Interrupted | ErrorKind::WouldBlock) => continue,
+                res => return res.map(|_| ()).map_err(PushErr::Submit),
+            }
+        }
+    }
+    // move completions to the done map and wake waiters, return whether the stop entry is seen
+    fn reap(&self) -> bool {
+        let mut stop = false;
+        let mut done = self.done.lock();
+        let mut inflight = self.inflight.lock();
+        // safety: the completion queue is only touched by the reaper thread
+        for cqe in unsafe { self.ring.completion_shared() } {
+            if cqe.user_data() == STOP { stop = true; continue }
+            let Some(flight) = inflight.remove(&cqe.user_data()) else { continue };
+            let res = match cqe.result() {
+                n if n < 0 => Err(Error::from_raw_os_error(-n)),
+                // regular files transfer whole buffers unless a read runs past the end
+                n if (n as usize) < flight.buf.len() => Err(ErrorKind::UnexpectedEof.into()),
+                _ => Ok(flight.buf),
+            };
+            done.insert(cqe.user_data(), res);
+        }
+        self.cond.notify_all();
+        stop
+    }
+}
+
+/// the io_uring backend, a reaper thread collects completions
+pub struct Uring {
+    ring: Arc<Ring>,
+    reaper: Option<JoinHandle<()>>,
+}
+
+impl Uring {
+    /// set up a ring with a given number of submission entries
+    pub fn new(entries: u32) -> Result<Self> {
+        let ring = Arc::new(Ring {
+            ring: IoUring::new(entries.max(1))?,
+            sq: Mutex::new(()),
+            next: AtomicU64::new(0),
+            inflight: Mutex::new(HashMap::new()),
+            done: Mutex::new(HashMap::new()),
+            cond: Condvar::new(),
+        });
+        let cpy = Arc::clone(&ring);
+        let reaper = move || {
+            let mut stop = false;
+            // after the stop entry, keep reaping until the kernel hands back every buffer
+            while !stop || !cpy.inflight.lock().is_empty() {
+                if let Err(e) = cpy.ring.submit_and_wait(1) {
+                    if e.kind() != ErrorKind::Interrupted { std::thread::yield_now() }
+                }
+                stop |= cpy.reap();
+            }
+        };
+        Ok(Uring { ring, reaper: Some(std::thread::spawn(reaper)) })
+    }
+}
+
+impl Aio for Uring {
+    fn submit(&self, op: Op) -> Result<Ticket> {
+        let id = self.ring.next.fetch_add(1, Relaxed);
+        let too_long = || Error::new(ErrorKind::InvalidInput, "an operation transfers at most u32::MAX bytes");
+        let (file, buf, entry) = match op {
+            Op::Read(file, at, len) => {
+                let mut buf = vec![0u8; len];
+                let fd = types::Fd(file.as_raw_fd());
+                let entry = opcode::Read::new(fd, buf.as_mut_ptr(), u32::try_from(len).map_err(|_| too_long())?).offset(at).build();
+                (file, buf, entry)
+            }
+            Op::Write(file, at, buf) => {
+                let fd = types::Fd(file.as_raw_fd());
+                let entry = opcode::Write::new(fd, buf.as_ptr(), u32::try_from(buf.len()).map_err(|_| too_long())?).offset(at).build();
+                (file, buf, entry)
+            }
+            Op::Sync(file) => {
+                let entry = opcode::Fsync::new(types::Fd(file.as_raw_fd())).flags(types::FsyncFlags::DATASYNC).build();
+                (file, vec![], entry)
+            }
+        };
+        self.ring.inflight.lock().insert(id, Flight { _file: file, buf });
+        if let Err(e) = self.ring.push(&entry.user_data(id)) {
+            // an entry that never reached the submission queue is not in flight
+            if !e.is_pushed() { self.ring.inflight.lock().remove(&id); }
+            return Err(e.into())
+        }
+        Ok(Ticket(id))
+    }
+    fn poll(&self, ticket: &Ticket) -> Option<Result<Vec<u8>>> {
+        self.ring.done.lock().remove(&ticket.0)
+    }
+    fn wait(&self, ticket: Ticket) -> Result<Vec<u8>> {
+        let mut done = self.ring.done.lock();
+        loop {
+            if let Some(res) = done.remove(&ticket.0) { return res }
+            self.ring.cond.wait(&mut done);
+        }
+    }
+    fn name(&self) -> &'static str {
+        "io_uring"
+    }
+}
+
+impl Drop for Uring {
+    fn drop(&mut self) {
+        let stop = opcode::Nop::new().build().user_data(STOP);
+        if let (Ok(()), Some(reaper)) = (self.ring.push(&stop), self.reaper.take()) {
+            reaper.join().unwrap_or(());
+        }
+    }
+}
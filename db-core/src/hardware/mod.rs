@@ -0,0 +1,5 @@
+/// simulated block device, with queueing, latency, bandwidth and a volatile write cache
+pub mod sim_disk;
+
+/// asynchronous file i/o, io_uring on linux with a blocking fallback
+pub mod aio;
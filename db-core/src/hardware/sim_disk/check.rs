@@ -0,0 +1,100 @@
+use super::*;
+use crate::rw_durable::latency::Dist;
+use std::sync::Arc;
+use std::time::Duration;
+
+fn us(n: u64) -> Duration {
+    Duration::from_micros(n)
+}
+
+// a device with fixed latencies, so times are exact
+fn fixed(depth: usize, bandwidth: u64) -> DiskConf {
+    DiskConf {
+        depth, bandwidth,
+        read: Dist::Fixed(us(100)), write: Dist::Fixed(us(10)), flush: Dist::Fixed(us(1000)),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn is_timing_modeled() {
+    let mut block = vec![0u8; 4096];
+    // reads are served depth at a time
+    for (depth, elapsed) in [(1, us(10000)), (4, us(2500))] {
+        let disk = SimDisk::new(fixed(depth, 0));
+        for i in 0..100 { disk.read(i, &mut block).unwrap() }
+        let stats = disk.stats();
+        assert!(stats.reads == 100 && stats.read_bytes == 409600 && stats.elapsed == elapsed);
+        assert!((stats.iops() - 100.0 / elapsed.as_secs_f64()).abs() < 1e-6);
+    }
+    // at 4 MiB per second a block takes about 1 ms on the bus, which dominates
+    let disk = SimDisk::new(fixed(32, 4 << 20));
+    for i in 0..100 { disk.write(i, &block).unwrap() }
+    let elapsed = disk.stats().elapsed;
+    assert!(us(97650) < elapsed && elapsed < us(97670));
+    // the same seed gives the same time
+    let run = || {
+        let disk = SimDisk::new(DiskConf { seed: 7, ..Default::default() });
+        for i in 0..100 { disk.write(i, &block).unwrap() }
+        disk.stats().elapsed
+    };
+    assert!(run() == run());
+    assert!(matches!(SimDisk::new(fixed(1, 0)).read(0, &mut block[..100]), Err(DiskErr::Unaligned)));
+    assert!(matches!(SimDisk::new(fixed(1, 0)).read(1 << 20, &mut block), Err(DiskErr::OutOfRange(_))));
+}
+
+#[test]
+fn is_cache_lost_on_power_loss() {
+    let disk = SimDisk::new(fixed(1, 0));
+    let (mut buf, a, b) = (vec![0u8; 4096], vec![1u8; 4096], vec![2u8; 4096]);
+    disk.write(0, &a).unwrap();
+    disk.flush();
+    disk.write(0, &b).unwrap();
+    disk.write(1, &b).unwrap();
+    disk.read(0, &mut buf).unwrap();
+    assert!(buf == b);
+    disk.power_loss();
+    disk.read(0, &mut buf).unwrap();
+    assert!(buf == a);
+    disk.read(1, &mut buf).unwrap();
+    assert!(buf.iter().all(|x| *x == 0));
+    // without a write cache, writes are durable at once
+    let disk = SimDisk::new(DiskConf { cache: false, ..fixed(1, 0) });
+    disk.write(0, &b).unwrap();
+    disk.power_loss();
+    disk.read(0, &mut buf).unwrap();
+    assert!(buf == b);
+}
+
+#[test]
+fn is_file_recovered() {
+    let disk = Arc::new(SimDisk::new(fixed(4, 0)));
+    let bytes = (0..10000u32).map(|x| (x % 251) as u8).collect::<Vec<_>>();
+    {
+        let file = SimFile::open(disk.clone(), 100, 16).unwrap();
+        assert!(file.is_empty());
+        // unaligned writes across block boundaries
+        file.write_all_at(&bytes[..5000], 0).unwrap();
+        file.write_all_at(&bytes[5000..], 5000).unwrap();
+        file.sync_data().unwrap();
+        file.write_all_at(b"lost", 10000).unwrap();
+        file.write_all_at(b"also lost", 3).unwrap();
+        assert!(file.len() == 10004);
+        let mut buf = vec![0u8; 9];
+        file.read_exact_at(&mut buf, 3).unwrap();
+        assert!(buf == b"also lost");
+    }
+    disk.power_loss();
+    let file = SimFile::open(disk.clone(), 100, 16).unwrap();
+    let mut buf = vec![0u8; 10000];
+    file.read_exact_at(&mut buf, 0).unwrap();
+    assert!(file.len() == 10000 && buf == bytes);
+    assert!(file.read_exact_at(&mut [0u8; 1], 10000).is_err());
+    file.set_len(10).unwrap();
+    file.set_len(20).unwrap();
+    let mut buf = vec![0u8; 20];
+    file.read_exact_at(&mut buf, 0).unwrap();
+    assert!(buf[..10] == bytes[..10] && buf[10..].iter().all(|x| *x == 0));
+    // a file never goes beyond its blocks
+    assert!(file.write_all_at(&[0u8; 1], 15 * 4096).is_err());
+}
@@ -0,0 +1,203 @@
+use super::error::*;
+use crate::rw_durable::latency::{wait, Dist};
+use parking_lot::{Condvar, Mutex};
+use rand::{Rng, SeedableRng};
+use rand_xoshiro::Xoshiro256PlusPlus;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// device configuration
+#[derive(Debug)]
+pub struct DiskConf {
+    /// the size of a block in bytes, and the number of blocks
+    pub block: usize,
+    pub blocks: u64,
+    /// the number of ops served at the same time
+    pub depth: usize,
+    /// latency of an op before its transfer
+    pub read: Dist,
+    pub write: Dist,
+    pub flush: Dist,
+    /// bytes per second on the bus, 0 for no limit
+    pub bandwidth: u64,
+    /// whether writes land in a volatile write cache
+    pub cache: bool,
+    /// the chance that a cached block is on the media already at a power loss
+    pub survive: f64,
+    /// seed of latency draws and power losses
+    pub seed: u64,
+    /// whether an op also waits its service time in real time
+    pub real: bool,
+}
+
+impl Default for DiskConf {
+    fn default() -> Self {
+        // roughly a datacenter nvme ssd
+        DiskConf {
+            block: 4096,
+            blocks: 1 << 20,
+            depth: 32,
+            read: Dist::LogNormal(Duration::from_micros(80), 0.3),
+            write: Dist::LogNormal(Duration::from_micros(20), 0.3),
+            flush: Dist::Fixed(Duration::from_micros(500)),
+            bandwidth: 2 << 30,
+            cache: true,
+            survive: 0.0,
+            seed: 0,
+            real: false,
+        }
+    }
+}
+
+/// op counts and device time
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DiskStats {
+    pub reads: u64,
+    pub writes: u64,
+    pub flushes: u64,
+    pub read_bytes: u64,
+    pub written_bytes: u64,
+    /// virtual time until the last op completes
+    pub elapsed: Duration,
+}
+
+impl DiskStats {
+    /// reads and writes per second of device time
+    pub fn iops(&self) -> f64 {
+        (self.reads + self.writes) as f64 / self.elapsed.as_secs_f64()
+    }
+}
+
+struct State {
+    // blocks on the media, and blocks in the write cache, absent blocks are zeros
+    media: BTreeMap<u64, Box<[u8]>>,
+    cache: BTreeMap<u64, Box<[u8]>>,
+    // the time each queue slot is free again, and the time the bus is free again
+    slots: Vec<Duration>,
+    bus: Duration,
+    stats: DiskStats,
+    rng: Xoshiro256PlusPlus,
+}
+
+enum Op { Read, Write, Flush }
+
+pub struct SimDisk {
+    conf: DiskConf,
+    state: Mutex<State>,
+    // free queue slots, in real time
+    free: Mutex<usize>,
+    freed: Condvar,
+}
+
+impl SimDisk {
+    pub fn new(conf: DiskConf) -> Self {
+        let state = State {
+            media: BTreeMap::new(),
+            cache: BTreeMap::new(),
+            slots: vec![Duration::ZERO; conf.depth.max(1)],
+            bus: Duration::ZERO,
+            stats: DiskStats::default(),
+            rng: Xoshiro256PlusPlus::seed_from_u64(conf.seed),
+        };
+        let free = Mutex::new(conf.depth.max(1));
+        SimDisk { conf, state: Mutex::new(state), free, freed: Condvar::new() }
+    }
+    pub fn block(&self) -> usize {
+        self.conf.block
+    }
+    pub fn blocks(&self) -> u64 {
+        self.conf.blocks
+    }
+    pub fn stats(&self) -> DiskStats {
+        self.state.lock().stats
+    }
+    // check that an op covers whole blocks inside the device, return the number of blocks
+    fn check(&self, at: u64, len: usize) -> Result<u64, DiskErr> {
+        if !len.is_multiple_of(self.conf.block) { return Err(DiskErr::Unaligned) }
+        let n = (len / self.conf.block) as u64;
+        if at.checked_add(n).is_none_or(|end| end > self.conf.blocks) { return Err(DiskErr::OutOfRange(at)) }
+        Ok(n)
+    }
+    // account an op in virtual time, return its service time
+    fn time(&self, state: &mut State, op: Op, bytes: u64) -> Duration {
+        let dist = match op { Op::Read => &self.conf.read, Op::Write => &self.conf.write, Op::Flush => &self.conf.flush };
+        let lat = dist.sample(&mut state.rng);
+        let (slot, start) = state.slots.iter().copied().enumerate().min_by_key(|(_, t)| *t).unwrap();
+        let transfer = match self.conf.bandwidth {
+            0 => Duration::ZERO,
+            bw => Duration::from_secs_f64(bytes as f64 / bw as f64),
+        };
+        let mut done = start + lat;
+        if bytes > 0 {
+            done = done.max(state.bus) + transfer;
+            state.bus = done;
+        }
+        state.slots[slot] = done;
+        state.stats.elapsed = state.stats.elapsed.max(done);
+        done - start
+    }
+    // wait a service time in real time, holding a queue slot
+    fn serve(&self, service: Duration) {
+        if !self.conf.real { return }
+        let mut free = self.free.lock();
+        while *free == 0 { self.freed.wait(&mut free) }
+        *free -= 1;
+        drop(free);
+        wait(service);
+        *self.free.lock() += 1;
+        self.freed.notify_one();
+    }
+    /// read whole blocks from a block on
+    pub fn read(&self, at: u64, buf: &mut [u8]) -> Result<(), DiskErr> {
+        let n = self.check(at, buf.len())?;
+        let service = {
+            let mut state = self.state.lock();
+            for (i, chunk) in (at..at + n).zip(buf.chunks_mut(self.conf.block)) {
+                match state.cache.get(&i).or_else(|| state.media.get(&i)) {
+                    Some(block) => chunk.copy_from_slice(block),
+                    None => chunk.fill(0),
+                }
+            }
+            state.stats.reads += 1;
+            state.stats.read_bytes += buf.len() as u64;
+            self.time(&mut state, Op::Read, buf.len() as u64)
+        };
+        self.serve(service);
+        Ok(())
+    }
+    /// write whole blocks from a block on, durable after a flush if the write cache is on
+    pub fn write(&self, at: u64, data: &[u8]) -> Result<(), DiskErr> {
+        let n = self.check(at, data.len())?;
+        let service = {
+            let mut state = self.state.lock();
+            for (i, chunk) in (at..at + n).zip(data.chunks(self.conf.block)) {
+                let store = if self.conf.cache { &mut state.cache } else { &mut state.media };
+                store.insert(i, chunk.into());
+            }
+            state.stats.writes += 1;
+            state.stats.written_bytes += data.len() as u64;
+            self.time(&mut state, Op::Write, data.len() as u64)
+        };
+        self.serve(service);
+        Ok(())
+    }
+    /// destage the write cache to the media
+    pub fn flush(&self) {
+        let service = {
+            let mut state = self.state.lock();
+            let cache = std::mem::take(&mut state.cache);
+            state.media.extend(cache);
+            state.stats.flushes += 1;
+            self.time(&mut state, Op::Flush, 0)
+        };
+        self.serve(service);
+    }
+    /// lose power, the write cache is dropped but for blocks destaged by chance
+    pub fn power_loss(&self) {
+        let mut state = self.state.lock();
+        let state = &mut *state;
+        for (i, block) in std::mem::take(&mut state.cache) {
+            if state.rng.gen::<f64>() < self.conf.survive { state.media.insert(i, block); }
+        }
+    }
+}
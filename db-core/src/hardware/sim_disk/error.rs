@@ -0,0 +1,13 @@
+#[derive(Debug)]
+pub enum DiskErr {
+    // an op is not a whole number of blocks
+    Unaligned,
+    // an op goes beyond the last block, from a given block
+    OutOfRange(u64),
+}
+
+impl From<DiskErr> for std::io::Error {
+    fn from(e: DiskErr) -> Self {
+        std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("{e:?}"))
+    }
+}
@@ -0,0 +1,107 @@
+use super::device::*;
+use crate::utilities::{FileIo, PosIo};
+use parking_lot::Mutex;
+use std::io::{Error, ErrorKind, Result};
+use std::sync::Arc;
+
+/// a byte-addressed file over a range of blocks of a device, its first block holds the length as of the last sync
+pub struct SimFile {
+    disk: Arc<SimDisk>,
+    // the header block, data follows it
+    start: u64,
+    blocks: u64,
+    // the current length
+    len: Mutex<u64>,
+}
+
+impl SimFile {
+    /// open a file over blocks [start, start + blocks), a never written one is empty
+    pub fn open(disk: Arc<SimDisk>, start: u64, blocks: u64) -> Result<Self> {
+        let mut head = vec![0u8; disk.block()];
+        disk.read(start, &mut head)?;
+        let len = u64::from_le_bytes(head[..8].try_into().unwrap());
+        Ok(SimFile { disk, start, blocks, len: Mutex::new(len) })
+    }
+    pub fn len(&self) -> u64 {
+        *self.len.lock()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    // the blocks covering a byte range: the first block (after the header) and the bytes of all covering blocks
+    fn span(&self, off: u64, n: usize) -> Result<(u64, usize)> {
+        let size = self.disk.block() as u64;
+        let (first, last) = (off / size, (off + n as u64).div_ceil(size));
+        if last + 1 > self.blocks { return Err(Error::new(ErrorKind::StorageFull, "file is out of blocks")) }
+        Ok((self.start + 1 + first, ((last - first) * size) as usize))
+    }
+    pub fn read_exact_at(&self, buf: &mut [u8], off: u64) -> Result<()> {
+        if off + buf.len() as u64 > self.len() { return Err(ErrorKind::UnexpectedEof.into()) }
+        if buf.is_empty() { return Ok(()) }
+        let (at, bytes) = self.span(off, buf.len())?;
+        let mut blocks = vec![0u8; bytes];
+        self.disk.read(at, &mut blocks)?;
+        let skip = (off % self.disk.block() as u64) as usize;
+        buf.copy_from_slice(&blocks[skip..skip + buf.len()]);
+        Ok(())
+    }
+    pub fn write_all_at(&self, data: &[u8], off: u64) -> Result<()> {
+        let mut len = self.len.lock();
+        if data.is_empty() { return Ok(()) }
+        let (at, bytes) = self.span(off, data.len())?;
+        let size = self.disk.block();
+        let skip = (off % size as u64) as usize;
+        let mut blocks = vec![0u8; bytes];
+        // read, modify and write partial blocks at both ends
+        if skip != 0 { self.disk.read(at, &mut blocks[..size])? }
+        if !(skip + data.len()).is_multiple_of(size) && (bytes > size || skip == 0) {
+            self.disk.read(at + (bytes / size) as u64 - 1, &mut blocks[bytes - size..])?;
+        }
+        blocks[skip..skip + data.len()].copy_from_slice(data);
+        self.disk.write(at, &blocks)?;
+        *len = (*len).max(off + data.len() as u64);
+        Ok(())
+    }
+    /// truncate or extend with zeros
+    pub fn set_len(&self, new: u64) -> Result<()> {
+        let old = self.len();
+        if new > old {
+            self.write_all_at(&vec![0u8; (new - old) as usize], old)?;
+        }
+        *self.len.lock() = new;
+        Ok(())
+    }
+    /// make written bytes and the length durable, bytes first so a length never covers lost bytes
+    pub fn sync_data(&self) -> Result<()> {
+        self.disk.flush();
+        let mut head = vec![0u8; self.disk.block()];
+        head[..8].copy_from_slice(&self.len().to_le_bytes());
+        self.disk.write(self.start, &head)?;
+        self.disk.flush();
+        Ok(())
+    }
+    pub fn sync_all(&self) -> Result<()> {
+        self.sync_data()
+    }
+}
+
+impl PosIo for SimFile {
+    fn read_exact_at(&self, buf: &mut [u8], at: u64) -> Result<()> {
+        SimFile::read_exact_at(self, buf, at)
+    }
+    fn write_all_at(&self, buf: &[u8], at: u64) -> Result<()> {
+        SimFile::write_all_at(self, buf, at)
+    }
+}
+
+impl FileIo for SimFile {
+    fn size(&self) -> Result<u64> {
+        Ok(self.len())
+    }
+    fn set_len(&self, len: u64) -> Result<()> {
+        SimFile::set_len(self, len)
+    }
+    fn sync_data(&self) -> Result<()> {
+        SimFile::sync_data(self)
+    }
+}
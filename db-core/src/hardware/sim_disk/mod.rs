@@ -0,0 +1,24 @@
+//! ## Simulated Block Device
+//!
+//! > Bucy, John S., et al. "The DiskSim simulation environment version 4.0 reference manual." Carnegie Mellon University Parallel Data Lab (2008).
+//!
+//! A block device in memory, for reproducible performance and crash semantics in tests with no real disk.
+//! Time is virtual by default: an op waits for the earliest free slot of the queue (queue depth slots), takes a latency drawn from a seeded distribution, then transfers its bytes over a bus shared by all slots at a bandwidth limit.
+//! So elapsed device time and iops depend on the workload and the configuration only, not on the machine; optionally an op also waits its service time in real time.
+//! With a write cache, writes land in volatile memory and reach the media on flush; a power loss drops the cache, except for a seeded random subset of blocks that were destaged already.
+//! A SimFile lays a byte-addressed file over a range of blocks, with the read_exact_at / write_all_at / sync_data / set_len surface of a file used by on-disk engines,
+//! so an engine generic over its file (PosIo, FileIo) runs on it, e.g. an encrypted log (CryptNull::with_file).
+
+// device error
+mod error;
+// core device model
+mod device;
+// byte-addressed files over blocks
+mod file;
+
+pub use error::*;
+pub use device::*;
+pub use file::*;
+
+#[cfg(test)]
+mod check; // timing, power loss and file checks
@@ -0,0 +1,107 @@
+use super::*;
+use crate::rw_durable::cmd_log::replay_cmds;
+use crate::tx_service::m_thread::*;
+use dashmap::DashMap;
+use db_test::core_workload::int::unif::*;
+use std::collections::BTreeMap;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use typing::constraint::*;
+use typing::rw::*;
+use typing::tx::*;
+
+const N_TXN: u64 = 200;
+const N_KEY: u64 = 4;
+const SEED: u64 = 42;
+
+type State = BTreeMap<u64, u64>;
+
+// a transaction writing N_KEY keys of its own (no reads), so its write set never depends on scheduling
+#[derive(Debug, Clone, Copy)]
+struct Blind {
+    id: u64,
+    step: u64,
+}
+
+impl TxCkpt for Blind {
+    type Ckpt = u64;
+    fn make(&mut self) -> Self::Ckpt { self.step }
+    fn goto(&mut self, ckpt: Self::Ckpt) { self.step = ckpt; }
+}
+
+impl Tx<U64Tup> for Blind {
+    type I = u64;
+    type Prp = U64Prp;
+    type Map = U64Map;
+    type Out = u64;
+    fn id(&self) -> Self::I { self.id }
+    fn go(self) -> RWClosure<Self, Self::Prp, Self::Map> {
+        if self.step == N_KEY { return RWClosure::Cl(self, End::Ready); }
+        let k = self.id * N_KEY + self.step;
+        RWClosure::Wr(self, U64Map(Some((k, Some(U64Tup(k, self.id))))))
+    }
+    fn rd(self, _map: Self::Map) -> Self { self }
+    fn wr(mut self) -> Self { self.step += 1; self }
+    fn op(self) -> Self { self }
+    fn cl(self) -> Option<Self::Out> { Some(self.id) }
+}
+
+// an in-memory engine over a shared table, readable after its service is dropped
+struct Probe<T> {
+    table: Arc<DashMap<u64, u64>>,
+    phant: PhantomData<T>,
+}
+
+impl<T: Tx<U64Tup, Prp = U64Prp, Map = U64Map>> RWDurable<U64Tup, T> for Probe<T> {
+    type Err = ();
+    fn open(&self, _txn: &T) -> Result<(), ()> { Ok(()) }
+    fn done(&self, _txn: &T, _end: End) -> Result<(), ()> { Ok(()) }
+    fn rd(&self, U64Prp(k): U64Prp) -> Result<U64Map, ()> {
+        Ok(U64Map(self.table.get(&k).map(|v| (k, Some(U64Tup(k, *v))))))
+    }
+    fn wr(&self, _txn: &T, map: U64Map) -> Result<(), ()> {
+        for (k, v) in map.into_mapping() {
+            match v {
+                Some(U64Tup(_, v)) => { self.table.insert(k, v); }
+                None => { self.table.remove(&k); }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn probe<T>() -> (Probe<T>, Arc<DashMap<u64, u64>>) {
+    let table = Arc::new(DashMap::new());
+    (Probe { table: table.clone(), phant: PhantomData }, table)
+}
+
+fn workload() -> Vec<Blind> {
+    (1..=N_TXN).map(|id| Blind { id, step: 0 }).collect()
+}
+
+fn state(table: &DashMap<u64, u64>) -> State {
+    table.iter().map(|e| (*e.key(), *e.value())).collect()
+}
+
+fn expect() -> State {
+    (1..=N_TXN).flat_map(|id| (0..N_KEY).map(move |s| (id * N_KEY + s, id))).collect()
+}
+
+#[test]
+fn is_every_written_key_committed() {
+    let (dur, table) = probe();
+    let mut service = MThreadService::new(4, |x| x, Serial::<Blind, U64Tup>::new(), dur);
+    replay_cmds::<U64Tup, _, _>(workload(), &mut service).unwrap_or_else(|_| panic!("fail to run serial"));
+    drop(service);
+    assert!(state(&table) == expect());
+    let (dur, table) = probe();
+    let mut service = MThreadService::new(4, KVSparkleTx::new, KVSparkle::<Blind, U64Tup>::new(SEED), dur);
+    replay_cmds::<U64Tup, _, _>(workload(), &mut service).unwrap_or_else(|_| panic!("fail to run sparkle"));
+    drop(service);
+    assert!(state(&table) == expect());
+    let (dur, table) = probe();
+    let mut service = MThreadService::new(4, KVSpliceTx::new, KVSplice::<Blind, U64Tup>::new(SEED), dur);
+    replay_cmds::<U64Tup, _, _>(workload(), &mut service).unwrap_or_else(|_| panic!("fail to run splice"));
+    drop(service);
+    assert!(state(&table) == expect());
+}
@@ -0,0 +1,57 @@
+use super::*;
+use crate::rw_durable::null::*;
+use crate::utilities::*;
+use db_test::core_workload::int::unif::*;
+use typing::rw::*;
+use typing::tx::*;
+
+type Txn = KVSparkleTx<U64Tup, U64Txn>;
+
+// transactions with ids 1 and 2 that always commit when closed, driven by hand to force a conflict
+fn setup() -> (KVSparkle<U64Txn, U64Tup>, Null<U64Tup, Txn>, Txn, Txn) {
+    let mut workload = U64Gen::new(0, (0, 0, 0, 1), 100);
+    workload.get();
+    let (t1, t2) = (KVSparkleTx::new(workload.get()), KVSparkleTx::new(workload.get()));
+    let con = KVSparkle::with_policy(LowestTid);
+    let dur = Null::new(0, 0, false);
+    let t1 = con.open(t1, &dur).unwrap();
+    let t2 = con.open(t2, &dur).unwrap();
+    (con, dur, t1, t2)
+}
+
+fn put(k: u64) -> U64Map {
+    U64Map(Some((k, Some(U64Tup(k, k)))))
+}
+
+#[test]
+fn is_preemption_logged() {
+    let (con, dur, t1, t2) = setup();
+    // txn 2 speculatively writes two keys, then txn 1 takes over the lock of key 5
+    let t2 = con.wr(t2, put(5), &dur).unwrap().unwrap();
+    let t2 = con.wr(t2, put(6), &dur).unwrap().unwrap();
+    let _t1 = con.wr(t1, put(5), &dur).unwrap().unwrap();
+    // txn 2 finds out at its next step and throws both writes away
+    con.done(t2, End::Ready, &dur).unwrap();
+    let log = con.reset_log();
+    let stats = log.get(2).unwrap();
+    assert!(stats.count == 1);
+    assert!(stats.records[0].cause == Some(ResetCause::Preempted { key: 5, by: 1 }));
+    assert!(stats.records[0].wasted == 2);
+    assert!(log.get(1).is_none());
+    assert!(log.count() == 1 && log.wasted() == 2);
+}
+
+#[test]
+fn is_stale_read_logged() {
+    let (con, dur, t1, t2) = setup();
+    // txn 2 reads key 7 before txn 1 publishes its write on it
+    let t2 = con.rd(t2, U64Prp(7), &dur).unwrap().unwrap();
+    let t1 = con.wr(t1, put(7), &dur).unwrap().unwrap();
+    let (_, out) = con.done(t1, End::Ready, &dur).unwrap();
+    assert!(out.is_some());
+    con.wr(t2, put(8), &dur).unwrap();
+    let stats = con.reset_log().get(2).unwrap();
+    assert!(stats.count == 1);
+    assert!(stats.records[0].cause == Some(ResetCause::StaleRead { key: 7, by: 1 }));
+    assert!(stats.records[0].wasted == 1);
+}
@@ -13,12 +13,17 @@ mod twrap;
 
 // sparkle error
 mod error;
+// reset cause attribution
+mod stats;
 // core sparkle protocol implementation
 mod proto;
 
 pub use twrap::*;
 pub use proto::*;
 pub use error::*;
+pub use stats::*;
 
 #[cfg(test)]
-mod bench; // inlined benchmark module to detect performance issues
\ No newline at end of file
+mod bench; // inlined benchmark module to detect performance issues
+#[cfg(test)]
+mod check; // reset log checks
\ No newline at end of file
@@ -2,14 +2,16 @@ use super::error::*;
 use crate::utilities::*;
 use super::tpool::*;
 use super::twrap::*;
+use super::stats::*;
 use parking_lot::Mutex;
 use std::fmt::Debug;
 use std::hash::Hash;
+use std::sync::Arc;
 
 use crate::constraint::*;
 use crate::rw::*;
 use crate::tx::*;
-use std::collections::BTreeSet;
+use std::collections::{BTreeSet, HashMap};
 
 // we use this macro to avoid writing the same trait bounds for multiple times
 macro_rules! ellipsis_trait_bag {
@@ -40,12 +42,16 @@ ellipsis_trait_bag![{T, V}
 {pub struct KVSparkle<T, V>}
 where ...
 {
-    // the key value table
-    table: KVTable<T::I, V::I, V>,
+    // the key value table, shared with dependency graph observers
+    table: Arc<KVTable<T::I, V::I, V>>,
     // pending transactions
     tpool: TPool<KVSparkleTx<V, T>, V>,
     // the transcations that need a roll back
     reset: dashmap::DashSet<T::I>,
+    // the cause of a pending roll back
+    blame: dashmap::DashMap<T::I, ResetCause<V::I, T::I>>,
+    // reset history of every transaction
+    rslog: ResetLog<V::I, T::I>,
     // the checkpoints of a transaction
     ckpts: dashmap::DashMap<T::I, T::Ckpt>,
     // the last committed transaction
@@ -61,16 +67,36 @@ ellipsis_trait_bag![{T, V}
 {impl<T, V> KVSparkle<T, V>}
 where ...
 {
-    pub fn new() -> Self {
+    /// schedule suspended transactions with a seeded random policy, a fixed seed replays the same picks
+    pub fn new(seed: u64) -> Self {
+        Self::with_policy(SeededRandom::new(seed))
+    }
+    /// pick a scheduling policy for suspended transactions
+    pub fn with_policy(policy: impl SchedPolicy<T::I> + Send + Sync + 'static) -> Self {
         Self {
-            table: KVTable::new(),
-            tpool: TPool::new(),
+            table: Arc::new(KVTable::new()),
+            tpool: TPool::new(Box::new(policy)),
             reset: dashmap::DashSet::new(),
+            blame: dashmap::DashMap::new(),
+            rslog: ResetLog::new(),
             ckpts: dashmap::DashMap::new(),
             progress: Mutex::new(T::I::zero()),
             last_tid: Mutex::new(T::I::zero()),
         }
     }
+    /// a handle to query reset history, valid even after a run
+    pub fn reset_log(&self) -> ResetLog<V::I, T::I> {
+        self.rslog.clone()
+    }
+    /// a handle to take dependency graph snapshots during a run, see KVTable::dep_graph
+    pub fn table_handle(&self) -> Arc<KVTable<T::I, V::I, V>> {
+        Arc::clone(&self.table)
+    }
+    /// mark a transaction for roll back, keep the first cause
+    fn blame(&self, tid: T::I, cause: ResetCause<V::I, T::I>) {
+        self.reset.insert(tid);
+        self.blame.entry(tid).or_insert(cause);
+    }
     fn reset(&self, mut txn: KVSparkleTx<V, T>) {
         // ----------------------------------------------
         #[cfg(feature="debug")]
@@ -91,14 +117,18 @@ where ...
                 BTreeSet::new()
             };
             for vic in victim.range(self.progress().succ()..) {
-                self.reset.insert(*vic);
+                self.blame(*vic, ResetCause::Cascade { key: key.clone(), by: tid });
             }
         }
+        let wasted = txn.ax.steps;
         let ckpt = self.ckpts.get(&tid).unwrap_or_else(|| unreachable!());
         *txn.ax.as_mut() = Aux::new();
         txn.tx.goto(*ckpt);
-        self.tpool.put_todo(txn);
+        // clear the mark before the transaction can run again, or a mark for its next run would be lost
         self.reset.remove(&tid);
+        let cause = self.blame.remove(&tid).map(|(_, cause)| cause);
+        self.rslog.push(tid, ResetRecord { cause, wasted });
+        self.tpool.put_reset(txn);
     }
     fn submit(&self, tid: T::I) {
         let mut lid = self.last_tid.lock();
@@ -134,8 +164,10 @@ where ...
             self.reset(txn);
             return Ok(self.get_next())
         }
+        txn.ax.steps += 1;
         let mut map = Vec::new();
-        let mut should_read_durable = false;
+        // keys whose versions are only in durable storage
+        let mut durable = Vec::new();
         if let Some(keys) = prp.tryc_indexer() {
             // read versions in range [progress + 1, tid]
             for key in keys {
@@ -150,7 +182,7 @@ where ...
                         txn.ax.rdset.insert(key, (val, ver));
                     },
                     Err(DepDurable) => {
-                        should_read_durable = true;
+                        durable.push(key);
                     },
                     Err(_) => unreachable!()
                 }
@@ -164,10 +196,17 @@ where ...
             "[{:<8?}    rd]       at:{:<8?}      {:?}    {:?}",
             txn.id(), self.progress(), prp, map);
         // -------------------------------------------------------------
-        // fallback to durable storage
-        if should_read_durable {
+        // fallback to durable storage, these keys are read in one batch
+        if !durable.is_empty() {
             // read versions in range [0, progress]
-            for (key, val) in dur.rd(prp).map_err(External)?.into_mapping() {
+            let prps = durable.iter()
+                .map(|key| MaybeIndexer::from_indexer(std::iter::once(key.clone())))
+                .collect();
+            let maps = dur.rd_batch(prps).map_err(External)?;
+            let mut found = maps.iter().flat_map(|map| map.into_mapping()).collect::<HashMap<_, _>>();
+            // a key absent from durable storage is read as deleted, so reading it again gives the same answer
+            for key in durable {
+                let val = found.remove(&key).flatten();
                 // put them into map iff there is no later version
                 if txn.ax.wrset.contains_key(&key) { continue }
                 if txn.ax.rdset.contains_key(&key) { continue }
@@ -200,7 +239,7 @@ where ...
             // acquire write lock and write locally
             match self.table.wlock(key.clone(), tid) {
                 Ok(Some(preempted)) => {
-                    self.reset.insert(preempted);
+                    self.blame(preempted, ResetCause::Preempted { key: key.clone(), by: tid });
                     txn.ax.wrset.insert(key, (val, false));
                 },
                 Ok(None) => {
@@ -213,6 +252,7 @@ where ...
                 Err(_) => unreachable!()
             };
         }
+        txn.ax.steps += 1;
         return Ok(Some(txn.wr()));
     }
     fn done(&self, mut txn: KVSparkleTx<V, T>, end: End, dur: &D)
@@ -240,9 +280,10 @@ where ...
             let wr_result = self.table.write(key, val.clone(), tid);
             match wr_result {
                 Ok(reset) => for r in reset {
-                    self.reset.insert(r);
+                    self.blame(r, ResetCause::StaleRead { key: key.clone(), by: tid });
                 }
                 Err(IsPreempted) => {
+                    self.blame(tid, ResetCause::LockLost { key: key.clone() });
                     self.reset(txn);
                     return Ok((self.get_next(), None));
                 }
@@ -265,12 +306,14 @@ where ...
             self.reset(txn);
             return Ok((self.get_next(), None));
         }
-        let mut map = vec![];
-        for (key, (val, _)) in &txn.ax.wrset {
-            if matches!(end, End::Abort) { continue }
-            map.push((key.clone(), val.clone()));
+        // a mapping may carry a single entry, so each key is a map, and they are written in one batch
+        if matches!(end, End::Ready) {
+            let maps = txn.ax.wrset.iter()
+                .map(|(key, (val, _))| Mapper::from_mapping(std::iter::once((key.clone(), val.clone()))))
+                .collect();
+            dur.wr_batch(&txn, maps).map_err(External)?;
         }
-        dur.wr(&txn, Mapper::from_mapping(map.into_iter()))
+        dur.done(&txn, end)
             .map_err(External)?;
         for (key, (_val, ispub)) in txn.ax.wrset.drain() {
             if matches!(end, End::Abort) { continue }
@@ -279,6 +322,7 @@ where ...
         }
         {*self.progress.lock() = tid;}
         self.ckpts.remove(&tid);
+        self.tpool.retire(tid);
         Ok((self.get_next(), Some(txn.cl())))
     }
     fn open(&self, mut txn: KVSparkleTx<V, T>, dur: &D)
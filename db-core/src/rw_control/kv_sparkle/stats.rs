@@ -0,0 +1,67 @@
+use dashmap::DashMap;
+use std::hash::Hash;
+use std::sync::Arc;
+
+/// why a transaction is reset
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResetCause<K, I> {
+    /// write lock on key is preempted by a smaller tid in wlock
+    Preempted { key: K, by: I },
+    /// read on key is stale, a smaller tid publishes its write in done
+    StaleRead { key: K, by: I },
+    /// read on key depends on a published write of a reset transaction
+    Cascade { key: K, by: I },
+    /// write lock on key is lost when publishing writes at done
+    LockLost { key: K },
+}
+
+/// one reset of a transaction
+#[derive(Debug, Clone)]
+pub struct ResetRecord<K, I> {
+    // none if the cause is not recorded, e.g. racing resets
+    pub cause: Option<ResetCause<K, I>>,
+    // the number of speculative rd/wr steps thrown away
+    pub wasted: usize,
+}
+
+/// reset history of a transaction
+#[derive(Debug, Clone)]
+pub struct ResetStats<K, I> {
+    pub count: usize,
+    pub records: Vec<ResetRecord<K, I>>,
+}
+
+/// a handle to query reset history, it remains valid after the protocol is moved into a service
+#[derive(Debug, Clone)]
+pub struct ResetLog<K, I: Eq + Hash>(pub(super) Arc<DashMap<I, ResetStats<K, I>>>);
+
+impl<K: Clone, I: Eq + Hash + Ord + Copy> ResetLog<K, I> {
+    pub(super) fn new() -> Self {
+        ResetLog(Arc::new(DashMap::new()))
+    }
+    pub(super) fn push(&self, tid: I, record: ResetRecord<K, I>) {
+        self.0.entry(tid)
+            .and_modify(|stats| { stats.count += 1; stats.records.push(record.clone()); })
+            .or_insert(ResetStats { count: 1, records: vec![record] });
+    }
+    /// reset history of a given transaction, none if it is never reset
+    pub fn get(&self, tid: I) -> Option<ResetStats<K, I>> {
+        self.0.get(&tid).map(|stats| stats.clone())
+    }
+    /// reset history of all transactions, sorted by tid
+    pub fn all(&self) -> Vec<(I, ResetStats<K, I>)> {
+        let mut out = self.0.iter()
+            .map(|x| (*x.key(), x.value().clone()))
+            .collect::<Vec<_>>();
+        out.sort_by_key(|(tid, _)| *tid);
+        out
+    }
+    /// total number of resets
+    pub fn count(&self) -> usize {
+        self.0.iter().map(|x| x.count).sum()
+    }
+    /// total number of wasted speculative steps
+    pub fn wasted(&self) -> usize {
+        self.0.iter().map(|x| x.records.iter().map(|r| r.wasted).sum::<usize>()).sum()
+    }
+}
@@ -1,15 +1,21 @@
+use crate::utilities::SchedPolicy;
 use dashmap::DashMap;
 use std::fmt::Debug;
 use std::hash::Hash;
 use std::marker::PhantomData;
+use std::sync::atomic::{AtomicU64, Ordering::*};
 use typing::tx::*;
 
 pub struct TPool<T: Sized + Tx<V>, V>
 where
     T::I: Ord + Debug + Eq + Hash + Copy,
 {
-    todo: DashMap<T::I, T>,
+    todo: DashMap<T::I, (u64, T)>,
     done: DashMap<T::I, T>,
+    // enqueue stamp of todo transactions
+    stamp: AtomicU64,
+    // which todo transaction to pick when the next-in-order one is absent
+    policy: Box<dyn SchedPolicy<T::I> + Send + Sync>,
     phan: PhantomData<V>,
 }
 
@@ -17,31 +23,43 @@ impl<T: Sized + Tx<V>, V> TPool<T, V>
 where
     T::I: Ord + Debug + Eq + Hash + Copy,
 {
-    pub fn new() -> Self {
+    pub fn new(policy: Box<dyn SchedPolicy<T::I> + Send + Sync>) -> Self {
         Self {
             todo: DashMap::new(),
             done: DashMap::new(),
+            stamp: AtomicU64::new(0),
+            policy,
             phan: PhantomData::<V>,
         }
     }
     pub fn put_todo(&self, txn: T) {
-        self.todo.insert(txn.id(), txn);
+        let stamp = self.stamp.fetch_add(1, Relaxed);
+        self.todo.insert(txn.id(), (stamp, txn));
     }
     pub fn put_done(&self, txn: T) {
         self.done.insert(txn.id(), txn);
     }
+    pub fn put_reset(&self, txn: T) {
+        self.policy.reset(txn.id());
+        self.put_todo(txn);
+    }
+    pub fn retire(&self, tid: T::I) {
+        self.policy.retire(tid);
+    }
     pub fn get_prog(&self, tid: T::I) -> Option<T> {
-        use rand::random;
         match self.done.remove(&tid) {
             Some((_, txn)) => return Some(txn),
             None => {}
         };
         match self.todo.remove(&tid) {
-            Some((_, txn)) => Some(txn),
-            None if random::<bool>() => None,
+            Some((_, (_, txn))) => Some(txn),
             None => {
-                let key = *(self.todo.iter().next()?.key());
-                Some(self.todo.remove(&key)?.1)
+                // shard locks of todo are released before removal
+                let key = {
+                    let mut cand = self.todo.iter().map(|x| (*x.key(), x.value().0));
+                    self.policy.pick(&mut cand)?
+                };
+                Some(self.todo.remove(&key)?.1.1)
             }
         }
     }
@@ -2,6 +2,7 @@ const NULL_WRITE: bool  = false;
 const RD_LATENCY: u64   = 10;
 const WR_LATENCY: u64   = 10;
 const NR_WORKERS: usize = 4;
+const SCHED_SEED: u64  = 42;
 
 #[test]
 fn run_u64_unif() {
@@ -15,7 +16,7 @@ fn run_u64_unif() {
     // durablility control, null control
     let dur = Null::<U64Tup, KVSpliceTx<U64Tup, U64Txn>>::new(RD_LATENCY, WR_LATENCY, NULL_WRITE);
     // concurrency control, kv sparkle in this module
-    let con = super::KVSplice::<U64Txn, U64Tup>::new();
+    let con = super::KVSplice::<U64Txn, U64Tup>::new(SCHED_SEED);
     // service, multi-thread service
     let srv = MThreadService::new(NR_WORKERS, KVSpliceTx::new, con, dur);
     preset::u64_little_bench(srv);
@@ -33,7 +34,7 @@ fn run_revm_10key() {
     // durability control, null control
     let dur = Null::<EVMU256Tup, KVSpliceTx<EVMU256Tup, REVMInterpTxn>>::new(RD_LATENCY, WR_LATENCY, NULL_WRITE);
     // concurrency control, kv sparkle in this module
-    let con = super::KVSplice::<REVMInterpTxn, EVMU256Tup>::new();
+    let con = super::KVSplice::<REVMInterpTxn, EVMU256Tup>::new(SCHED_SEED);
     // service, multi-thread service
     let srv = MThreadService::new(NR_WORKERS, KVSpliceTx::new, con, dur);
     preset::revm_10k_bench(srv);
@@ -9,7 +9,7 @@ use std::hash::Hash;
 use crate::constraint::*;
 use crate::rw::*;
 use crate::tx::*;
-use std::collections::BTreeSet;
+use std::collections::{BTreeSet, HashMap};
 
 // we use this macro to avoid writing the same trait bounds for multiple times
 macro_rules! ellipsis_trait_bag {
@@ -61,10 +61,15 @@ ellipsis_trait_bag![{T, V}
 {impl<T, V> KVSplice<T, V>}
 where ...
 {
-    pub fn new() -> Self {
+    /// schedule suspended transactions with a seeded random policy, a fixed seed replays the same picks
+    pub fn new(seed: u64) -> Self {
+        Self::with_policy(SeededRandom::new(seed))
+    }
+    /// pick a scheduling policy for suspended transactions
+    pub fn with_policy(policy: impl SchedPolicy<T::I> + Send + Sync + 'static) -> Self {
         Self {
             table: KVTable::new(),
-            tpool: TPool::new(),
+            tpool: TPool::new(Box::new(policy)),
             reset: dashmap::DashSet::new(),
             ckpts: dashmap::DashMap::new(),
             progress: Mutex::new(T::I::zero()),
@@ -97,8 +102,9 @@ where ...
         let ckpt = self.ckpts.get(&tid).unwrap_or_else(|| unreachable!());
         *txn.ax.as_mut() = Aux::new();
         txn.tx.goto(*ckpt);
-        self.tpool.put_todo(txn);
+        // clear the mark before the transaction can run again, or a mark for its next run would be lost
         self.reset.remove(&tid);
+        self.tpool.put_reset(txn);
     }
     fn submit(&self, tid: T::I) {
         let mut lid = self.last_tid.lock();
@@ -135,7 +141,8 @@ where ...
             return Ok(self.get_next())
         }
         let mut map = Vec::new();
-        let mut should_read_durable = false;
+        // keys whose versions are only in durable storage
+        let mut durable = Vec::new();
         if let Some(keys) = prp.tryc_indexer() {
             // read versions in range [progress + 1, tid]
             for key in keys {
@@ -150,7 +157,7 @@ where ...
                         txn.ax.rdset.insert(key, (val, ver));
                     },
                     Err(DepDurable) => {
-                        should_read_durable = true;
+                        durable.push(key);
                     },
                     Err(_) => unreachable!()
                 }
@@ -164,10 +171,17 @@ where ...
             "[{:<8?}    rd]       at:{:<8?}      {:?}    {:?}",
             txn.id(), self.progress(), prp, map);
         // -------------------------------------------------------------
-        // fallback to durable storage
-        if should_read_durable {
+        // fallback to durable storage, these keys are read in one batch
+        if !durable.is_empty() {
             // read versions in range [0, progress]
-            for (key, val) in dur.rd(prp).map_err(External)?.into_mapping() {
+            let prps = durable.iter()
+                .map(|key| MaybeIndexer::from_indexer(std::iter::once(key.clone())))
+                .collect();
+            let maps = dur.rd_batch(prps).map_err(External)?;
+            let mut found = maps.iter().flat_map(|map| map.into_mapping()).collect::<HashMap<_, _>>();
+            // a key absent from durable storage is read as deleted, so reading it again gives the same answer
+            for key in durable {
+                let val = found.remove(&key).flatten();
                 // put them into map iff there is no later version
                 if txn.ax.wrset.contains_key(&key) { continue }
                 if txn.ax.rdset.contains_key(&key) { continue }
@@ -265,12 +279,14 @@ where ...
             self.reset(txn);
             return Ok((self.get_next(), None));
         }
-        let mut map = vec![];
-        for (key, (val, _)) in &txn.ax.wrset {
-            if matches!(end, End::Abort) { continue }
-            map.push((key.clone(), val.clone()));
+        // a mapping may carry a single entry, so each key is a map, and they are written in one batch
+        if matches!(end, End::Ready) {
+            let maps = txn.ax.wrset.iter()
+                .map(|(key, (val, _))| Mapper::from_mapping(std::iter::once((key.clone(), val.clone()))))
+                .collect();
+            dur.wr_batch(&txn, maps).map_err(External)?;
         }
-        dur.wr(&txn, Mapper::from_mapping(map.into_iter()))
+        dur.done(&txn, end)
             .map_err(External)?;
         for (key, (_val, ispub)) in txn.ax.wrset.drain() {
             if matches!(end, End::Abort) { continue }
@@ -279,6 +295,7 @@ where ...
         }
         {*self.progress.lock() = tid;}
         self.ckpts.remove(&tid);
+        self.tpool.retire(tid);
         Ok((self.get_next(), Some(txn.cl())))
     }
     fn open(&self, mut txn: KVSpliceTx<V, T>, dur: &D)
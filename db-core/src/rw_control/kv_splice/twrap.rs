@@ -19,17 +19,24 @@ impl<I, K: Hash + Eq, V: Clone> Aux<I, K, V> {
             wrpub: false,
         }
     }
+    /// a transaction reads its own writes first, then what it has read before
     pub fn read_local(&self, key: &K) -> Option<Option<V>> {
-        if self.rdset.contains_key(key) {
-            return Some(self.rdset[key].0.clone());
-        }
         if self.wrset.contains_key(key) {
             return Some(self.wrset[key].0.clone());
         }
+        if self.rdset.contains_key(key) {
+            return Some(self.rdset[key].0.clone());
+        }
         return None;
     }
 }
 
+impl<I, K: Hash + Eq, V: Clone> Default for Aux<I, K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 pub type KVSpliceTx<V, T> = Wrap<T, Box<Aux<<T as Tx<V>>::I, <V as Id>::I, V>>>;
 
 impl<V: Clone + Id, T: Tx<V>> KVSpliceTx<V, T>
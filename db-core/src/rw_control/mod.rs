@@ -18,3 +18,6 @@ pub use kv_splice::*;
 mod two_pl;
 pub use two_pl::*;
 
+
+#[cfg(test)]
+mod check; // commit path checks of deterministic protocols
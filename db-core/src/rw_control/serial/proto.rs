@@ -55,7 +55,9 @@ where
     V: Id,
     V::I: Hash + Eq,
     T::I: Nat + Hash + Eq,
+    V: Clone,
     D: RWDurable<V, T>,
+    T::Prp: MaybeIndexer<V::I>,
     T::Map: Mapper<V::I, V>,
 {
     type Err = ();
@@ -72,7 +74,9 @@ where
         } else {
             let map = self.waiting.remove(&txn.id()).unwrap().1.into_iter();
             if matches!(end, End::Ready) {
-                dur.wr(&txn, Mapper::from_mapping(map)).map_err(|_| ())?;
+                // a mapping may carry a single entry, so each key is a map, and they are written in one batch
+                let maps = map.map(|kv| Mapper::from_mapping(std::iter::once(kv))).collect();
+                dur.wr_batch(&txn, maps).map_err(|_| ())?;
             }
             dur.done(&txn, end).map_err(|_| ())?;
             self.proceed();
@@ -85,8 +89,28 @@ where
             self.put(txn);
             Ok(self.get())
         } else {
-            let map = dur.rd(prp).map_err(|_| ())?;
-            Ok(Some(txn.rd(map)))
+            // a query without an index only sees committed writes
+            let Some(keys) = prp.tryc_indexer() else {
+                let map = dur.rd(prp).map_err(|_| ())?;
+                return Ok(Some(txn.rd(map)));
+            };
+            // a transaction reads its own writes first, other keys are read from durable storage in one batch
+            let mut map = vec![];
+            let mut durable = vec![];
+            let waiting = self.waiting.get(&txn.id()).unwrap();
+            for key in keys {
+                match waiting.get(&key) {
+                    Some(val) => if val.is_some() { map.push((key, val.clone())) },
+                    None => durable.push(MaybeIndexer::from_indexer(std::iter::once(key))),
+                }
+            }
+            drop(waiting);
+            if !durable.is_empty() {
+                for got in dur.rd_batch(durable).map_err(|_| ())? {
+                    map.extend(got.into_mapping());
+                }
+            }
+            Ok(Some(txn.rd(Mapper::from_mapping(map.into_iter()))))
         }
     }
     fn wr(&self, txn: T, map: T::Map, _dur: &D) 
@@ -0,0 +1,132 @@
+use super::*;
+use crate::hardware::aio::*;
+use crate::rw_control::Serial;
+use crate::rw_durable::check_util::*;
+use crate::rw_durable::cmd_log::replay_cmds;
+use crate::rw_durable::snapshot::Dump;
+use crate::rw_durable::wal::*;
+use crate::tx_service::m_thread::*;
+use crate::utilities::*;
+use db_test::core_workload::int::unif::*;
+use std::sync::Arc;
+use typing::rw::*;
+use typing::tx::*;
+
+const N_TXN: u64 = 2000;
+const RWAC: (u64, u64, u64, u64) = (15, 15, 1, 4);
+const VRNG: u64 = 64;
+const SEED: u64 = 1145141919810;
+
+fn workload() -> Vec<U64Txn> {
+    let mut workload = U64Gen::new(SEED, RWAC, VRNG);
+    // transaction ids start from 1
+    (0..=N_TXN).map(|_| workload.get()).filter(|txn| txn.id() != 0).collect()
+}
+
+fn run(mut service: impl TxService<U64Txn, U64Tup>) -> Vec<Option<u64>> {
+    replay_cmds::<U64Tup, _, _>(workload(), &mut service).unwrap_or_else(|_| panic!("fail to run service"))
+}
+
+fn state(dur: &impl Dump<U64Tup>) -> Vec<(u64, u64)> {
+    let mut rows = vec![];
+    dur.dump(&mut |k, U64Tup(_, v)| rows.push((*k, *v))).unwrap_or_else(|_| panic!("fail to dump"));
+    rows.sort();
+    rows
+}
+
+#[test]
+fn is_same_on_both_backends() {
+    let path = tmp_path("aio-log");
+    let dur = Wal::<U64Tup, U64Txn>::new(&path).unwrap();
+    let expect = run(MThreadService::new(4, |x| x, Serial::<U64Txn, U64Tup>::new(), dur));
+    let expect_state = state(&Wal::<U64Tup, U64Txn>::new(&path).unwrap());
+    std::fs::remove_file(&path).unwrap();
+    let aios: [Arc<dyn Aio>; 2] = [Arc::new(Blocking::new()), backend(64)];
+    for aio in aios {
+        let path = tmp_path("aio-log");
+        let dur = AioLog::<U64Tup, U64Txn>::new(&path, aio.clone()).unwrap();
+        assert!(dur.backend() == aio.name());
+        assert!(run(MThreadService::new(4, |x| x, Serial::<U64Txn, U64Tup>::new(), dur)) == expect);
+        // recovered on the other backend
+        let dur = AioLog::<U64Tup, U64Txn>::new(&path, Arc::new(Blocking::new())).unwrap();
+        assert!(state(&dur) == expect_state, "{}", aio.name());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
+
+#[test]
+fn is_hole_truncated() {
+    let path = tmp_path("aio-log");
+    let record = |k: u64, v: Option<u64>| {
+        let (mut payload, mut frame) = (vec![], vec![]);
+        put_record::<U64Tup>(&[(k, v.map(|v| U64Tup(k, v)))], &mut payload);
+        put_frame(&payload, &mut frame);
+        frame
+    };
+    let mut bytes = [record(1, Some(10)), record(2, Some(20)), record(1, None)].concat();
+    let valid = bytes.len() as u64;
+    // an append that never landed, then one that did
+    bytes.extend(vec![0u8; 40]);
+    bytes.extend(record(3, Some(30)));
+    std::fs::write(&path, &bytes).unwrap();
+    let txn = txns().get();
+    {
+        let dur = AioLog::<U64Tup, U64Txn>::new(&path, backend(8)).unwrap();
+        assert!(state(&dur) == vec![(2, 20)]);
+        assert!(std::fs::metadata(&path).unwrap().len() == valid);
+        dur.wr(&txn, U64Map(Some((4, Some(U64Tup(4, 40)))))).unwrap();
+        // served from memory while in flight, then from the log
+        let U64Map(map) = dur.rd(U64Prp(4)).unwrap();
+        assert!(map.and_then(|(_, v)| v).map(|U64Tup(_, v)| v) == Some(40));
+        dur.done(&txn, End::Ready).unwrap();
+        assert!(state(&dur) == vec![(2, 20), (4, 40)]);
+    }
+    // a torn tail
+    let len = std::fs::metadata(&path).unwrap().len();
+    std::fs::OpenOptions::new().write(true).open(&path).unwrap().set_len(len - 3).unwrap();
+    let dur = AioLog::<U64Tup, U64Txn>::new(&path, backend(8)).unwrap();
+    assert!(state(&dur) == vec![(2, 20)]);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn is_read_fetched_ahead() {
+    let path = tmp_path("aio-log");
+    let dur = AioLog::<U64Tup, U64Txn>::new(&path, backend(8)).unwrap();
+    let txn = txns().get();
+    let get = |k| {
+        let U64Map(map) = dur.rd(U64Prp(k)).unwrap();
+        map.and_then(|(_, v)| v).map(|U64Tup(_, v)| v)
+    };
+    for k in 0..4 { dur.wr(&txn, U64Map(Some((k, Some(U64Tup(k, k * 10)))))).unwrap(); }
+    // values in memory need no fetch
+    assert!(dur.submit(&U64Prp(1)).is_none());
+    dur.done(&txn, End::Ready).unwrap();
+    let tickets = (0..4).map(|k| dur.submit(&U64Prp(k)).unwrap()).collect::<Vec<_>>();
+    for ticket in tickets {
+        while !dur.arrived(ticket) { std::thread::yield_now() }
+    }
+    // a fetched value is taken once, a key written after its fetch is read again
+    dur.wr(&txn, U64Map(Some((2, Some(U64Tup(2, 99)))))).unwrap();
+    dur.done(&txn, End::Ready).unwrap();
+    assert!((0..4).map(get).collect::<Vec<_>>() == vec![Some(0), Some(10), Some(99), Some(30)]);
+    assert!(get(0) == Some(0) && get(4).is_none());
+    assert!(dur.submit(&U64Prp(4)).is_none());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn is_batch_read_in_order() {
+    let path = tmp_path("aio-log");
+    let dur = AioLog::<U64Tup, U64Txn>::new(&path, backend(8)).unwrap();
+    let txn = txns().get();
+    dur.wr_batch(&txn, (0..8).map(|k| U64Map(Some((k, Some(U64Tup(k, k * 3)))))).collect()).unwrap();
+    dur.done(&txn, End::Ready).unwrap();
+    // keys in memory, in the log and missing, in one batch
+    dur.wr(&txn, U64Map(Some((5, Some(U64Tup(5, 50)))))).unwrap();
+    let maps = dur.rd_batch([7, 5, 9, 0, 7].into_iter().map(U64Prp).collect()).unwrap();
+    let vals = maps.into_iter().map(|U64Map(map)| map.and_then(|(_, v)| v).map(|U64Tup(_, v)| v)).collect::<Vec<_>>();
+    assert!(vals == vec![Some(21), Some(50), None, Some(0), Some(21)]);
+    dur.done(&txn, End::Ready).unwrap();
+    std::fs::remove_file(&path).unwrap();
+}
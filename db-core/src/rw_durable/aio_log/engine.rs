@@ -0,0 +1,339 @@
+use super::error::*;
+use crate::hardware::aio::*;
+use crate::rw_durable::snapshot::Dump;
+use crate::rw_durable::wal::{get_record, put_record};
+use crate::utilities::*;
+use dashmap::DashMap;
+use parking_lot::{Condvar, Mutex};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{File, OpenOptions};
+use std::hash::Hash;
+use std::marker::PhantomData;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering::*};
+use std::sync::Arc;
+use typing::constraint::*;
+use typing::rw::*;
+use typing::tx::*;
+
+// the location of a record in the log
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Loc {
+    at: u64,
+    len: u32,
+}
+
+// the latest value of a key
+enum Slot<V> {
+    // its append at an offset is in flight, none for a delete
+    Fresh(u64, Option<V>),
+    // its record is in the log
+    Disk(Loc),
+}
+
+// an append of an open transaction
+struct Append<I> {
+    loc: Loc,
+    keys: Vec<I>,
+}
+
+// the log tail, appends complete out of order
+#[derive(Default)]
+struct Tail {
+    // the offset of the next record
+    next: u64,
+    // the log is written contiguously up to this offset
+    written: u64,
+    // appends in flight by offset, with their tickets and ends, any thread may complete them
+    flights: BTreeMap<u64, (Ticket, u64)>,
+    // completed regions above written, from start to end
+    done: BTreeMap<u64, u64>,
+    // whether an append failed, then written never moves again
+    broken: bool,
+}
+
+impl Tail {
+    fn complete(&mut self, at: u64, end: u64) {
+        self.done.insert(at, end);
+        while let Some(end) = self.done.remove(&self.written) {
+            self.written = end;
+        }
+    }
+}
+
+pub struct AioLog<V: Id, T: Tx<V>>
+where
+    V: Sync + Send + Clone + Codec,
+    V::I: Eq + Hash + Sync + Send + Clone,
+    T::I: Eq + Hash,
+    T::Prp: Filter<V>,
+    T::Map: Mapper<V::I, V>,
+{
+    aio: Arc<dyn Aio>,
+    file: Arc<File>,
+    keydir: DashMap<V::I, Slot<V>>,
+    tail: Mutex<Tail>,
+    // notified when written moves or the log breaks
+    moved: Condvar,
+    // the log is durable up to this offset
+    synced: AtomicU64,
+    // appends of open transactions
+    appends: DashMap<T::I, Vec<Append<V::I>>>,
+    // page reads submitted ahead of rd by ticket, and the values they fetched with their locations
+    fetches: DashMap<u64, Vec<(V::I, Loc, Ticket)>>,
+    fetched: DashMap<V::I, (Loc, V)>,
+    next: AtomicU64,
+    phant: PhantomData<T>,
+}
+
+impl<V: Id, T: Tx<V>> AioLog<V, T>
+where
+    V: Sync + Send + Clone + Codec,
+    V::I: Eq + Hash + Sync + Send + Clone,
+    T::I: Eq + Hash,
+    T::Prp: Filter<V>,
+    T::Map: Mapper<V::I, V>,
+{
+    /// open a log file (create it if absent) on an i/o backend and rebuild the keydir by scanning it
+    pub fn new(path: impl AsRef<Path>, aio: Arc<dyn Aio>) -> Result<Self, AioLogErr> {
+        let file = OpenOptions::new()
+            .read(true).write(true).create(true).truncate(false)
+            .open(path.as_ref())?;
+        let bytes = std::fs::read(path.as_ref())?;
+        let keydir = DashMap::new();
+        let mut cur = &bytes[..];
+        let valid = loop {
+            let at = (bytes.len() - cur.len()) as u64;
+            match get_frame(&mut cur) {
+                // records are never empty, an empty frame is a zero-filled hole
+                Ok(Some(payload)) if !payload.is_empty() => {
+                    let len = ((bytes.len() - cur.len()) as u64 - at) as u32;
+                    for (k, v) in get_record::<V>(payload).ok_or(AioLogErr::Corrupt(at))? {
+                        match v {
+                            Some(_) => {keydir.insert(k, Slot::Disk(Loc { at, len }));},
+                            None => {keydir.remove(&k);},
+                        }
+                    }
+                }
+                _ => break at,
+            }
+        };
+        // drop a torn tail, so new records are written right after the last valid one
+        file.set_len(valid)?;
+        file.sync_all()?;
+        Ok(AioLog {
+            aio, file: Arc::new(file), keydir,
+            tail: Mutex::new(Tail { next: valid, written: valid, ..Default::default() }),
+            moved: Condvar::new(),
+            synced: AtomicU64::new(valid),
+            appends: DashMap::new(),
+            fetches: DashMap::new(),
+            fetched: DashMap::new(),
+            next: AtomicU64::new(0),
+            phant: PhantomData,
+        })
+    }
+    /// the name of the i/o backend
+    pub fn backend(&self) -> &'static str {
+        self.aio.name()
+    }
+    // decode the value of a key from a page read at a location
+    fn value(&self, key: &V::I, loc: Loc, page: Vec<u8>) -> Result<V, AioLogErr> {
+        let corrupt = || AioLogErr::Corrupt(loc.at);
+        let payload = get_frame(&mut &page[..]).map_err(|_| corrupt())?.ok_or_else(corrupt)?;
+        get_record::<V>(payload).ok_or_else(corrupt)?
+            .into_iter().rev()
+            .find(|(k, _)| k == key)
+            .and_then(|(_, v)| v)
+            .ok_or_else(corrupt)
+    }
+    // read the current values of some keys, with all page reads in flight at once
+    fn read(&self, keys: impl Iterator<Item = V::I>) -> Result<Vec<(V::I, V)>, AioLogErr> {
+        let mut out = vec![];
+        let mut reads = vec![];
+        for k in keys {
+            let Some(slot) = self.keydir.get(&k) else { continue };
+            match &*slot {
+                Slot::Fresh(_, v) => out.extend(v.clone().map(|v| (k.clone(), v))),
+                // a value fetched ahead is taken if the key is still at the same location
+                Slot::Disk(loc) => match self.fetched.remove_if(&k, |_, (at, _)| at == loc) {
+                    Some((k, (_, v))) => out.push((k, v)),
+                    None => reads.push((k.clone(), *loc)),
+                },
+            }
+        }
+        let reads = reads.into_iter()
+            .map(|(k, loc)| Ok((k, loc, self.aio.submit(Op::Read(self.file.clone(), loc.at, loc.len as usize))?)))
+            .collect::<Result<Vec<_>, AioLogErr>>()?;
+        for (k, loc, ticket) in reads {
+            let page = self.aio.wait(ticket)?;
+            let v = self.value(&k, loc, page)?;
+            out.push((k, v));
+        }
+        Ok(out)
+    }
+    // wait until the log is written contiguously up to an offset, completing appends of any transaction on the way
+    fn written(&self, end: u64) -> Result<(), AioLogErr> {
+        let mut tail = self.tail.lock();
+        loop {
+            if tail.written >= end { return Ok(()) }
+            if tail.broken { return Err(AioLogErr::Broken) }
+            let Some(flight) = tail.flights.first_entry().filter(|flight| *flight.key() < end) else {
+                // appends below end are completed by other threads
+                self.moved.wait(&mut tail);
+                continue
+            };
+            let (at, (ticket, stop)) = (*flight.key(), flight.remove());
+            drop(tail);
+            let res = self.aio.wait(ticket);
+            tail = self.tail.lock();
+            match res {
+                Ok(_) => tail.complete(at, stop),
+                // a failed append leaves a hole, the log takes no more commits
+                Err(e) => { tail.broken = true; self.moved.notify_all(); return Err(e.into()) }
+            }
+            self.moved.notify_all();
+        }
+    }
+    // append written mappings as a record, and serve them from memory until it is written
+    fn append(&self, txn: &T, map: Vec<(V::I, Option<V>)>) -> Result<(), AioLogErr> {
+        if map.is_empty() { return Ok(()) }
+        let mut payload = vec![];
+        put_record(&map, &mut payload);
+        let mut frame = vec![];
+        put_frame(&payload, &mut frame);
+        let len = frame.len() as u32;
+        // hold the tail while updating the keydir and submitting, so the keydir order is the log order,
+        // and an append is in flights once its region is taken
+        let mut tail = self.tail.lock();
+        if tail.broken { return Err(AioLogErr::Broken) }
+        let at = tail.next;
+        let ticket = self.aio.submit(Op::Write(self.file.clone(), at, frame))?;
+        tail.next += len as u64;
+        tail.flights.insert(at, (ticket, at + len as u64));
+        let keys = map.iter().map(|(k, _)| k.clone()).collect();
+        for (k, v) in map { self.keydir.insert(k, Slot::Fresh(at, v)); }
+        drop(tail);
+        self.appends.entry(txn.id()).or_default().push(Append { loc: Loc { at, len }, keys });
+        Ok(())
+    }
+    // serve keys written by appends from the log once they are written
+    fn settle(&self, appends: Vec<Append<V::I>>) {
+        for Append { loc, keys } in appends {
+            for k in keys {
+                self.keydir.remove_if(&k, |_, slot| matches!(slot, Slot::Fresh(at, None) if *at == loc.at));
+                if let Some(mut slot) = self.keydir.get_mut(&k) {
+                    if matches!(&*slot, Slot::Fresh(at, _) if *at == loc.at) { *slot = Slot::Disk(loc) }
+                }
+            }
+        }
+    }
+}
+
+impl<V: Id, T: Tx<V>> RWDurable<V, T> for AioLog<V, T>
+where
+    V: Sync + Send + Clone + Codec,
+    V::I: Eq + Hash + Sync + Send + Clone,
+    T::I: Eq + Hash,
+    T::Prp: Filter<V> + MaybeIndexer<V::I>,
+    T::Map: Mapper<V::I, V>,
+{
+    type Err = AioLogErr;
+    fn done(&self, txn: &T, end: End) -> Result<(), Self::Err> {
+        let appends = self.appends.remove(&txn.id()).map(|(_, appends)| appends).unwrap_or_default();
+        let Some(last) = appends.iter().map(|append| append.loc.at + append.loc.len as u64).max() else { return Ok(()) };
+        // a record is durable only when every record before it is written
+        self.written(last)?;
+        self.settle(appends);
+        if matches!(end, End::Abort) || self.synced.load(Acquire) >= last { return Ok(()) }
+        // one sync covers every record written so far
+        let written = self.tail.lock().written;
+        self.aio.wait(self.aio.submit(Op::Sync(self.file.clone()))?)?;
+        self.synced.fetch_max(written, AcqRel);
+        Ok(())
+    }
+    fn open(&self, _txn: &T) -> Result<(), Self::Err> {
+        Ok(())
+    }
+    fn submit(&self, prp: &T::Prp) -> Option<u64> {
+        let mut reads = vec![];
+        for k in prp.tryc_indexer()? {
+            let loc = match self.keydir.get(&k).as_deref() {
+                Some(Slot::Disk(loc)) => *loc,
+                _ => continue,
+            };
+            if self.fetched.get(&k).is_some_and(|fetched| fetched.0 == loc) { continue }
+            // a failed submission is left to rd, which reads again and reports the error
+            let Ok(ticket) = self.aio.submit(Op::Read(self.file.clone(), loc.at, loc.len as usize)) else { continue };
+            reads.push((k, loc, ticket));
+        }
+        if reads.is_empty() { return None }
+        let ticket = self.next.fetch_add(1, Relaxed);
+        self.fetches.insert(ticket, reads);
+        Some(ticket)
+    }
+    fn arrived(&self, ticket: u64) -> bool {
+        let Some(mut reads) = self.fetches.get_mut(&ticket) else { return true };
+        reads.retain(|(k, loc, ticket)| match self.aio.poll(ticket) {
+            None => true,
+            Some(Ok(page)) => {
+                if let Ok(v) = self.value(k, *loc, page) { self.fetched.insert(k.clone(), (*loc, v)); }
+                false
+            }
+            Some(Err(_)) => false,
+        });
+        let arrived = reads.is_empty();
+        drop(reads);
+        if arrived { self.fetches.remove(&ticket); }
+        arrived
+    }
+    fn rd(&self, prp: T::Prp) -> Result<T::Map, Self::Err> {
+        let map = match prp.tryc_indexer() {
+            Some(keys) => self.read(keys)?,
+            None => {
+                let prp = prp.into_filter();
+                let keys = self.keydir.iter().map(|e| e.key().clone()).collect::<Vec<_>>();
+                self.read(keys.into_iter())?.into_iter().filter(|(_, v)| (prp)(v)).collect()
+            }
+        };
+        Ok(Mapper::from_mapping(map.into_iter().map(|(k, v)| (k, Some(v)))))
+    }
+    fn wr(&self, txn: &T, map: T::Map) -> Result<(), Self::Err> {
+        self.append(txn, map.into_mapping().collect())
+    }
+    // a batch is one append
+    fn wr_batch(&self, txn: &T, maps: Vec<T::Map>) -> Result<(), Self::Err> {
+        self.append(txn, maps.iter().flat_map(|map| map.into_mapping()).collect())
+    }
+    // page reads of all propositions in a batch are in flight at once
+    fn rd_batch(&self, prps: Vec<T::Prp>) -> Result<Vec<T::Map>, Self::Err> {
+        let keys = prps.iter()
+            .map(|prp| Some(prp.tryc_indexer()?.collect::<Vec<_>>()))
+            .collect::<Vec<_>>();
+        let read = self.read(keys.iter().flatten().flatten().cloned())?
+            .into_iter().collect::<HashMap<_, _>>();
+        prps.into_iter().zip(keys).map(|(prp, keys)| match keys {
+            Some(keys) => Ok(Mapper::from_mapping(keys.into_iter().filter_map(|k| {
+                let v = read.get(&k)?.clone();
+                Some((k, Some(v)))
+            }))),
+            None => self.rd(prp),
+        }).collect()
+    }
+}
+
+impl<V: Id, T: Tx<V>> Dump<V> for AioLog<V, T>
+where
+    V: Sync + Send + Clone + Codec,
+    V::I: Eq + Hash + Sync + Send + Clone,
+    T::I: Eq + Hash,
+    T::Prp: Filter<V>,
+    T::Map: Mapper<V::I, V>,
+{
+    type Err = AioLogErr;
+    fn dump(&self, f: &mut dyn FnMut(&V::I, &V)) -> Result<(), Self::Err> {
+        let keys = self.keydir.iter().map(|e| e.key().clone()).collect::<Vec<_>>();
+        for (k, v) in self.read(keys.into_iter())? { f(&k, &v) }
+        Ok(())
+    }
+}
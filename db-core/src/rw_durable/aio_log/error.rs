@@ -0,0 +1,15 @@
+#[derive(Debug)]
+pub enum AioLogErr {
+    // file system error
+    Io(std::io::Error),
+    // a record at a given offset cannot be decoded
+    Corrupt(u64),
+    // an earlier append failed, the log has a hole and takes no more commits
+    Broken,
+}
+
+impl From<std::io::Error> for AioLogErr {
+    fn from(e: std::io::Error) -> Self {
+        AioLogErr::Io(e)
+    }
+}
@@ -0,0 +1,21 @@
+//! ## Asynchronous Log Store
+//!
+//! > Johnson, Ryan, et al. "Aether: a scalable approach to logging." Proceedings of the VLDB Endowment 3.1-2 (2010): 681-692.
+//!
+//! A log-structured store whose appends and page reads go through an asynchronous i/o backend, io_uring or blocking pread / pwrite.
+//! A write reserves a region at the log tail and submits its record at once, so the appends of a transaction stay in flight while it goes on, and the value is served from memory until its append completes.
+//! A keydir maps each live key to the location of its record, and a read submits positioned reads for all keys of a proposition before waiting on any of them.
+//! A read may also be submitted ahead of rd: its pages are fetched while the worker parks the transaction, and rd takes a fetched value if its key has not moved since.
+//! Appends complete out of order, so a ready transaction waits until the log is written contiguously up to its last record, completing earlier appends of other transactions itself if need be, then one sync covers every record written so far.
+//! On startup, the keydir is rebuilt by scanning the log, a torn tail or a hole left by a crash is truncated.
+
+// aio log error
+mod error;
+// core aio log engine implementation
+mod engine;
+
+pub use error::*;
+pub use engine::*;
+
+#[cfg(test)]
+mod check; // backend and recovery checks
@@ -0,0 +1,80 @@
+use super::*;
+use crate::rw_durable::check_util::*;
+use db_test::core_workload::int::unif::*;
+use typing::rw::*;
+use typing::tx::*;
+
+fn put(dur: &Aries<U64Tup, U64Txn>, txn: &U64Txn, k: u64, v: Option<u64>) {
+    dur.wr(txn, U64Map(Some((k, v.map(|v| U64Tup(k, v)))))).unwrap();
+}
+
+#[test]
+fn is_committed_redone() {
+    let dir = tmp_path("aries");
+    let mut gen = txns();
+    let txn = gen.get();
+    {
+        // pages are never written back, every committed update comes from redo
+        let dur = Aries::<U64Tup, U64Txn>::new(&dir, 8, 8).unwrap();
+        dur.open(&txn).unwrap();
+        for k in 0..50 { put(&dur, &txn, k, Some(k + 1)) }
+        put(&dur, &txn, 9, None);
+        dur.done(&txn, End::Ready).unwrap();
+    }
+    let dur = Aries::<U64Tup, U64Txn>::new(&dir, 8, 8).unwrap();
+    for k in 0..50 {
+        assert!(get(&dur, k) == if k == 9 { None } else { Some(k + 1) });
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn is_stolen_undone() {
+    let dir = tmp_path("aries");
+    let mut gen = txns();
+    let (txn_1, txn_2) = (gen.get(), gen.get());
+    {
+        // a single page in buffer pool, uncommitted updates are stolen to disk on eviction
+        let dur = Aries::<U64Tup, U64Txn>::new(&dir, 8, 1).unwrap();
+        dur.open(&txn_1).unwrap();
+        for k in 0..50 { put(&dur, &txn_1, k, Some(k)) }
+        dur.done(&txn_1, End::Ready).unwrap();
+        dur.open(&txn_2).unwrap();
+        for k in 0..50 { put(&dur, &txn_2, k, Some(k * 100)) }
+        for k in 50..60 { put(&dur, &txn_2, k, Some(k)) }
+        dur.flush_pages().unwrap();
+        // crash before txn_2 is done
+    }
+    for _ in 0..2 {
+        // recovery is repeatable
+        let dur = Aries::<U64Tup, U64Txn>::new(&dir, 8, 1).unwrap();
+        for k in 0..60 {
+            assert!(get(&dur, k) == if k < 50 { Some(k) } else { None });
+        }
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn is_aborted_rolled_back() {
+    let dir = tmp_path("aries");
+    let mut gen = txns();
+    let (txn_1, txn_2) = (gen.get(), gen.get());
+    {
+        let dur = Aries::<U64Tup, U64Txn>::new(&dir, 4, 2).unwrap();
+        dur.open(&txn_1).unwrap();
+        put(&dur, &txn_1, 1, Some(10));
+        dur.done(&txn_1, End::Ready).unwrap();
+        dur.open(&txn_2).unwrap();
+        // every update of a batch is rolled back
+        dur.wr_batch(&txn_2, vec![U64Map(Some((1, Some(U64Tup(1, 20))))), U64Map(Some((2, Some(U64Tup(2, 20)))))]).unwrap();
+        assert!(dur.rd_batch(vec![U64Prp(2), U64Prp(1)]).unwrap().iter().all(|U64Map(map)| map.as_ref().is_some_and(|(_, v)| v.as_ref().is_some_and(|v| v.1 == 20))));
+        dur.done(&txn_2, End::Abort).unwrap();
+        assert!(get(&dur, 1) == Some(10));
+        assert!(get(&dur, 2).is_none());
+    }
+    let dur = Aries::<U64Tup, U64Txn>::new(&dir, 4, 2).unwrap();
+    assert!(get(&dur, 1) == Some(10));
+    assert!(get(&dur, 2).is_none());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,297 @@
+use super::error::*;
+use super::page::*;
+use super::record::*;
+use crate::rw_durable::snapshot::Dump;
+use crate::utilities::*;
+use parking_lot::Mutex;
+use std::fs::{File, OpenOptions};
+use std::hash::Hash;
+use std::io::{BufWriter, Read, Write};
+use std::marker::PhantomData;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering::*};
+use typing::constraint::*;
+use typing::rw::*;
+use typing::tx::*;
+
+// log file header: magic and the number of pages
+const MAGIC: &[u8; 8] = b"ARIESLOG";
+pub(super) const HEAD: u64 = 12;
+
+/// the tail of a log file
+pub(super) struct LogTail {
+    buf: BufWriter<File>,
+    // a cloned handle for syncing
+    file: File,
+    // the lsn of the next record
+    next: Lsn,
+    // records before this lsn are durable
+    flushed: Lsn,
+}
+
+impl LogTail {
+    /// append a record, return its lsn
+    pub fn append<V: Codec>(&mut self, rec: &LogRec<V>) -> std::io::Result<Lsn> {
+        let mut payload = vec![];
+        rec.put(&mut payload);
+        let mut frame = vec![];
+        put_frame(&payload, &mut frame);
+        self.buf.write_all(&frame)?;
+        let lsn = self.next;
+        self.next += frame.len() as u64;
+        Ok(lsn)
+    }
+    /// make sure the record at lsn is durable
+    pub fn flush(&mut self, lsn: Lsn) -> std::io::Result<()> {
+        if self.flushed > lsn { return Ok(()) }
+        self.buf.flush()?;
+        self.file.sync_data()?;
+        self.flushed = self.next;
+        Ok(())
+    }
+    /// make sure all records are durable
+    pub fn force(&mut self) -> std::io::Result<()> {
+        let last = self.next.saturating_sub(1);
+        self.flush(last)
+    }
+}
+
+// an update for rolling back: (lsn, prev lsn, page, key, before image)
+type Undo<V> = (Lsn, Lsn, u32, <V as Id>::I, Option<V>);
+
+// an active transaction
+struct Active<V: Id> {
+    xid: u64,
+    // the lsn of the last record of this transaction
+    last: Lsn,
+    // updates for rolling back
+    undo: Vec<Undo<V>>,
+}
+
+pub struct Aries<V: Id, T: Tx<V>>
+where
+    V: Sync + Clone + Codec,
+    V::I: Eq + Hash + Sync + Clone,
+    T::I: Eq + Hash,
+    T::Prp: Filter<V>,
+    T::Map: Mapper<V::I, V>,
+{
+    // lock order: active, pool, log
+    log: Mutex<LogTail>,
+    pool: Mutex<Pool<V>>,
+    active: dashmap::DashMap<T::I, Active<V>>,
+    next_xid: AtomicU64,
+    phant: PhantomData<T>,
+}
+
+impl<V: Id, T: Tx<V>> Aries<V, T>
+where
+    V: Sync + Clone + Codec,
+    V::I: Eq + Hash + Sync + Clone,
+    T::I: Eq + Hash,
+    T::Prp: Filter<V>,
+    T::Map: Mapper<V::I, V>,
+{
+    /// open (or create) an engine in a directory, then run recovery
+    /// npage: the number of pages for a new engine, an existing one keeps its own
+    /// cap: the number of pages in buffer pool
+    pub fn new(dir: impl AsRef<Path>, npage: u32, cap: usize) -> Result<Self, AriesErr> {
+        let dir = dir.as_ref().to_path_buf();
+        std::fs::create_dir_all(&dir)?;
+        let mut file = OpenOptions::new()
+            .read(true).append(true).create(true)
+            .open(dir.join("log"))?;
+        let mut bytes = vec![];
+        file.read_to_end(&mut bytes)?;
+        if bytes.is_empty() {
+            bytes.extend(MAGIC);
+            bytes.extend(npage.max(1).to_le_bytes());
+            file.write_all(&bytes)?;
+        }
+        if bytes.len() < HEAD as usize || &bytes[..8] != MAGIC {
+            return Err(AriesErr::Corrupt(0));
+        }
+        let npage = u32::from_le_bytes(bytes[8..12].try_into().unwrap());
+        let (recs, valid) = scan::<V>(&bytes)?;
+        // drop a torn tail, so new records are appended right after the last valid one
+        file.set_len(valid)?;
+        file.sync_all()?;
+        let mut log = LogTail {
+            file: file.try_clone()?,
+            buf: BufWriter::new(file),
+            next: valid,
+            flushed: valid,
+        };
+        let mut pool = Pool::new(dir, npage, cap);
+        let max_xid = super::recovery::recover(recs, &mut pool, &mut log)?;
+        Ok(Self {
+            log: Mutex::new(log),
+            pool: Mutex::new(pool),
+            active: dashmap::DashMap::new(),
+            next_xid: AtomicU64::new(max_xid + 1),
+            phant: PhantomData,
+        })
+    }
+    /// write back all dirty pages, e.g. before a planned shutdown
+    pub fn flush_pages(&self) -> Result<(), AriesErr> {
+        let mut pool = self.pool.lock();
+        let mut log = self.log.lock();
+        pool.flush_all(&mut |lsn| log.flush(lsn))
+    }
+}
+
+/// log records with their lsn
+pub(super) type Scanned<V> = Vec<(Lsn, LogRec<V>)>;
+
+/// scan all valid records in a log, return them with the length of the valid prefix
+pub(super) fn scan<V: Codec>(bytes: &[u8]) -> Result<(Scanned<V>, u64), AriesErr> {
+    let mut recs = vec![];
+    let mut cur = &bytes[HEAD as usize..];
+    loop {
+        let lsn = (bytes.len() - cur.len()) as u64;
+        match get_frame(&mut cur) {
+            Ok(Some(payload)) => recs.push((lsn, LogRec::get(payload).ok_or(AriesErr::Corrupt(lsn))?)),
+            Ok(None) | Err(_) => return Ok((recs, lsn)),
+        }
+    }
+}
+
+impl<V: Id, T: Tx<V>> RWDurable<V, T> for Aries<V, T>
+where
+    V: Sync + Clone + Codec,
+    V::I: Eq + Hash + Sync + Clone,
+    T::I: Eq + Hash,
+    T::Prp: Filter<V> + MaybeIndexer<V::I>,
+    T::Map: Mapper<V::I, V>,
+{
+    type Err = AriesErr;
+    fn open(&self, txn: &T) -> Result<(), Self::Err> {
+        let xid = self.next_xid.fetch_add(1, Relaxed);
+        let last = self.log.lock().append(&LogRec::<V>::Begin { xid })?;
+        self.active.insert(txn.id(), Active { xid, last, undo: vec![] });
+        Ok(())
+    }
+    fn done(&self, txn: &T, end: End) -> Result<(), Self::Err> {
+        let (_, Active { xid, mut last, undo }) = self.active.remove(&txn.id()).ok_or(AriesErr::NotOpen)?;
+        match end {
+            End::Ready => {
+                // no-force: only the log is forced at commit
+                let mut log = self.log.lock();
+                last = log.append(&LogRec::<V>::Commit { xid, prev: last })?;
+                log.flush(last)?;
+                log.append(&LogRec::<V>::End { xid, prev: last })?;
+            }
+            End::Abort => {
+                // roll back updates in reverse order, each undo is logged as a clr
+                let mut pool = self.pool.lock();
+                let mut log = self.log.lock();
+                last = log.append(&LogRec::<V>::Abort { xid, prev: last })?;
+                for (_lsn, prev, page, key, before) in undo.into_iter().rev() {
+                    let clr = LogRec::Clr { xid, prev: last, page, key: key.clone(), after: before.clone(), next: prev };
+                    last = log.append(&clr)?;
+                    pool.fetch(page, &mut |lsn| log.flush(lsn))?.apply(key, before, last);
+                }
+                log.append(&LogRec::<V>::End { xid, prev: last })?;
+            }
+        }
+        Ok(())
+    }
+    fn rd(&self, prp: T::Prp) -> Result<T::Map, Self::Err> {
+        read::<V, T>(&mut self.pool.lock(), &mut self.log.lock(), prp)
+    }
+    fn wr(&self, txn: &T, map: T::Map) -> Result<(), Self::Err> {
+        self.update(txn, map.into_mapping())
+    }
+    // a batch takes pool and log once for all of its reads
+    fn rd_batch(&self, prps: Vec<T::Prp>) -> Result<Vec<T::Map>, Self::Err> {
+        let mut pool = self.pool.lock();
+        let mut log = self.log.lock();
+        prps.into_iter().map(|prp| read::<V, T>(&mut pool, &mut log, prp)).collect()
+    }
+    // a batch takes pool and log once, so its update records are contiguous in the log
+    fn wr_batch(&self, txn: &T, maps: Vec<T::Map>) -> Result<(), Self::Err> {
+        self.update(txn, maps.iter().flat_map(|map| map.into_mapping()))
+    }
+}
+
+// answer a proposition from the pages, a page is fetched through the pool
+fn read<V, T>(pool: &mut Pool<V>, log: &mut LogTail, prp: T::Prp) -> Result<T::Map, AriesErr>
+where
+    V: Id + Sync + Clone + Codec,
+    T: Tx<V>,
+    V::I: Eq + Hash + Sync + Clone,
+    T::Prp: Filter<V> + MaybeIndexer<V::I>,
+    T::Map: Mapper<V::I, V>,
+{
+    let mut map = vec![];
+    if let Some(prp_iter) = prp.tryc_indexer() {
+        for i in prp_iter {
+            let id = page_of::<V>(&i, pool.npage);
+            let page = pool.fetch(id, &mut |lsn| log.flush(lsn))?;
+            if let Some(v) = page.rows.get(&i) {
+                map.push((i, Some(v.clone())));
+            }
+        }
+    } else {
+        let prp = prp.into_filter();
+        for id in 0..pool.npage {
+            let page = pool.fetch(id, &mut |lsn| log.flush(lsn))?;
+            for (i, v) in page.rows.iter() {
+                if (prp)(v) { map.push((i.clone(), Some(v.clone()))) }
+            }
+        }
+    }
+    Ok(Mapper::from_mapping(map.into_iter()))
+}
+
+impl<V: Id, T: Tx<V>> Aries<V, T>
+where
+    V: Sync + Clone + Codec,
+    V::I: Eq + Hash + Sync + Clone,
+    T::I: Eq + Hash,
+    T::Prp: Filter<V>,
+    T::Map: Mapper<V::I, V>,
+{
+    // log and apply updates of a transaction, with their before images kept for rolling back
+    fn update(&self, txn: &T, rows: impl Iterator<Item = (V::I, Option<V>)>) -> Result<(), AriesErr> {
+        let mut act = self.active.get_mut(&txn.id()).ok_or(AriesErr::NotOpen)?;
+        let mut pool = self.pool.lock();
+        let mut log = self.log.lock();
+        for (key, after) in rows {
+            let id = page_of::<V>(&key, pool.npage);
+            let page = pool.fetch(id, &mut |lsn| log.flush(lsn))?;
+            let before = page.rows.get(&key).cloned();
+            let rec = LogRec::Update {
+                xid: act.xid, prev: act.last, page: id,
+                key: key.clone(), before: before.clone(), after: after.clone(),
+            };
+            let lsn = log.append(&rec)?;
+            page.apply(key.clone(), after, lsn);
+            let prev = act.last;
+            act.undo.push((lsn, prev, id, key, before));
+            act.last = lsn;
+        }
+        Ok(())
+    }
+}
+
+impl<V: Id, T: Tx<V>> Dump<V> for Aries<V, T>
+where
+    V: Sync + Clone + Codec,
+    V::I: Eq + Hash + Sync + Clone,
+    T::I: Eq + Hash,
+    T::Prp: Filter<V>,
+    T::Map: Mapper<V::I, V>,
+{
+    type Err = AriesErr;
+    fn dump(&self, f: &mut dyn FnMut(&V::I, &V)) -> Result<(), Self::Err> {
+        // pages may hold writes of open transactions (steal), so no transaction should be running
+        let mut pool = self.pool.lock();
+        let mut log = self.log.lock();
+        for id in 0..pool.npage {
+            let page = pool.fetch(id, &mut |lsn| log.flush(lsn))?;
+            for (i, v) in page.rows.iter() { f(i, v) }
+        }
+        Ok(())
+    }
+}
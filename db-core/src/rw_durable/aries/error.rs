@@ -0,0 +1,15 @@
+#[derive(Debug)]
+pub enum AriesErr {
+    // file system error
+    Io(std::io::Error),
+    // a log record at a given lsn, or a page file with a given id, cannot be decoded
+    Corrupt(u64),
+    // a transaction writes without being opened
+    NotOpen,
+}
+
+impl From<std::io::Error> for AriesErr {
+    fn from(e: std::io::Error) -> Self {
+        AriesErr::Io(e)
+    }
+}
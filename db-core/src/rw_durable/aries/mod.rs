@@ -0,0 +1,26 @@
+//! ## ARIES
+//!
+//! > Mohan, C., et al. "ARIES: a transaction recovery method supporting fine-granularity locking and partial rollbacks using write-ahead logging." ACM Transactions on Database Systems (TODS) 17.1 (1992): 94-162.
+//!
+//! A steal/no-force durable engine. Keys are hashed into a fixed number of pages, each page carries the lsn of the last record applied to it.
+//! Dirty pages may be written back before commit (steal), and commit only forces the log (no-force).
+//! Every update is logged with its page, key, before image and after image (physiological logging).
+//! On startup, recovery runs analysis, redo and undo passes, undo writes compensation log records (CLR) so it is idempotent under repeated crashes.
+
+// aries error
+mod error;
+// log record encoding
+mod record;
+// pages and buffer pool
+mod page;
+// core aries engine implementation
+mod engine;
+// analysis, redo and undo passes
+mod recovery;
+
+pub use error::*;
+pub use record::*;
+pub use engine::*;
+
+#[cfg(test)]
+mod check; // recovery checks
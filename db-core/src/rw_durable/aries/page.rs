@@ -0,0 +1,137 @@
+use super::error::*;
+use super::record::*;
+use crate::utilities::*;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::path::{Path, PathBuf};
+use typing::constraint::*;
+
+/// a page holds all rows whose keys hash into it
+pub(super) struct Page<V: Id> {
+    // the lsn of the last record applied to this page
+    pub lsn: Lsn,
+    // the lsn of the first record that dirties this page since it is written back, none if clean
+    pub rec: Option<Lsn>,
+    // rows of this page
+    pub rows: HashMap<V::I, V>,
+    // last access time, for lru eviction
+    tick: u64,
+}
+
+impl<V: Id> Page<V>
+where
+    V::I: Eq + Hash,
+{
+    /// apply an after image of a log record
+    pub fn apply(&mut self, key: V::I, val: Option<V>, lsn: Lsn) {
+        match val {
+            Some(v) => {self.rows.insert(key, v);},
+            None => {self.rows.remove(&key);},
+        }
+        self.lsn = lsn;
+        self.rec.get_or_insert(lsn);
+    }
+}
+
+/// the page of a key, by checksum of its encoding, so it is stable across runs
+pub(super) fn page_of<V: Codec>(key: &V::I, npage: u32) -> u32 {
+    let mut buf = vec![];
+    V::put_id(key, &mut buf);
+    crc32c(&buf) % npage
+}
+
+fn page_path(dir: &Path, id: u32) -> PathBuf {
+    dir.join(format!("page.{id}"))
+}
+
+// page file: a checksummed frame of [lsn: u64] [count: u32] ([id] [value])*
+fn read_page<V: Codec>(dir: &Path, id: u32) -> Result<Page<V>, AriesErr>
+where
+    V::I: Eq + Hash,
+{
+    let mut page = Page { lsn: 0, rec: None, rows: HashMap::new(), tick: 0 };
+    let bytes = match std::fs::read(page_path(dir, id)) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(page),
+        Err(e) => return Err(e.into()),
+    };
+    let corrupt = || AriesErr::Corrupt(id as u64);
+    let buf = &mut get_frame(&mut &bytes[..]).map_err(|_| corrupt())?.ok_or_else(corrupt)?;
+    page.lsn = get_u64(buf).ok_or_else(corrupt)?;
+    for _ in 0..get_u32(buf).ok_or_else(corrupt)? {
+        let key = V::get_id(buf).ok_or_else(corrupt)?;
+        let val = V::get(buf).ok_or_else(corrupt)?;
+        page.rows.insert(key, val);
+    }
+    Ok(page)
+}
+
+// write a page to a temporary file, then rename it, so a page file is never torn
+fn write_page<V: Codec>(dir: &Path, id: u32, page: &Page<V>) -> Result<(), AriesErr> {
+    let mut payload = vec![];
+    payload.extend(page.lsn.to_le_bytes());
+    payload.extend((page.rows.len() as u32).to_le_bytes());
+    for (key, val) in &page.rows {
+        V::put_id(key, &mut payload);
+        val.put(&mut payload);
+    }
+    let mut frame = vec![];
+    put_frame(&payload, &mut frame);
+    let tmp = dir.join(format!("page.{id}.tmp"));
+    {
+        use std::io::Write;
+        let mut file = std::fs::File::create(&tmp)?;
+        file.write_all(&frame)?;
+        file.sync_data()?;
+    }
+    std::fs::rename(tmp, page_path(dir, id))?;
+    Ok(())
+}
+
+/// a buffer pool with lru eviction, dirty pages can be evicted before commit (steal)
+pub(super) struct Pool<V: Id> {
+    pub dir: PathBuf,
+    pub npage: u32,
+    cap: usize,
+    pages: HashMap<u32, Page<V>>,
+    tick: u64,
+}
+
+impl<V: Codec> Pool<V>
+where
+    V::I: Eq + Hash,
+{
+    pub fn new(dir: PathBuf, npage: u32, cap: usize) -> Self {
+        Self { dir, npage, cap: cap.max(1), pages: HashMap::new(), tick: 0 }
+    }
+    /// fetch a page, flush is called with page lsn before a dirty page is written back (write-ahead rule)
+    pub fn fetch(&mut self, id: u32, flush: &mut dyn FnMut(Lsn) -> std::io::Result<()>)
+    -> Result<&mut Page<V>, AriesErr> {
+        if !self.pages.contains_key(&id) {
+            if self.pages.len() >= self.cap {
+                let (&vic, _) = self.pages.iter().min_by_key(|(_, p)| p.tick).unwrap();
+                let page = self.pages.remove(&vic).unwrap();
+                if page.rec.is_some() {
+                    flush(page.lsn)?;
+                    write_page(&self.dir, vic, &page)?;
+                }
+            }
+            let page = read_page(&self.dir, id)?;
+            self.pages.insert(id, page);
+        }
+        self.tick += 1;
+        let page = self.pages.get_mut(&id).unwrap();
+        page.tick = self.tick;
+        Ok(page)
+    }
+    /// write back all dirty pages
+    pub fn flush_all(&mut self, flush: &mut dyn FnMut(Lsn) -> std::io::Result<()>) -> Result<(), AriesErr> {
+        for (id, page) in self.pages.iter_mut() {
+            if page.rec.is_none() { continue }
+            flush(page.lsn)?;
+            write_page(&self.dir, *id, page)?;
+            page.rec = None;
+        }
+        Ok(())
+    }
+}
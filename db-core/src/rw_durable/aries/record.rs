@@ -0,0 +1,106 @@
+use typing::constraint::*;
+
+/// a log sequence number, the byte offset of a record in the log file, 0 means none
+pub type Lsn = u64;
+
+/// physiological log records, xid is the engine-internal transaction number
+pub enum LogRec<V: Codec> {
+    Begin { xid: u64 },
+    /// an update on a key inside a page
+    Update { xid: u64, prev: Lsn, page: u32, key: V::I, before: Option<V>, after: Option<V> },
+    /// a compensation record, redo-only, undo continues from next
+    Clr { xid: u64, prev: Lsn, page: u32, key: V::I, after: Option<V>, next: Lsn },
+    Commit { xid: u64, prev: Lsn },
+    Abort { xid: u64, prev: Lsn },
+    End { xid: u64, prev: Lsn },
+}
+
+// record tags
+const TAG_BEGIN: u8 = 0;
+const TAG_UPDATE: u8 = 1;
+const TAG_CLR: u8 = 2;
+const TAG_COMMIT: u8 = 3;
+const TAG_ABORT: u8 = 4;
+const TAG_END: u8 = 5;
+
+fn put_val<V: Codec>(v: &Option<V>, buf: &mut Vec<u8>) {
+    match v {
+        None => buf.push(0),
+        Some(v) => { buf.push(1); v.put(buf) }
+    }
+}
+
+fn get_val<V: Codec>(buf: &mut &[u8]) -> Option<Option<V>> {
+    match take(buf, 1)?[0] {
+        0 => Some(None),
+        1 => Some(Some(V::get(buf)?)),
+        _ => None,
+    }
+}
+
+impl<V: Codec> LogRec<V> {
+    pub fn xid(&self) -> u64 {
+        use LogRec::*;
+        match self {
+            Begin { xid } | Update { xid, .. } | Clr { xid, .. } |
+            Commit { xid, .. } | Abort { xid, .. } | End { xid, .. } => *xid
+        }
+    }
+    /// encode a record as a log frame payload
+    pub fn put(&self, buf: &mut Vec<u8>) {
+        use LogRec::*;
+        match self {
+            Begin { xid } => {
+                buf.push(TAG_BEGIN);
+                buf.extend(xid.to_le_bytes());
+            }
+            Update { xid, prev, page, key, before, after } => {
+                buf.push(TAG_UPDATE);
+                buf.extend(xid.to_le_bytes());
+                buf.extend(prev.to_le_bytes());
+                buf.extend(page.to_le_bytes());
+                V::put_id(key, buf);
+                put_val(before, buf);
+                put_val(after, buf);
+            }
+            Clr { xid, prev, page, key, after, next } => {
+                buf.push(TAG_CLR);
+                buf.extend(xid.to_le_bytes());
+                buf.extend(prev.to_le_bytes());
+                buf.extend(page.to_le_bytes());
+                V::put_id(key, buf);
+                put_val(after, buf);
+                buf.extend(next.to_le_bytes());
+            }
+            Commit { xid, prev } | Abort { xid, prev } | End { xid, prev } => {
+                buf.push(match self { Commit { .. } => TAG_COMMIT, Abort { .. } => TAG_ABORT, _ => TAG_END });
+                buf.extend(xid.to_le_bytes());
+                buf.extend(prev.to_le_bytes());
+            }
+        }
+    }
+    /// decode a record from a log frame payload, none if it is malformed
+    pub fn get(mut buf: &[u8]) -> Option<Self> {
+        use LogRec::*;
+        let buf = &mut buf;
+        let tag = take(buf, 1)?[0];
+        let xid = get_u64(buf)?;
+        let rec = match tag {
+            TAG_BEGIN => Begin { xid },
+            TAG_UPDATE => Update {
+                xid, prev: get_u64(buf)?, page: get_u32(buf)?,
+                key: V::get_id(buf)?, before: get_val(buf)?, after: get_val(buf)?,
+            },
+            TAG_CLR => Clr {
+                xid, prev: get_u64(buf)?, page: get_u32(buf)?,
+                key: V::get_id(buf)?, after: get_val(buf)?, next: get_u64(buf)?,
+            },
+            TAG_COMMIT => Commit { xid, prev: get_u64(buf)? },
+            TAG_ABORT => Abort { xid, prev: get_u64(buf)? },
+            TAG_END => End { xid, prev: get_u64(buf)? },
+            _ => return None,
+        };
+        if !buf.is_empty() { return None }
+        Some(rec)
+    }
+}
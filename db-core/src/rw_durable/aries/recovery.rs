@@ -0,0 +1,88 @@
+use super::engine::*;
+use super::error::*;
+use super::page::*;
+use super::record::*;
+use std::collections::{BinaryHeap, HashMap};
+use std::hash::Hash;
+use typing::constraint::*;
+
+/// run analysis, redo and undo passes over log records, return the largest xid in the log
+pub(super) fn recover<V: Codec + Clone>(
+    recs: Vec<(Lsn, LogRec<V>)>,
+    pool: &mut Pool<V>,
+    log: &mut LogTail,
+) -> Result<u64, AriesErr>
+where
+    V::I: Eq + Hash + Clone,
+{
+    // ---------------------------- analysis ---------------------------- //
+    // active transaction table: xid -> (last lsn, committed)
+    let mut att = HashMap::<u64, (Lsn, bool)>::new();
+    // dirty page table: page -> recovery lsn
+    let mut dpt = HashMap::<u32, Lsn>::new();
+    let mut max_xid = 0;
+    for (lsn, rec) in &recs {
+        let xid = rec.xid();
+        max_xid = max_xid.max(xid);
+        match rec {
+            LogRec::End { .. } => { att.remove(&xid); continue }
+            LogRec::Update { page, .. } | LogRec::Clr { page, .. } => { dpt.entry(*page).or_insert(*lsn); }
+            _ => {}
+        }
+        let entry = att.entry(xid).or_insert((*lsn, false));
+        entry.0 = *lsn;
+        entry.1 |= matches!(rec, LogRec::Commit { .. });
+    }
+    // ------------------------------ redo ------------------------------ //
+    // repeat history: apply every update and clr that may be missing from its page
+    let start = dpt.values().min().copied().unwrap_or(Lsn::MAX);
+    for (lsn, rec) in &recs {
+        if *lsn < start { continue }
+        let (page, key, after) = match rec {
+            LogRec::Update { page, key, after, .. } => (page, key, after),
+            LogRec::Clr { page, key, after, .. } => (page, key, after),
+            _ => continue,
+        };
+        if dpt.get(page).is_none_or(|rec_lsn| lsn < rec_lsn) { continue }
+        let p = pool.fetch(*page, &mut |lsn| log.flush(lsn))?;
+        if p.lsn >= *lsn { continue }
+        p.apply(key.clone(), after.clone(), *lsn);
+    }
+    // ------------------------------ undo ------------------------------ //
+    // committed transactions only miss their end records
+    for (xid, (last, _)) in att.iter().filter(|(_, (_, committed))| *committed) {
+        log.append(&LogRec::<V>::End { xid: *xid, prev: *last })?;
+    }
+    att.retain(|_, (_, committed)| !*committed);
+    // roll back losers together, always undo the largest lsn first
+    let index = recs.iter().enumerate().map(|(n, (lsn, _))| (*lsn, n)).collect::<HashMap<_, _>>();
+    let mut todo = att.values().map(|(last, _)| *last).collect::<BinaryHeap<_>>();
+    while let Some(lsn) = todo.pop() {
+        let rec = &recs[index[&lsn]].1;
+        let xid = rec.xid();
+        let next = match rec {
+            LogRec::Update { prev, page, key, before, .. } => {
+                let clr = LogRec::Clr {
+                    xid, prev: att[&xid].0, page: *page,
+                    key: key.clone(), after: before.clone(), next: *prev,
+                };
+                let clr = log.append(&clr)?;
+                att.get_mut(&xid).unwrap().0 = clr;
+                pool.fetch(*page, &mut |lsn| log.flush(lsn))?.apply(key.clone(), before.clone(), clr);
+                *prev
+            }
+            // a clr is never undone, skip the updates that it compensates
+            LogRec::Clr { next, .. } => *next,
+            LogRec::Abort { prev, .. } => *prev,
+            LogRec::Begin { .. } => 0,
+            LogRec::Commit { .. } | LogRec::End { .. } => unreachable!(),
+        };
+        if next != 0 {
+            todo.push(next);
+        } else {
+            log.append(&LogRec::<V>::End { xid, prev: att[&xid].0 })?;
+        }
+    }
+    log.force()?;
+    Ok(max_xid)
+}
@@ -0,0 +1,147 @@
+use super::*;
+use crate::rw_durable::check_util::*;
+use db_test::core_workload::int::unif::*;
+use typing::rw::*;
+use typing::tx::*;
+
+fn hints(path: &std::path::Path) -> usize {
+    std::fs::read_dir(path).unwrap()
+        .filter(|e| e.as_ref().unwrap().file_name().to_string_lossy().ends_with(".hint"))
+        .count()
+}
+
+// write rows in transactions of a few keys each, so records spread over many data files
+fn commit(bc: &Bitcask<U64Tup, U64Txn>, rows: impl Iterator<Item = (u64, Option<U64Tup>)>) {
+    let txn = txns().get();
+    for chunk in rows.collect::<Vec<_>>().chunks(16) {
+        for (k, v) in chunk {
+            bc.wr(&txn, U64Map(Some((*k, *v)))).unwrap();
+        }
+        bc.done(&txn, End::Ready).unwrap();
+    }
+}
+
+#[test]
+fn is_recovered_with_hints() {
+    let path = tmp_path("bitcask");
+    {
+        // small data files, so most of them are sealed with hints
+        let bc = Bitcask::<U64Tup, U64Txn>::new(&path, 1 << 10).unwrap();
+        commit(&bc, (0..1000u64).map(|k| (k, Some(U64Tup(k, k * 2)))));
+        commit(&bc, (0..1000u64).step_by(5).map(|k| (k, None)));
+        assert!(bc.nfile() > 10);
+        assert!(hints(&path) == bc.nfile() - 1);
+    }
+    let bc = Bitcask::<U64Tup, U64Txn>::new(&path, 1 << 10).unwrap();
+    for k in 0..1000u64 {
+        assert!(get(&bc, k) == if k % 5 == 0 { None } else { Some(k * 2) });
+    }
+    drop(bc);
+    std::fs::remove_dir_all(&path).unwrap();
+}
+
+#[test]
+fn is_merged() {
+    let path = tmp_path("bitcask");
+    let txn = txns().get();
+    {
+        let bc = Bitcask::<U64Tup, U64Txn>::new(&path, 1 << 10).unwrap();
+        for round in 0..5u64 {
+            for k in 0..200u64 {
+                bc.wr(&txn, U64Map(Some((k, Some(U64Tup(k, k + round)))))).unwrap();
+            }
+        }
+        for k in (0..200u64).step_by(2) {
+            bc.wr(&txn, U64Map(Some((k, None)))).unwrap();
+        }
+        bc.done(&txn, End::Ready).unwrap();
+        bc.merge().unwrap();
+        // a merged file and an empty active file
+        assert!(bc.nfile() == 2);
+        // writes after merge override merged values
+        bc.wr(&txn, U64Map(Some((1, Some(U64Tup(1, 0)))))).unwrap();
+        bc.wr(&txn, U64Map(Some((3, None)))).unwrap();
+        bc.done(&txn, End::Ready).unwrap();
+    }
+    let bc = Bitcask::<U64Tup, U64Txn>::new(&path, 1 << 10).unwrap();
+    for k in 0..200u64 {
+        let v = match k {
+            1 => Some(0),
+            3 => None,
+            k if k % 2 == 0 => None,
+            k => Some(k + 4),
+        };
+        assert!(get(&bc, k) == v);
+    }
+    // deleted keys don't come back after another merge
+    bc.merge().unwrap();
+    drop(bc);
+    let bc = Bitcask::<U64Tup, U64Txn>::new(&path, 1 << 10).unwrap();
+    assert!(get(&bc, 3).is_none() && get(&bc, 4).is_none() && get(&bc, 1) == Some(0));
+    drop(bc);
+    std::fs::remove_dir_all(&path).unwrap();
+}
+
+#[test]
+fn is_concurrent_with_merge() {
+    let path = tmp_path("bitcask");
+    let mut gen = txns();
+    let bc = Bitcask::<U64Tup, U64Txn>::new(&path, 4 << 10).unwrap();
+    std::thread::scope(|s| {
+        for t in 0..4u64 {
+            let (bc, txn) = (&bc, gen.get());
+            s.spawn(move || for round in 0..20u64 {
+                let keys = (0..100u64).filter(|k| k % 4 == t);
+                for k in keys.clone() {
+                    bc.wr(&txn, U64Map(Some((k, Some(U64Tup(k, round)))))).unwrap();
+                }
+                bc.done(&txn, End::Ready).unwrap();
+                for k in keys {
+                    assert!(bc.get(&k).unwrap().map(|v| v.1) == Some(round));
+                }
+            });
+        }
+        s.spawn(|| for _ in 0..10 { bc.merge().unwrap() });
+    });
+    for k in 0..100u64 {
+        assert!(get(&bc, k) == Some(19));
+    }
+    drop(bc);
+    std::fs::remove_dir_all(&path).unwrap();
+}
+
+#[test]
+fn is_transaction_atomic() {
+    let path = tmp_path("bitcask");
+    let mut gen = txns();
+    let (first, second, open) = (gen.get(), gen.get(), gen.get());
+    {
+        let bc = Bitcask::<U64Tup, U64Txn>::new(&path, 1 << 20).unwrap();
+        for (txn, v) in [(&first, 1), (&second, 2)] {
+            let maps = (0..50u64).map(|k| U64Map(Some((k, Some(U64Tup(k, v)))))).collect::<Vec<_>>();
+            // a batch goes in the record of its transaction like single writes
+            if v == 1 {
+                for map in maps { bc.wr(txn, map).unwrap() }
+            } else {
+                bc.wr_batch(txn, maps).unwrap();
+            }
+            bc.done(txn, End::Ready).unwrap();
+        }
+        // writes of an unfinished transaction are neither visible nor appended
+        bc.wr(&open, U64Map(Some((50, Some(U64Tup(50, 3)))))).unwrap();
+        assert!(get(&bc, 50).is_none());
+        assert!((0..50u64).all(|k| get(&bc, k) == Some(2)));
+    }
+    // a crash tears the record of the second transaction
+    let data = std::fs::read_dir(&path).unwrap().map(|e| e.unwrap().path())
+        .find(|p| p.extension().is_some_and(|e| e == "data")).unwrap();
+    let len = std::fs::metadata(&data).unwrap().len();
+    std::fs::OpenOptions::new().write(true).open(&data).unwrap().set_len(len - 10).unwrap();
+    let bc = Bitcask::<U64Tup, U64Txn>::new(&path, 1 << 20).unwrap();
+    assert!((0..50u64).all(|k| get(&bc, k) == Some(1)));
+    assert!(get(&bc, 50).is_none());
+    let maps = bc.rd_batch(vec![U64Prp(3), U64Prp(50), U64Prp(7)]).unwrap();
+    assert!(maps.iter().map(|U64Map(map)| map.as_ref().and_then(|(_, v)| v.as_ref()).map(|v| v.1)).collect::<Vec<_>>() == vec![Some(1), None, Some(1)]);
+    drop(bc);
+    std::fs::remove_dir_all(&path).unwrap();
+}
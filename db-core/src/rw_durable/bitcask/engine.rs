@@ -0,0 +1,338 @@
+use super::error::*;
+use super::file::*;
+use crate::rw_durable::snapshot::Dump;
+use crate::rw_durable::wal::{put_record, Mapping};
+use crate::utilities::*;
+use dashmap::DashMap;
+use parking_lot::{Mutex, RwLock};
+use std::collections::{hash_map::Entry, HashMap};
+use std::fs::{File, OpenOptions};
+use std::hash::Hash;
+use std::io::{BufWriter, Write};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use typing::constraint::*;
+use typing::rw::*;
+use typing::tx::*;
+
+// the active data file, records are appended to it
+struct Active {
+    id: u64,
+    file: File,
+    size: u64,
+}
+
+pub struct Bitcask<V: Id, T: Tx<V>>
+where
+    V: Sync + Send + Clone + Codec,
+    V::I: Eq + Hash + Sync + Send + Clone,
+    T::I: Eq + Hash,
+    T::Prp: Filter<V>,
+    T::Map: Mapper<V::I, V>,
+{
+    dir: PathBuf,
+    // the size that an active file is sealed at
+    cap: u64,
+    // lock order: active, files
+    active: Mutex<Active>,
+    // read handles of all data files
+    files: RwLock<HashMap<u64, File>>,
+    // the location of the latest value of each live key
+    keydir: DashMap<V::I, Loc>,
+    // only one merge runs at a time
+    merge: Mutex<()>,
+    // written mappings of each open transaction, appended as one record when it is ready
+    pending: DashMap<T::I, Mapping<V>>,
+    phant: PhantomData<T>,
+}
+
+impl<V: Id, T: Tx<V>> Bitcask<V, T>
+where
+    V: Sync + Send + Clone + Codec,
+    V::I: Eq + Hash + Sync + Send + Clone,
+    T::I: Eq + Hash,
+    T::Prp: Filter<V>,
+    T::Map: Mapper<V::I, V>,
+{
+    /// open (or create) an engine in a directory, rebuild the keydir from hint files and data files
+    /// cap: the size in bytes that an active file is sealed at
+    pub fn new(dir: impl AsRef<Path>, cap: u64) -> Result<Self, BitcaskErr> {
+        let dir = dir.as_ref().to_path_buf();
+        std::fs::create_dir_all(&dir)?;
+        let (mut data, mut merged, mut hints) = (vec![], vec![], vec![]);
+        for entry in std::fs::read_dir(&dir)? {
+            let name = entry?.file_name().to_string_lossy().to_string();
+            // files left by an unfinished merge or sealing
+            if name.ends_with(".tmp") { std::fs::remove_file(dir.join(name))?; continue }
+            let Some((id, ext)) = name.split_once('.') else { continue };
+            let Ok(id) = id.parse::<u64>() else { continue };
+            match ext {
+                "data" => data.push(id),
+                "merged" => merged.push(id),
+                "hint" => hints.push(id),
+                _ => {}
+            }
+        }
+        // the latest merged file supersedes every file below it
+        let base = merged.iter().max().copied().unwrap_or(0);
+        for id in data.iter().chain(merged.iter()).filter(|id| **id < base) {
+            remove(&data_path(&dir, *id, false))?;
+            remove(&data_path(&dir, *id, true))?;
+        }
+        data.retain(|id| *id > base);
+        data.sort();
+        let last = data.pop();
+        // the active file may be appended to, so its hint (if any) becomes stale
+        for id in hints.into_iter().filter(|id| *id != base && !data.contains(id)) {
+            remove(&hint_path(&dir, id))?;
+        }
+        let keydir = DashMap::new();
+        let load = |hint: Hint<V>| for (k, loc) in hint {
+            match loc {
+                Some(loc) => {keydir.insert(k, loc);},
+                None => {keydir.remove(&k);},
+            }
+        };
+        let mut files = HashMap::new();
+        let sealed = (base > 0).then_some((base, true)).into_iter().chain(data.into_iter().map(|id| (id, false)));
+        for (id, merged) in sealed {
+            let path = data_path(&dir, id, merged);
+            let hint = match read_hint::<V>(&dir, id) {
+                Some(hint) => hint,
+                None => {
+                    let (hint, _) = scan::<V>(&std::fs::read(&path)?, id)?;
+                    write_hint::<V>(&dir, id, &hint)?;
+                    hint
+                }
+            };
+            load(hint);
+            files.insert(id, File::open(&path)?);
+        }
+        let id = last.unwrap_or(base + 1);
+        let file = OpenOptions::new()
+            .read(true).append(true).create(true)
+            .open(data_path(&dir, id, false))?;
+        let (hint, valid) = scan::<V>(&std::fs::read(data_path(&dir, id, false))?, id)?;
+        load(hint);
+        // drop a torn tail, so new records are appended right after the last valid one
+        file.set_len(valid)?;
+        file.sync_all()?;
+        sync_dir(&dir)?;
+        files.insert(id, file.try_clone()?);
+        Ok(Self {
+            dir, cap: cap.max(1),
+            active: Mutex::new(Active { id, file, size: valid }),
+            files: RwLock::new(files),
+            keydir, merge: Mutex::new(()),
+            pending: DashMap::new(),
+            phant: PhantomData,
+        })
+    }
+    /// the number of data files, including the active one
+    pub fn nfile(&self) -> usize {
+        self.files.read().len()
+    }
+    /// get the value of a key
+    pub fn get(&self, key: &V::I) -> Result<Option<V>, BitcaskErr> {
+        let files = self.files.read();
+        let Some(loc) = self.keydir.get(key).map(|loc| *loc) else { return Ok(None) };
+        read(&files[&loc.file], loc, key)
+    }
+    // seal the active file with a hint, and start a new one
+    fn rotate(&self, active: &mut Active, id: u64) -> Result<(), BitcaskErr> {
+        active.file.sync_data()?;
+        let (hint, _) = scan::<V>(&std::fs::read(data_path(&self.dir, active.id, false))?, active.id)?;
+        write_hint::<V>(&self.dir, active.id, &hint)?;
+        let file = OpenOptions::new()
+            .read(true).append(true).create(true)
+            .open(data_path(&self.dir, id, false))?;
+        sync_dir(&self.dir)?;
+        self.files.write().insert(id, file.try_clone()?);
+        *active = Active { id, file, size: 0 };
+        Ok(())
+    }
+    // append the writes of a transaction as one record, so a torn tail drops all or none of them
+    // every key in the record is located at the whole record
+    fn append(&self, map: Mapping<V>) -> Result<(), BitcaskErr> {
+        if map.is_empty() { return Ok(()) }
+        let mut payload = vec![];
+        put_record(&map, &mut payload);
+        let mut frame = vec![];
+        put_frame(&payload, &mut frame);
+        let mut active = self.active.lock();
+        active.file.write_all(&frame)?;
+        let loc = Loc { file: active.id, at: active.size, len: frame.len() as u32 };
+        active.size += frame.len() as u64;
+        for entry in map {
+            match entry {
+                (i, Some(_)) => {self.keydir.insert(i, loc);},
+                (i, None) => {self.keydir.remove(&i);},
+            }
+        }
+        if active.size >= self.cap {
+            let id = active.id + 1;
+            self.rotate(&mut active, id)?;
+        }
+        Ok(())
+    }
+    /// rewrite live values in sealed files into a merged file, then remove the sealed files
+    /// writes go on meanwhile, a value overwritten during merge keeps its newer location
+    pub fn merge(&self) -> Result<(), BitcaskErr> {
+        let _merge = self.merge.lock();
+        // seal the active file, and reserve an id between sealed files and the new active file
+        let out = {
+            let mut active = self.active.lock();
+            let out = active.id + 1;
+            self.rotate(&mut active, out + 1)?;
+            out
+        };
+        let live = self.keydir.iter()
+            .filter(|e| e.value().file < out)
+            .map(|e| (e.key().clone(), *e.value()))
+            .collect::<Vec<_>>();
+        let tmp = self.dir.join(format!("{out}.merged.tmp"));
+        let mut buf = BufWriter::new(File::create(&tmp)?);
+        let mut hint = vec![];
+        let mut size = 0;
+        for (k, loc) in live.iter() {
+            let v = read::<V>(&self.files.read()[&loc.file], *loc, k)?;
+            let mut payload = vec![];
+            put_record(&[(k.clone(), v)], &mut payload);
+            let mut frame = vec![];
+            put_frame(&payload, &mut frame);
+            buf.write_all(&frame)?;
+            hint.push((k.clone(), Some(Loc { file: out, at: size, len: frame.len() as u32 })));
+            size += frame.len() as u64;
+        }
+        buf.into_inner().map_err(|e| e.into_error())?.sync_data()?;
+        write_hint::<V>(&self.dir, out, &hint)?;
+        // the merged file takes effect once it is renamed
+        std::fs::rename(tmp, data_path(&self.dir, out, true))?;
+        sync_dir(&self.dir)?;
+        self.files.write().insert(out, File::open(data_path(&self.dir, out, true))?);
+        for ((k, old), (_, new)) in live.iter().zip(hint) {
+            if let Some(mut loc) = self.keydir.get_mut(k) {
+                if *loc == *old { *loc = new.unwrap() }
+            }
+        }
+        let old = {
+            let mut files = self.files.write();
+            let old = files.keys().copied().filter(|id| *id < out).collect::<Vec<_>>();
+            for id in old.iter() { files.remove(id); }
+            old
+        };
+        for id in old {
+            remove(&data_path(&self.dir, id, false))?;
+            remove(&data_path(&self.dir, id, true))?;
+            remove(&hint_path(&self.dir, id))?;
+        }
+        Ok(())
+    }
+}
+
+impl<V: Id, T: Tx<V>> Drop for Bitcask<V, T>
+where
+    V: Sync + Send + Clone + Codec,
+    V::I: Eq + Hash + Sync + Send + Clone,
+    T::I: Eq + Hash,
+    T::Prp: Filter<V>,
+    T::Map: Mapper<V::I, V>,
+{
+    fn drop(&mut self) {
+        self.active.lock().file.sync_data().unwrap_or(());
+    }
+}
+
+impl<V: Id, T: Tx<V>> RWDurable<V, T> for Bitcask<V, T>
+where
+    V: Sync + Send + Clone + Codec,
+    V::I: Eq + Hash + Sync + Send + Clone,
+    T::I: Eq + Hash,
+    T::Prp: Filter<V> + MaybeIndexer<V::I>,
+    T::Map: Mapper<V::I, V>,
+{
+    type Err = BitcaskErr;
+    fn done(&self, txn: &T, end: End) -> Result<(), Self::Err> {
+        let writes = self.pending.remove(&txn.id()).map(|(_, writes)| writes);
+        if matches!(end, End::Abort) { return Ok(()) }
+        self.append(writes.unwrap_or_default())?;
+        self.active.lock().file.sync_data()?;
+        Ok(())
+    }
+    fn open(&self, _txn: &T) -> Result<(), Self::Err> {
+        Ok(())
+    }
+    fn rd(&self, prp: T::Prp) -> Result<T::Map, Self::Err> {
+        let mut map = vec![];
+        if let Some(prp_iter) = prp.tryc_indexer() {
+            for i in prp_iter {
+                if let Some(v) = self.get(&i)? {
+                    map.push((i, Some(v)));
+                }
+            }
+        } else {
+            // without an index, every live value is read
+            let prp = prp.into_filter();
+            let files = self.files.read();
+            for e in self.keydir.iter() {
+                if let Some(v) = read::<V>(&files[&e.value().file], *e.value(), e.key())? {
+                    if (prp)(&v) { map.push((e.key().clone(), Some(v))) }
+                }
+            }
+        }
+        Ok(Mapper::from_mapping(map.into_iter()))
+    }
+    // writes are buffered until the transaction is done, and they are visible to readers after that
+    fn wr(&self, txn: &T, map: T::Map) -> Result<(), Self::Err> {
+        self.pending.entry(txn.id()).or_default().extend(map.into_mapping());
+        Ok(())
+    }
+    // point reads of a batch read and decode each record once, however many of its keys are asked for
+    fn rd_batch(&self, prps: Vec<T::Prp>) -> Result<Vec<T::Map>, Self::Err> {
+        if prps.iter().any(|prp| prp.tryc_indexer().is_none()) {
+            return prps.into_iter().map(|prp| self.rd(prp)).collect();
+        }
+        let files = self.files.read();
+        let mut records = HashMap::new();
+        let mut maps = vec![];
+        for prp in prps {
+            let mut map = vec![];
+            for i in prp.tryc_indexer().into_iter().flatten() {
+                let Some(loc) = self.keydir.get(&i).map(|loc| *loc) else { continue };
+                let rec = match records.entry(loc) {
+                    Entry::Occupied(e) => e.into_mut(),
+                    Entry::Vacant(e) => e.insert(read_record::<V>(&files[&loc.file], loc)?),
+                };
+                if let Some(v) = find(rec, loc, &i)? {
+                    map.push((i, Some(v)));
+                }
+            }
+            maps.push(Mapper::from_mapping(map.into_iter()));
+        }
+        Ok(maps)
+    }
+    // a batch joins the buffered writes, so it is appended in the record of its transaction
+    fn wr_batch(&self, txn: &T, maps: Vec<T::Map>) -> Result<(), Self::Err> {
+        self.pending.entry(txn.id()).or_default().extend(maps.iter().flat_map(|map| map.into_mapping()));
+        Ok(())
+    }
+}
+
+impl<V: Id, T: Tx<V>> Dump<V> for Bitcask<V, T>
+where
+    V: Sync + Send + Clone + Codec,
+    V::I: Eq + Hash + Sync + Send + Clone,
+    T::I: Eq + Hash,
+    T::Prp: Filter<V>,
+    T::Map: Mapper<V::I, V>,
+{
+    type Err = BitcaskErr;
+    fn dump(&self, f: &mut dyn FnMut(&V::I, &V)) -> Result<(), Self::Err> {
+        // files are not removed by a merge while they are read
+        let files = self.files.read();
+        for e in self.keydir.iter() {
+            if let Some(v) = read::<V>(&files[&e.file], *e.value(), e.key())? { f(e.key(), &v) }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,13 @@
+#[derive(Debug)]
+pub enum BitcaskErr {
+    // file system error
+    Io(std::io::Error),
+    // a record at a given offset cannot be decoded
+    Corrupt(u64),
+}
+
+impl From<std::io::Error> for BitcaskErr {
+    fn from(e: std::io::Error) -> Self {
+        BitcaskErr::Io(e)
+    }
+}
@@ -0,0 +1,125 @@
+use super::error::*;
+use crate::rw_durable::wal::{get_record, Mapping};
+use crate::utilities::*;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use typing::constraint::*;
+
+/// the location of a record in data files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(super) struct Loc {
+    pub file: u64,
+    pub at: u64,
+    pub len: u32,
+}
+
+/// keys of a data file in write order, with locations of their values, none for tombstones
+pub(super) type Hint<V> = Vec<(<V as Id>::I, Option<Loc>)>;
+
+pub(super) fn data_path(dir: &Path, id: u64, merged: bool) -> PathBuf {
+    dir.join(format!("{id}.{}", if merged { "merged" } else { "data" }))
+}
+
+pub(super) fn hint_path(dir: &Path, id: u64) -> PathBuf {
+    dir.join(format!("{id}.hint"))
+}
+
+/// remove a file, a missing one is fine
+pub(super) fn remove(path: &Path) -> std::io::Result<()> {
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+pub(super) fn sync_dir(dir: &Path) -> std::io::Result<()> {
+    File::open(dir)?.sync_all()
+}
+
+/// scan records in a data file, return its hint and the length of the valid prefix
+/// a record that fails its checksum is treated as a torn tail, and the scan stops there
+pub(super) fn scan<V: Codec>(bytes: &[u8], id: u64) -> Result<(Hint<V>, u64), BitcaskErr> {
+    let mut cur = bytes;
+    let mut hint = vec![];
+    loop {
+        let at = (bytes.len() - cur.len()) as u64;
+        match get_frame(&mut cur) {
+            Ok(Some(payload)) => {
+                let len = (bytes.len() - cur.len()) as u64 - at;
+                for (k, v) in get_record::<V>(payload).ok_or(BitcaskErr::Corrupt(at))? {
+                    hint.push((k, v.map(|_| Loc { file: id, at, len: len as u32 })));
+                }
+            }
+            Ok(None) | Err(_) => return Ok((hint, at)),
+        }
+    }
+}
+
+/// read the value of a key in a record, a record holds all writes of a transaction
+pub(super) fn read<V: Codec + Clone>(file: &File, loc: Loc, key: &V::I) -> Result<Option<V>, BitcaskErr>
+where
+    V::I: Eq,
+{
+    find(&read_record(file, loc)?, loc, key)
+}
+
+/// read all writes of a record
+pub(super) fn read_record<V: Codec>(file: &File, loc: Loc) -> Result<Mapping<V>, BitcaskErr> {
+    let mut frame = vec![0u8; loc.len as usize];
+    file.read_exact_at(&mut frame, loc.at)?;
+    let corrupt = || BitcaskErr::Corrupt(loc.at);
+    let payload = get_frame(&mut &frame[..]).map_err(|_| corrupt())?.ok_or_else(corrupt)?;
+    get_record::<V>(payload).ok_or_else(corrupt)
+}
+
+/// find the value of a key in the writes of a record at a location
+pub(super) fn find<V: Codec + Clone>(rec: &Mapping<V>, loc: Loc, key: &V::I) -> Result<Option<V>, BitcaskErr>
+where
+    V::I: Eq,
+{
+    // a key written twice in a transaction takes its last value
+    Ok(rec.iter().rev().find(|(k, _)| k == key).ok_or(BitcaskErr::Corrupt(loc.at))?.1.clone())
+}
+
+// hint file: a checksummed frame of ([id] [tag: u8] ([offset: u64] [length: u32])?)*
+pub(super) fn write_hint<V: Codec>(dir: &Path, id: u64, hint: &Hint<V>) -> std::io::Result<()> {
+    let mut payload = vec![];
+    for (k, loc) in hint {
+        V::put_id(k, &mut payload);
+        match loc {
+            None => payload.push(0),
+            Some(loc) => {
+                payload.push(1);
+                payload.extend(loc.at.to_le_bytes());
+                payload.extend(loc.len.to_le_bytes());
+            }
+        }
+    }
+    let mut frame = vec![];
+    put_frame(&payload, &mut frame);
+    let tmp = dir.join(format!("{id}.hint.tmp"));
+    {
+        let mut file = File::create(&tmp)?;
+        file.write_all(&frame)?;
+        file.sync_data()?;
+    }
+    std::fs::rename(tmp, hint_path(dir, id))
+}
+
+/// read a hint file, none if it is missing or invalid
+pub(super) fn read_hint<V: Codec>(dir: &Path, id: u64) -> Option<Hint<V>> {
+    let bytes = std::fs::read(hint_path(dir, id)).ok()?;
+    let buf = &mut get_frame(&mut &bytes[..]).ok()??;
+    let mut hint = vec![];
+    while !buf.is_empty() {
+        let k = V::get_id(buf)?;
+        let loc = match take(buf, 1)?[0] {
+            0 => None,
+            1 => Some(Loc { file: id, at: get_u64(buf)?, len: get_u32(buf)? }),
+            _ => return None,
+        };
+        hint.push((k, loc));
+    }
+    Some(hint)
+}
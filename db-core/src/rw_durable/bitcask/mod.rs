@@ -0,0 +1,21 @@
+//! ## Bitcask
+//!
+//! > Sheehy, Justin, and David Smith. "Bitcask: A log-structured hash table for fast key/value data." Basho White Paper (2010).
+//!
+//! A log-structured hash store. Writes of a transaction are buffered until it is ready, then appended to the active data file as one record, deletes are appended as tombstones (None).
+//! An in-memory keydir maps each live key to the location of the record with its latest value, so a point read takes a single positioned read.
+//! Once the active file grows past a size limit, it is sealed with a hint file that lists its keys and locations, so startup reads hints instead of data.
+//! A merge rewrites live values in sealed files into one merged file, which supersedes every file with a smaller id.
+
+// bitcask error
+mod error;
+// data files and hint files
+mod file;
+// core bitcask engine implementation
+mod engine;
+
+pub use error::*;
+pub use engine::*;
+
+#[cfg(test)]
+mod check; // recovery and merge checks
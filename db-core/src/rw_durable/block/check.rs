@@ -0,0 +1,62 @@
+use super::*;
+
+// text-like data with repeats, a long run, and random bytes
+fn samples() -> Vec<Vec<u8>> {
+    let text = (0..2000u64).flat_map(|k| format!("key{:06}=value{};", k, k % 17).into_bytes()).collect();
+    let random = (0..5000).map(|_| rand::random::<u8>()).collect();
+    let mut mixed = vec![7u8; 1000];
+    mixed.extend((0..300u16).map(|x| (x * 31 % 251) as u8));
+    mixed.extend([0u8; 300]);
+    vec![vec![], vec![1], vec![1, 1, 1, 1, 1], text, random, mixed]
+}
+
+#[test]
+fn is_round_trip() {
+    let codecs: [&dyn Compressor; 3] = [&Raw, &Rle, &Lz];
+    for comp in codecs {
+        for payload in samples() {
+            let mut buf = vec![];
+            comp.compress(&payload, &mut buf);
+            let mut out = vec![];
+            assert!(comp.decompress(&buf, payload.len(), &mut out).is_some() && out == payload);
+            let mut buf = vec![];
+            put_block(comp, &payload, &mut buf);
+            assert!(get_block(&mut &buf[..], comp).unwrap() == payload);
+            // a block of a built-in codec is read whatever the configured codec is
+            assert!(get_block(&mut &buf[..], &Raw).unwrap() == payload);
+        }
+    }
+    let text = &samples()[3];
+    let size = |comp: &dyn Compressor| {
+        let mut buf = vec![];
+        put_block(comp, text, &mut buf);
+        buf.len()
+    };
+    assert!(size(&Lz) * 2 < size(&Raw));
+}
+
+#[test]
+fn is_corruption_detected() {
+    let text = &samples()[3];
+    let mut buf = vec![];
+    put_block(&Lz, text, &mut buf);
+    for at in [0, 4, 9, buf.len() / 2, buf.len() - 1] {
+        let mut bad = buf.clone();
+        bad[at] ^= 0x10;
+        assert!(get_block(&mut &bad[..], &Lz).is_err());
+    }
+    assert!(matches!(get_block(&mut &buf[..buf.len() - 1], &Lz), Err(BlockErr::Checksum)));
+    // a block that passes its checksum, but lies about its raw length
+    let mut block = vec![2u8];
+    block.extend(5u32.to_le_bytes());
+    Lz.compress(b"abcd", &mut block);
+    let mut bad = vec![];
+    crate::utilities::put_frame(&block, &mut bad);
+    assert!(matches!(get_block(&mut &bad[..], &Lz), Err(BlockErr::Malformed)));
+    let mut block = vec![9u8];
+    block.extend(4u32.to_le_bytes());
+    block.extend(b"abcd");
+    let mut bad = vec![];
+    crate::utilities::put_frame(&block, &mut bad);
+    assert!(matches!(get_block(&mut &bad[..], &Lz), Err(BlockErr::Codec(9))));
+}
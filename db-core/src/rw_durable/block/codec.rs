@@ -0,0 +1,161 @@
+use std::fmt::Debug;
+
+/// a block compression codec
+pub trait Compressor: Debug + Send + Sync {
+    /// a tag stored with each block, 0 is raw, 1 and 2 are the built-in rle and lz
+    fn tag(&self) -> u8;
+    /// append compressed bytes of src to dst
+    fn compress(&self, src: &[u8], dst: &mut Vec<u8>);
+    /// append decompressed bytes of src to dst, None if src is malformed or does not decompress to len bytes
+    fn decompress(&self, src: &[u8], len: usize, dst: &mut Vec<u8>) -> Option<()>;
+}
+
+/// no compression
+#[derive(Debug, Clone, Copy)]
+pub struct Raw;
+
+impl Compressor for Raw {
+    fn tag(&self) -> u8 { 0 }
+    fn compress(&self, src: &[u8], dst: &mut Vec<u8>) {
+        dst.extend(src);
+    }
+    fn decompress(&self, src: &[u8], len: usize, dst: &mut Vec<u8>) -> Option<()> {
+        if src.len() != len { return None }
+        dst.extend(src);
+        Some(())
+    }
+}
+
+/// run-length encoding in the style of packbits
+///     [c < 128] then c + 1 literal bytes, or [c >= 128] then one byte repeated c - 126 times
+#[derive(Debug, Clone, Copy)]
+pub struct Rle;
+
+// the shortest run worth encoding, and the longest one in a control byte
+const RUN_MIN: usize = 3;
+const RUN_MAX: usize = 129;
+const LIT_MAX: usize = 128;
+
+impl Compressor for Rle {
+    fn tag(&self) -> u8 { 1 }
+    fn compress(&self, src: &[u8], dst: &mut Vec<u8>) {
+        let (mut i, mut lit) = (0, 0);
+        let flush = |dst: &mut Vec<u8>, lit: &[u8]| {
+            for chunk in lit.chunks(LIT_MAX) {
+                dst.push(chunk.len() as u8 - 1);
+                dst.extend(chunk);
+            }
+        };
+        while i < src.len() {
+            let run = src[i..].iter().take(RUN_MAX).take_while(|b| **b == src[i]).count();
+            if run < RUN_MIN { i += 1; continue }
+            flush(dst, &src[lit..i]);
+            dst.push((run + 126) as u8);
+            dst.push(src[i]);
+            i += run;
+            lit = i;
+        }
+        flush(dst, &src[lit..]);
+    }
+    fn decompress(&self, mut src: &[u8], len: usize, dst: &mut Vec<u8>) -> Option<()> {
+        let start = dst.len();
+        while let Some((&c, rest)) = src.split_first() {
+            if c < 128 {
+                let n = c as usize + 1;
+                dst.extend(rest.get(..n)?);
+                src = &rest[n..];
+            } else {
+                let b = *rest.first()?;
+                dst.resize(dst.len() + c as usize - 126, b);
+                src = &rest[1..];
+            }
+            if dst.len() - start > len { return None }
+        }
+        (dst.len() - start == len).then_some(())
+    }
+}
+
+/// lz77 with a hash table of 4-byte prefixes and a 64 KiB window, in the style of the lz4 block format
+///     sequences of [token: literal length << 4 | match length - 4] [more literal length] [literals] [offset: u16] [more match length]
+///     a length nibble of 15 goes on in following bytes, each adding up to 255, the last sequence has literals only
+#[derive(Debug, Clone, Copy)]
+pub struct Lz;
+
+const MATCH_MIN: usize = 4;
+const WINDOW: usize = u16::MAX as usize;
+const HASH_BITS: u32 = 12;
+
+fn hash(word: u32) -> usize {
+    (word.wrapping_mul(2654435761) >> (32 - HASH_BITS)) as usize
+}
+
+fn put_len(mut n: usize, dst: &mut Vec<u8>) {
+    while n >= 255 {
+        dst.push(255);
+        n -= 255;
+    }
+    dst.push(n as u8);
+}
+
+fn get_len(nibble: u8, src: &mut &[u8]) -> Option<usize> {
+    let mut n = nibble as usize;
+    if nibble < 15 { return Some(n) }
+    loop {
+        let (&b, rest) = src.split_first()?;
+        *src = rest;
+        n += b as usize;
+        if b < 255 { return Some(n) }
+    }
+}
+
+impl Lz {
+    fn sequence(lit: &[u8], mat: Option<(usize, usize)>, dst: &mut Vec<u8>) {
+        let (off, len) = mat.map(|(off, len)| (off, len - MATCH_MIN)).unwrap_or((0, 0));
+        dst.push((lit.len().min(15) as u8) << 4 | len.min(15) as u8);
+        if lit.len() >= 15 { put_len(lit.len() - 15, dst) }
+        dst.extend(lit);
+        let Some(_) = mat else { return };
+        dst.extend((off as u16).to_le_bytes());
+        if len >= 15 { put_len(len - 15, dst) }
+    }
+}
+
+impl Compressor for Lz {
+    fn tag(&self) -> u8 { 2 }
+    fn compress(&self, src: &[u8], dst: &mut Vec<u8>) {
+        let word = |i: usize| u32::from_le_bytes(src[i..i + 4].try_into().unwrap());
+        // the last position of each hashed prefix, plus one so zero is empty
+        let mut table = vec![0usize; 1 << HASH_BITS];
+        let (mut i, mut lit) = (0, 0);
+        while i + MATCH_MIN <= src.len() {
+            let h = hash(word(i));
+            let cand = table[h].checked_sub(1);
+            table[h] = i + 1;
+            let Some(cand) = cand.filter(|c| i - c <= WINDOW && word(*c) == word(i)) else { i += 1; continue };
+            let len = MATCH_MIN + src[i + MATCH_MIN..].iter().zip(&src[cand + MATCH_MIN..]).take_while(|(a, b)| a == b).count();
+            Lz::sequence(&src[lit..i], Some((i - cand, len)), dst);
+            i += len;
+            lit = i;
+        }
+        Lz::sequence(&src[lit..], None, dst);
+    }
+    fn decompress(&self, mut src: &[u8], len: usize, dst: &mut Vec<u8>) -> Option<()> {
+        let start = dst.len();
+        let src = &mut src;
+        loop {
+            let (&token, rest) = src.split_first()?;
+            *src = rest;
+            let n = get_len(token >> 4, src)?;
+            dst.extend(src.get(..n)?);
+            *src = &src[n..];
+            if src.is_empty() { break }
+            let off = u16::from_le_bytes(src.get(..2)?.try_into().unwrap()) as usize;
+            *src = &src[2..];
+            let n = get_len(token & 15, src)? + MATCH_MIN;
+            if off == 0 || off > dst.len() - start || dst.len() - start + n > len { return None }
+            // a match may overlap the bytes it produces, so copy byte by byte
+            for _ in 0..n { dst.push(dst[dst.len() - off]) }
+        }
+        (dst.len() - start == len).then_some(())
+    }
+}
@@ -0,0 +1,41 @@
+use super::codec::*;
+use super::error::*;
+use crate::utilities::*;
+use typing::constraint::*;
+
+// tag of a block stored as is
+const TAG_RAW: u8 = 0;
+
+/// append a block of payload to a buffer, compressed by a codec if that shrinks it
+///     frame of [tag: u8] [raw length: u32] [compressed or raw bytes]
+pub fn put_block(comp: &dyn Compressor, payload: &[u8], buf: &mut Vec<u8>) {
+    let mut block = vec![comp.tag()];
+    block.extend((payload.len() as u32).to_le_bytes());
+    comp.compress(payload, &mut block);
+    if block.len() >= payload.len() + 5 {
+        block.truncate(1);
+        block[0] = TAG_RAW;
+        block.extend((payload.len() as u32).to_le_bytes());
+        block.extend(payload);
+    }
+    put_frame(&block, buf);
+}
+
+/// take a block from the front of a buffer, verify it and return its payload
+/// blocks of built-in codecs are always readable, others only by the given codec
+pub fn get_block(buf: &mut &[u8], comp: &dyn Compressor) -> Result<Vec<u8>, BlockErr> {
+    let block = &mut get_frame(buf).map_err(|_| BlockErr::Checksum)?.ok_or(BlockErr::Checksum)?;
+    let tag = take(block, 1).ok_or(BlockErr::Malformed)?[0];
+    let len = get_u32(block).ok_or(BlockErr::Malformed)? as usize;
+    let codec: &dyn Compressor = match tag {
+        TAG_RAW if block.len() == len => return Ok(block.to_vec()),
+        TAG_RAW => return Err(BlockErr::Malformed),
+        tag if tag == comp.tag() => comp,
+        tag if tag == Rle.tag() => &Rle,
+        tag if tag == Lz.tag() => &Lz,
+        tag => return Err(BlockErr::Codec(tag)),
+    };
+    let mut payload = Vec::with_capacity(len);
+    codec.decompress(block, len, &mut payload).ok_or(BlockErr::Malformed)?;
+    Ok(payload)
+}
@@ -0,0 +1,9 @@
+#[derive(Debug)]
+pub enum BlockErr {
+    // a block is torn or its checksum does not match
+    Checksum,
+    // a block is compressed by a codec with a given tag that is not known here
+    Codec(u8),
+    // a block passes its checksum but cannot be decompressed
+    Malformed,
+}
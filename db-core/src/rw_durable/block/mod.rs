@@ -0,0 +1,23 @@
+//! ## Block Storage
+//!
+//! > Ziv, Jacob, and Abraham Lempel. "A universal algorithm for sequential data compression." IEEE Transactions on Information Theory 23.3 (1977): 337-343.
+//!
+//! A block layer for on-disk engines: a block is a checksummed (crc32c) frame of a codec tag, the raw length and the compressed bytes.
+//! The checksum covers what is stored, so it is verified before a block is decompressed, and a corrupt block is an error rather than a panic.
+//! Codecs are pluggable (Compressor), a run-length codec and an lz77 codec in the style of lz4 are built in.
+//! A block that does not shrink is stored raw, and built-in tags are always readable, so the codec of an engine can change between runs.
+//! Fixed-size pages (b+tree, copy-on-write b-tree, aries) are checksummed frames already, blocks fit variable-size storage like sstables.
+
+// block error
+mod error;
+// pluggable compression codecs
+mod codec;
+// block encoding and verification
+mod encode;
+
+pub use error::*;
+pub use codec::*;
+pub use encode::*;
+
+#[cfg(test)]
+mod check; // codec round trip and corruption checks
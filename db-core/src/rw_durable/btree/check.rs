@@ -0,0 +1,93 @@
+use super::*;
+use crate::rw_durable::check_util::*;
+use db_test::core_workload::int::unif::*;
+use std::ops::Bound;
+use typing::rw::*;
+use typing::tx::*;
+
+// keys in a pseudo random order
+fn shuffled(n: u64) -> impl Iterator<Item = u64> {
+    (0..n).map(move |i| i * 7919 % n)
+}
+
+#[test]
+fn is_sorted_and_reopened() {
+    let path = tmp_path("btree");
+    {
+        // a small pool, so pages are evicted and read back
+        let tree = BTree::<U64Tup, U64Txn>::new(&path, 8).unwrap();
+        for k in shuffled(5000) {
+            tree.put(k, Some(U64Tup(k, k * 2))).unwrap();
+        }
+        for k in (0..5000).step_by(3) {
+            tree.put(k, None).unwrap();
+        }
+        let mut keys = vec![];
+        tree.scan(Bound::Included(&100), Bound::Excluded(&200), |k, v| {
+            assert!(v.1 == k * 2);
+            keys.push(*k);
+        }).unwrap();
+        assert!(keys == (100..200).filter(|k| k % 3 != 0).collect::<Vec<_>>());
+    }
+    let tree = BTree::<U64Tup, U64Txn>::new(&path, 8).unwrap();
+    for k in 0..5000u64 {
+        let U64Map(map) = tree.rd(U64Prp(k)).unwrap();
+        let v = map.and_then(|(_, v)| v).map(|U64Tup(_, v)| v);
+        assert!(v == if k % 3 == 0 { None } else { Some(k * 2) });
+    }
+    let mut count = 0;
+    tree.scan(Bound::Unbounded, Bound::Unbounded, |_, _| count += 1).unwrap();
+    assert!(count == (0..5000).filter(|k| k % 3 != 0).count());
+    drop(tree);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn is_concurrent() {
+    let path = tmp_path("btree");
+    let tree = BTree::<U64Tup, U64Txn>::new(&path, 64).unwrap();
+    std::thread::scope(|s| {
+        for t in 0..4u64 {
+            let tree = &tree;
+            s.spawn(move || for k in shuffled(4000).filter(|k| k % 4 == t) {
+                tree.put(k, Some(U64Tup(k, k + 1))).unwrap();
+                assert!(tree.get(&k).unwrap().map(|v| v.1) == Some(k + 1));
+            });
+        }
+    });
+    let mut keys = vec![];
+    tree.scan(Bound::Unbounded, Bound::Unbounded, |k, _| keys.push(*k)).unwrap();
+    assert!(keys == (0..4000).collect::<Vec<_>>());
+    drop(tree);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn is_whole_after_failed_split() {
+    let path = tmp_path("btree");
+    // two frames can't hold a leaf and the two pages of a root split
+    let tree = BTree::<U64Tup, U64Txn>::new(&path, 2).unwrap();
+    let fail = (0..10000u64).find(|&k| tree.put(k, Some(U64Tup(k, k))).is_err()).unwrap();
+    assert!((0..fail).all(|k| tree.get(&k).unwrap().map(|v| v.1) == Some(k)));
+    assert!(tree.get(&fail).unwrap().is_none());
+    drop(tree);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn is_durable_at_commit() {
+    let path = tmp_path("btree");
+    let tree = BTree::<U64Tup, U64Txn>::new(&path, 8).unwrap();
+    let txn = txns().get();
+    tree.open(&txn).unwrap();
+    for k in 0..1000 {
+        tree.wr(&txn, U64Map(Some((k, Some(U64Tup(k, k + 1)))))).unwrap();
+    }
+    tree.done(&txn, End::Ready).unwrap();
+    // open the data file again while the tree is alive, so nothing is written back on drop
+    let copy = BTree::<U64Tup, U64Txn>::new(&path, 8).unwrap();
+    assert!((0..1000).all(|k| get(&copy, k) == Some(k + 1)));
+    drop(copy);
+    drop(tree);
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,273 @@
+use super::error::*;
+use super::node::*;
+use super::pool::*;
+use crate::rw_durable::snapshot::Dump;
+use crate::utilities::*;
+use parking_lot::RwLock;
+use std::fs::OpenOptions;
+use std::hash::Hash;
+use std::marker::PhantomData;
+use std::ops::{Bound, RangeBounds};
+use std::path::Path;
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering::*};
+use typing::constraint::*;
+use typing::rw::*;
+use typing::tx::*;
+
+// meta page: a checksummed frame of [magic] [root: u32] [number of pages: u32] [largest key size: u32]
+const MAGIC: &[u8; 8] = b"BPLUSTRE";
+
+pub struct BTree<V: Id, T: Tx<V>>
+where
+    V: Sync + Send + Clone + Codec,
+    V::I: Ord + Hash + Sync + Send + Clone,
+    T::Prp: Filter<V>,
+    T::Map: Mapper<V::I, V>,
+{
+    pool: Pool<V>,
+    // the root page, latched before any page on the way down
+    root: RwLock<u32>,
+    // the number of pages in data file, including the meta page
+    npage: AtomicU32,
+    // the largest encoded key, separators pushed up by splits are no larger than it
+    max_key: AtomicUsize,
+    phant: PhantomData<T>,
+}
+
+impl<V: Id, T: Tx<V>> BTree<V, T>
+where
+    V: Sync + Send + Clone + Codec,
+    V::I: Ord + Hash + Sync + Send + Clone,
+    T::Prp: Filter<V>,
+    T::Map: Mapper<V::I, V>,
+{
+    /// open (or create) a data file, cap is the number of frames in buffer pool
+    pub fn new(path: impl AsRef<Path>, cap: usize) -> Result<Self, BTreeErr> {
+        let file = OpenOptions::new()
+            .read(true).write(true).create(true).truncate(false)
+            .open(path)?;
+        let fresh = file.metadata()?.len() == 0;
+        let this = Self {
+            pool: Pool::new(file, cap),
+            root: RwLock::new(1),
+            npage: AtomicU32::new(2),
+            max_key: AtomicUsize::new(0),
+            phant: PhantomData,
+        };
+        if fresh {
+            drop(this.pool.create(1, Node::leaf(vec![], 0))?);
+            this.flush()?;
+            return Ok(this);
+        }
+        let mut page = vec![0u8; PAGE_SIZE];
+        this.pool.file().read_exact_at(&mut page, 0).map_err(|_| BTreeErr::Corrupt(0))?;
+        let corrupt = || BTreeErr::Corrupt(0);
+        let buf = &mut get_frame(&mut &page[..]).map_err(|_| corrupt())?.ok_or_else(corrupt)?;
+        if take(buf, MAGIC.len()) != Some(&MAGIC[..]) { return Err(corrupt()) }
+        *this.root.write() = get_u32(buf).ok_or_else(corrupt)?;
+        this.npage.store(get_u32(buf).ok_or_else(corrupt)?, Relaxed);
+        this.max_key.store(get_u32(buf).ok_or_else(corrupt)? as usize, Relaxed);
+        Ok(this)
+    }
+    /// write back all dirty pages and the meta page
+    pub fn flush(&self) -> Result<(), BTreeErr> {
+        // hold the root, so no split happens in the middle
+        let root = self.root.write();
+        self.pool.flush_all()?;
+        let mut payload = vec![];
+        payload.extend(MAGIC);
+        payload.extend(root.to_le_bytes());
+        payload.extend(self.npage.load(Relaxed).to_le_bytes());
+        payload.extend((self.max_key.load(Relaxed) as u32).to_le_bytes());
+        let mut page = vec![];
+        put_frame(&payload, &mut page);
+        page.resize(PAGE_SIZE, 0);
+        self.pool.file().write_all_at(&page, 0)?;
+        self.pool.file().sync_data()?;
+        Ok(())
+    }
+    /// get the value of a key
+    pub fn get(&self, key: &V::I) -> Result<Option<V>, BTreeErr> {
+        let root = self.root.read();
+        let mut latch = self.pool.fetch(*root)?.read();
+        drop(root);
+        loop {
+            let page = match &latch.node.body {
+                Body::Leaf { rows, .. } => {
+                    let i = rows.binary_search_by(|(k, _)| k.cmp(key));
+                    return Ok(i.ok().map(|i| rows[i].1.clone()));
+                }
+                Body::Inner { .. } => latch.node.child(key),
+            };
+            // latch the child before releasing its parent
+            latch = self.pool.fetch(page)?.read();
+        }
+    }
+    /// visit rows with keys in a range in key order
+    pub fn scan(&self, lo: Bound<&V::I>, hi: Bound<&V::I>, mut f: impl FnMut(&V::I, &V)) -> Result<(), BTreeErr> {
+        let root = self.root.read();
+        let mut latch = self.pool.fetch(*root)?.read();
+        drop(root);
+        while let Body::Inner { .. } = &latch.node.body {
+            let page = match lo {
+                Bound::Included(key) | Bound::Excluded(key) => latch.node.child(key),
+                Bound::Unbounded => latch.node.first(),
+            };
+            latch = self.pool.fetch(page)?.read();
+        }
+        // leaves are latched from left to right
+        loop {
+            let Body::Leaf { rows, next } = &latch.node.body else { unreachable!() };
+            for (k, v) in rows {
+                if !(lo, hi).contains(k) {
+                    if matches!(hi, Bound::Included(h) | Bound::Excluded(h) if k >= h) { return Ok(()) }
+                    continue;
+                }
+                f(k, v);
+            }
+            if *next == 0 { return Ok(()) }
+            latch = self.pool.fetch(*next)?.read();
+        }
+    }
+    /// insert, replace or remove (if val is none) a row
+    pub fn put(&self, key: V::I, val: Option<V>) -> Result<(), BTreeErr> {
+        // a row must fit in half a page, so a split always makes room
+        let row = match &val {
+            Some(v) => row_len(&key, v),
+            None => 0,
+        };
+        if 2 * row + NODE_HEAD > PAGE_SIZE { return Err(BTreeErr::TooLarge) }
+        let max_key = self.max_key.fetch_max(key_len::<V>(&key), Relaxed).max(key_len::<V>(&key));
+        // a node is safe if it won't split after taking a row or a separator, removal never splits
+        let slack = if val.is_some() { row.max(max_key + 4) } else { 0 };
+        let mut root = Some(self.root.write());
+        let mut stack: Vec<WriteLatch<'_, V>> = vec![];
+        let mut page = **root.as_ref().unwrap();
+        loop {
+            let latch = self.pool.fetch(page)?.write();
+            if latch.node().size + slack <= PAGE_SIZE {
+                // release all latches above a safe node
+                stack.clear();
+                root = None;
+            }
+            let child = match &latch.node().body {
+                Body::Inner { .. } => Some(latch.node().child(&key)),
+                Body::Leaf { .. } => None,
+            };
+            stack.push(latch);
+            match child {
+                Some(child) => page = child,
+                None => break,
+            }
+        }
+        let v = match val {
+            Some(v) => v,
+            None => { stack.last_mut().unwrap().node_mut().del(&key); return Ok(()) }
+        };
+        // reserve a frame for each unsafe node and for a new root, so no split fails halfway and loses rows
+        let unsafe_nodes = stack.iter().filter(|latch| latch.node().size + slack > PAGE_SIZE).count();
+        let mut frames = (0..unsafe_nodes + root.is_some() as usize)
+            .map(|_| self.pool.reserve())
+            .collect::<Result<Vec<_>, _>>()?;
+        stack.last_mut().unwrap().node_mut().put(key, v);
+        // split overflowed nodes bottom up
+        while stack.last().unwrap().node().size > PAGE_SIZE {
+            let mut node = stack.pop().unwrap();
+            let right = self.npage.fetch_add(1, Relaxed);
+            let (sep, half) = node.node_mut().split(right);
+            drop(self.pool.create_in(frames.pop().unwrap(), right, half));
+            drop(node);
+            match stack.last_mut() {
+                Some(parent) => parent.node_mut().put_child(sep, right),
+                None => {
+                    // the root splits, so it is unsafe and its latch is kept
+                    let root = root.as_mut().unwrap();
+                    let new = self.npage.fetch_add(1, Relaxed);
+                    drop(self.pool.create_in(frames.pop().unwrap(), new, Node::inner(vec![sep], vec![**root, right])));
+                    **root = new;
+                    return Ok(());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<V: Id, T: Tx<V>> Drop for BTree<V, T>
+where
+    V: Sync + Send + Clone + Codec,
+    V::I: Ord + Hash + Sync + Send + Clone,
+    T::Prp: Filter<V>,
+    T::Map: Mapper<V::I, V>,
+{
+    fn drop(&mut self) {
+        self.flush().unwrap_or(());
+    }
+}
+
+impl<V: Id, T: Tx<V>> RWDurable<V, T> for BTree<V, T>
+where
+    V: Sync + Send + Clone + Codec,
+    V::I: Ord + Hash + Sync + Send + Clone,
+    T::Prp: Filter<V> + MaybeIndexer<V::I> + MaybeRanger<V::I>,
+    T::Map: Mapper<V::I, V>,
+{
+    type Err = BTreeErr;
+    fn done(&self, _txn: &T, end: End) -> Result<(), Self::Err> {
+        // writes go to the tree at once, a commit only makes them durable
+        match end {
+            End::Ready => self.flush(),
+            End::Abort => Ok(()),
+        }
+    }
+    fn open(&self, _txn: &T) -> Result<(), Self::Err> {
+        Ok(())
+    }
+    fn rd(&self, prp: T::Prp) -> Result<T::Map, Self::Err> {
+        let mut map = vec![];
+        if let Some(prp_iter) = prp.tryc_indexer() {
+            for i in prp_iter {
+                if let Some(v) = self.get(&i)? {
+                    map.push((i, Some(v)));
+                }
+            }
+        } else {
+            // without a range, all leaves are scanned
+            let (lo, hi) = prp.tryc_ranger().unwrap_or((Bound::Unbounded, Bound::Unbounded));
+            let filter = prp.into_filter();
+            self.scan(lo.as_ref(), hi.as_ref(), |i, v| {
+                if (filter)(v) { map.push((i.clone(), Some(v.clone()))) }
+            })?;
+        }
+        Ok(Mapper::from_mapping(map.into_iter()))
+    }
+    fn wr(&self, _txn: &T, map: T::Map) -> Result<(), Self::Err> {
+        for (i, v) in map.into_mapping() {
+            self.put(i, v)?;
+        }
+        Ok(())
+    }
+    // writes of a batch go in key order, so neighbouring keys find their leaf in the pool
+    // the sort is stable, so a key written twice in a batch still takes its last value
+    fn wr_batch(&self, _txn: &T, maps: Vec<T::Map>) -> Result<(), Self::Err> {
+        let mut rows = maps.iter().flat_map(|map| map.into_mapping()).collect::<Vec<_>>();
+        rows.sort_by(|(a, _), (b, _)| a.cmp(b));
+        for (i, v) in rows {
+            self.put(i, v)?;
+        }
+        Ok(())
+    }
+}
+
+impl<V: Id, T: Tx<V>> Dump<V> for BTree<V, T>
+where
+    V: Sync + Send + Clone + Codec,
+    V::I: Ord + Hash + Sync + Send + Clone,
+    T::Prp: Filter<V>,
+    T::Map: Mapper<V::I, V>,
+{
+    type Err = BTreeErr;
+    fn dump(&self, f: &mut dyn FnMut(&V::I, &V)) -> Result<(), Self::Err> {
+        self.scan(Bound::Unbounded, Bound::Unbounded, f)
+    }
+}
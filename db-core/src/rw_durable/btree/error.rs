@@ -0,0 +1,17 @@
+#[derive(Debug)]
+pub enum BTreeErr {
+    // file system error
+    Io(std::io::Error),
+    // a page with a given id cannot be decoded
+    Corrupt(u32),
+    // a row doesn't fit into half a page
+    TooLarge,
+    // all frames in buffer pool are pinned
+    NoFrame,
+}
+
+impl From<std::io::Error> for BTreeErr {
+    fn from(e: std::io::Error) -> Self {
+        BTreeErr::Io(e)
+    }
+}
@@ -0,0 +1,25 @@
+//! ## B+Tree
+//!
+//! A page-oriented B+tree stored in a single data file of fixed-size pages, page 0 holds metadata.
+//! Pages are cached in a buffer pool with CLOCK eviction, pin counts and dirty tracking.
+//! Concurrent accesses follow latch crabbing: a latch on a parent is released once its child is latched and safe, i.e. it won't split.
+//! Leaves are linked, so a proposition with a key range (MaybeRanger) is answered by scanning the leaves in range only.
+//! Dirty pages are written back on eviction, on flush, on drop and when a transaction is ready.
+//! Writes are applied in place as they come, so an aborted transaction is not rolled back,
+//! and there is no logging, so a crash in the middle of a write back may leave a torn tree.
+
+// b+tree error
+mod error;
+// node layout in a page, shared with the copy-on-write b-tree
+pub(crate) mod node;
+// buffer pool
+mod pool;
+// core b+tree engine implementation
+mod engine;
+
+pub use error::*;
+pub use node::PAGE_SIZE;
+pub use engine::*;
+
+#[cfg(test)]
+mod check; // tree checks
@@ -0,0 +1,182 @@
+use super::error::*;
+use crate::utilities::*;
+use typing::constraint::*;
+
+/// the size of a page in data file
+pub const PAGE_SIZE: usize = 4096;
+
+// node header: frame header, node kind, entry count and a link (next leaf or first child)
+pub(crate) const NODE_HEAD: usize = FRAME_HEAD + 1 + 4 + 4;
+
+// node kinds
+const KIND_LEAF: u8 = 0;
+const KIND_INNER: u8 = 1;
+
+pub(crate) enum Body<V: Codec> {
+    /// rows in key order, and the page of the next leaf (0 if none, page 0 is metadata)
+    Leaf { rows: Vec<(V::I, V)>, next: u32 },
+    /// kids[i] holds keys in [keys[i-1], keys[i])
+    Inner { keys: Vec<V::I>, kids: Vec<u32> },
+}
+
+pub(crate) struct Node<V: Codec> {
+    pub body: Body<V>,
+    // the size of this node when encoded
+    pub size: usize,
+}
+
+/// the encoded size of a key
+pub(crate) fn key_len<V: Codec>(key: &V::I) -> usize {
+    let mut buf = vec![];
+    V::put_id(key, &mut buf);
+    buf.len()
+}
+
+/// the encoded size of a row
+pub(crate) fn row_len<V: Codec>(key: &V::I, val: &V) -> usize {
+    let mut buf = vec![];
+    V::put_id(key, &mut buf);
+    val.put(&mut buf);
+    buf.len()
+}
+
+impl<V: Codec> Node<V>
+where
+    V::I: Ord + Clone,
+{
+    pub fn leaf(rows: Vec<(V::I, V)>, next: u32) -> Self {
+        let size = NODE_HEAD + rows.iter().map(|(k, v)| row_len(k, v)).sum::<usize>();
+        Node { body: Body::Leaf { rows, next }, size }
+    }
+    pub fn inner(keys: Vec<V::I>, kids: Vec<u32>) -> Self {
+        let size = NODE_HEAD + keys.iter().map(|k| key_len::<V>(k) + 4).sum::<usize>();
+        Node { body: Body::Inner { keys, kids }, size }
+    }
+    /// the child that holds a key, only for inner nodes
+    pub fn child(&self, key: &V::I) -> u32 {
+        match &self.body {
+            Body::Inner { keys, kids } => kids[keys.partition_point(|k| k <= key)],
+            Body::Leaf { .. } => unreachable!("leaf has no child"),
+        }
+    }
+    /// replace the child that holds a key, only for inner nodes
+    pub fn set_child(&mut self, key: &V::I, page: u32) {
+        match &mut self.body {
+            Body::Inner { keys, kids } => kids[keys.partition_point(|k| k <= key)] = page,
+            Body::Leaf { .. } => unreachable!("leaf has no child"),
+        }
+    }
+    /// the child that holds the smallest key, only for inner nodes
+    pub fn first(&self) -> u32 {
+        match &self.body {
+            Body::Inner { kids, .. } => kids[0],
+            Body::Leaf { .. } => unreachable!("leaf has no child"),
+        }
+    }
+    /// insert or replace a row, only for leaves
+    pub fn put(&mut self, key: V::I, val: V) {
+        let Body::Leaf { rows, .. } = &mut self.body else { unreachable!("inner node has no row") };
+        self.size += row_len(&key, &val);
+        match rows.binary_search_by(|(k, _)| k.cmp(&key)) {
+            Ok(i) => {
+                let old = std::mem::replace(&mut rows[i].1, val);
+                self.size -= row_len(&key, &old);
+            }
+            Err(i) => rows.insert(i, (key, val)),
+        }
+    }
+    /// remove a row, only for leaves
+    pub fn del(&mut self, key: &V::I) {
+        let Body::Leaf { rows, .. } = &mut self.body else { unreachable!("inner node has no row") };
+        if let Ok(i) = rows.binary_search_by(|(k, _)| k.cmp(key)) {
+            let (k, v) = rows.remove(i);
+            self.size -= row_len(&k, &v);
+        }
+    }
+    /// add a separator and the child on its right, only for inner nodes
+    pub fn put_child(&mut self, sep: V::I, page: u32) {
+        let Body::Inner { keys, kids } = &mut self.body else { unreachable!("leaf has no child") };
+        self.size += key_len::<V>(&sep) + 4;
+        let i = keys.partition_point(|k| k <= &sep);
+        keys.insert(i, sep);
+        kids.insert(i + 1, page);
+    }
+    /// split this node in half, return the separator and the right half, which goes into a given page
+    pub fn split(&mut self, page: u32) -> (V::I, Node<V>) {
+        match &mut self.body {
+            Body::Leaf { rows, next } => {
+                let right = rows.split_off(rows.len() / 2);
+                let sep = right[0].0.clone();
+                let right = Node::leaf(right, std::mem::replace(next, page));
+                self.size -= right.size - NODE_HEAD;
+                (sep, right)
+            }
+            Body::Inner { keys, kids } => {
+                let mid = keys.len() / 2;
+                let right_keys = keys.split_off(mid + 1);
+                let sep = keys.pop().unwrap();
+                let right = Node::inner(right_keys, kids.split_off(mid + 1));
+                self.size -= right.size - NODE_HEAD + key_len::<V>(&sep) + 4;
+                (sep, right)
+            }
+        }
+    }
+    /// encode this node into a page
+    ///     [kind: u8] [count: u32] [next leaf] ([id] [value])*
+    ///     [kind: u8] [count: u32] [first child] ([id] [child])*
+    pub fn encode(&self) -> Vec<u8> {
+        let mut payload = vec![];
+        match &self.body {
+            Body::Leaf { rows, next } => {
+                payload.push(KIND_LEAF);
+                payload.extend((rows.len() as u32).to_le_bytes());
+                payload.extend(next.to_le_bytes());
+                for (k, v) in rows {
+                    V::put_id(k, &mut payload);
+                    v.put(&mut payload);
+                }
+            }
+            Body::Inner { keys, kids } => {
+                payload.push(KIND_INNER);
+                payload.extend((keys.len() as u32).to_le_bytes());
+                payload.extend(kids[0].to_le_bytes());
+                for (k, c) in keys.iter().zip(&kids[1..]) {
+                    V::put_id(k, &mut payload);
+                    payload.extend(c.to_le_bytes());
+                }
+            }
+        }
+        let mut page = Vec::with_capacity(PAGE_SIZE);
+        put_frame(&payload, &mut page);
+        debug_assert!(page.len() <= PAGE_SIZE);
+        page.resize(PAGE_SIZE, 0);
+        page
+    }
+    /// decode a page with a given id
+    pub fn decode(page: &[u8], id: u32) -> Result<Self, BTreeErr> {
+        let corrupt = || BTreeErr::Corrupt(id);
+        let buf = &mut get_frame(&mut &page[..]).map_err(|_| corrupt())?.ok_or_else(corrupt)?;
+        let kind = take(buf, 1).ok_or_else(corrupt)?[0];
+        let count = get_u32(buf).ok_or_else(corrupt)?;
+        let link = get_u32(buf).ok_or_else(corrupt)?;
+        match kind {
+            KIND_LEAF => {
+                let mut rows = vec![];
+                for _ in 0..count {
+                    let k = V::get_id(buf).ok_or_else(corrupt)?;
+                    rows.push((k, V::get(buf).ok_or_else(corrupt)?));
+                }
+                Ok(Node::leaf(rows, link))
+            }
+            KIND_INNER => {
+                let (mut keys, mut kids) = (vec![], vec![link]);
+                for _ in 0..count {
+                    keys.push(V::get_id(buf).ok_or_else(corrupt)?);
+                    kids.push(get_u32(buf).ok_or_else(corrupt)?);
+                }
+                Ok(Node::inner(keys, kids))
+            }
+            _ => Err(corrupt()),
+        }
+    }
+}
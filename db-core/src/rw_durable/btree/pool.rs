@@ -0,0 +1,205 @@
+use super::error::*;
+use super::node::*;
+use crate::utilities::PosIo;
+use parking_lot::{Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::collections::HashMap;
+use std::fs::File;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering::*};
+use typing::constraint::*;
+
+// a frame holds no page
+const NONE: u32 = u32::MAX;
+
+// a frame in buffer pool, its latch (node lock) is only taken while it is pinned
+struct Frame<V: Codec> {
+    // the page in this frame, only changed with the page table locked
+    page: AtomicU32,
+    pins: AtomicUsize,
+    dirty: AtomicBool,
+    // reference bit for clock eviction
+    refd: AtomicBool,
+    node: RwLock<Node<V>>,
+}
+
+/// a pinned frame, it cannot be evicted until unpinned on drop
+pub(super) struct Pinned<'a, V: Codec> {
+    frame: &'a Frame<V>,
+}
+
+impl<V: Codec> Drop for Pinned<'_, V> {
+    fn drop(&mut self) {
+        self.frame.pins.fetch_sub(1, Release);
+    }
+}
+
+/// a read latch on a pinned page
+pub(super) struct ReadLatch<'a, V: Codec> {
+    // fields are dropped in order, so the latch is released before unpinning
+    pub node: RwLockReadGuard<'a, Node<V>>,
+    _pin: Pinned<'a, V>,
+}
+
+/// a write latch on a pinned page
+pub(super) struct WriteLatch<'a, V: Codec> {
+    node: RwLockWriteGuard<'a, Node<V>>,
+    pin: Pinned<'a, V>,
+}
+
+impl<'a, V: Codec> Pinned<'a, V> {
+    pub fn read(self) -> ReadLatch<'a, V> {
+        ReadLatch { node: self.frame.node.read(), _pin: self }
+    }
+    pub fn write(self) -> WriteLatch<'a, V> {
+        WriteLatch { node: self.frame.node.write(), pin: self }
+    }
+}
+
+impl<V: Codec> WriteLatch<'_, V> {
+    pub fn node(&self) -> &Node<V> {
+        &self.node
+    }
+    /// borrow the node for modification, mark the page dirty
+    pub fn node_mut(&mut self) -> &mut Node<V> {
+        self.pin.frame.dirty.store(true, Release);
+        &mut self.node
+    }
+}
+
+/// a pinned frame without a page, a page created in it needs no eviction
+pub(super) struct Reserved<'a, V: Codec> {
+    slot: usize,
+    pin: Pinned<'a, V>,
+}
+
+/// a buffer pool over a data file
+pub(super) struct Pool<V: Codec> {
+    file: File,
+    frames: Vec<Frame<V>>,
+    // page table and clock hand, pinning and eviction are serialized on it
+    table: Mutex<(HashMap<u32, usize>, usize)>,
+}
+
+impl<V: Codec> Pool<V>
+where
+    V::I: Ord + Clone,
+{
+    pub fn new(file: File, cap: usize) -> Self {
+        let frames = (0..cap.max(1)).map(|_| Frame {
+            page: AtomicU32::new(NONE),
+            pins: AtomicUsize::new(0),
+            dirty: AtomicBool::new(false),
+            refd: AtomicBool::new(false),
+            node: RwLock::new(Node::leaf(vec![], 0)),
+        }).collect();
+        Self { file, frames, table: Mutex::new((HashMap::new(), 0)) }
+    }
+    pub fn file(&self) -> &File {
+        &self.file
+    }
+    fn read_page(&self, page: u32) -> Result<Node<V>, BTreeErr> {
+        let mut buf = vec![0u8; PAGE_SIZE];
+        self.file.read_exact_at(&mut buf, page as u64 * PAGE_SIZE as u64)
+            .map_err(|_| BTreeErr::Corrupt(page))?;
+        Node::decode(&buf, page)
+    }
+    fn write_page(&self, page: u32, node: &Node<V>) -> Result<(), BTreeErr> {
+        self.file.write_all_at(&node.encode(), page as u64 * PAGE_SIZE as u64)?;
+        Ok(())
+    }
+    // find a free frame with clock, write back a dirty victim
+    fn evict(&self, table: &mut (HashMap<u32, usize>, usize)) -> Result<usize, BTreeErr> {
+        let n = self.frames.len();
+        for _ in 0..2 * n + 1 {
+            let i = table.1;
+            table.1 = (i + 1) % n;
+            let frame = &self.frames[i];
+            if frame.pins.load(Acquire) > 0 { continue }
+            let page = frame.page.load(Relaxed);
+            if page == NONE { return Ok(i) }
+            if frame.refd.swap(false, Relaxed) { continue }
+            // an unpinned frame is not latched by others
+            if frame.dirty.swap(false, AcqRel) {
+                self.write_page(page, &frame.node.read())?;
+            }
+            table.0.remove(&page);
+            frame.page.store(NONE, Relaxed);
+            return Ok(i);
+        }
+        Err(BTreeErr::NoFrame)
+    }
+    // put a page into a frame and pin it
+    fn install(&self, page: u32, node: Option<Node<V>>) -> Result<Pinned<'_, V>, BTreeErr> {
+        let mut table = self.table.lock();
+        if let Some(&i) = table.0.get(&page) {
+            let frame = &self.frames[i];
+            frame.pins.fetch_add(1, Acquire);
+            frame.refd.store(true, Relaxed);
+            if let Some(node) = node {
+                *frame.node.write() = node;
+                frame.dirty.store(true, Release);
+            }
+            return Ok(Pinned { frame });
+        }
+        let i = self.evict(&mut table)?;
+        let frame = &self.frames[i];
+        let dirty = node.is_some();
+        *frame.node.write() = match node {
+            Some(node) => node,
+            None => self.read_page(page)?,
+        };
+        frame.dirty.store(dirty, Release);
+        frame.refd.store(true, Relaxed);
+        frame.pins.store(1, Release);
+        frame.page.store(page, Relaxed);
+        table.0.insert(page, i);
+        Ok(Pinned { frame })
+    }
+    /// pin a page, read it from data file if it is not cached
+    pub fn fetch(&self, page: u32) -> Result<Pinned<'_, V>, BTreeErr> {
+        self.install(page, None)
+    }
+    /// pin a new page with a given node, it is dirty until written back
+    pub fn create(&self, page: u32, node: Node<V>) -> Result<Pinned<'_, V>, BTreeErr> {
+        self.install(page, Some(node))
+    }
+    /// pin a free frame ahead of creating a page, eviction happens here, so it is the only step that fails
+    pub fn reserve(&self) -> Result<Reserved<'_, V>, BTreeErr> {
+        let mut table = self.table.lock();
+        let slot = self.evict(&mut table)?;
+        let frame = &self.frames[slot];
+        frame.pins.store(1, Release);
+        Ok(Reserved { slot, pin: Pinned { frame } })
+    }
+    /// create a new page with a given node in a reserved frame, it is dirty until written back
+    pub fn create_in<'a>(&'a self, reserved: Reserved<'a, V>, page: u32, node: Node<V>) -> Pinned<'a, V> {
+        let mut table = self.table.lock();
+        let Reserved { slot, pin } = reserved;
+        *pin.frame.node.write() = node;
+        pin.frame.dirty.store(true, Release);
+        pin.frame.refd.store(true, Relaxed);
+        pin.frame.page.store(page, Relaxed);
+        table.0.insert(page, slot);
+        pin
+    }
+    /// write back all dirty pages
+    pub fn flush_all(&self) -> Result<(), BTreeErr> {
+        // pin cached pages first, so latching doesn't happen with the page table locked
+        let pinned = {
+            let table = self.table.lock();
+            table.0.values().map(|&i| {
+                self.frames[i].pins.fetch_add(1, Acquire);
+                Pinned { frame: &self.frames[i] }
+            }).collect::<Vec<_>>()
+        };
+        for pin in pinned {
+            let frame = pin.frame;
+            let latch = pin.read();
+            if frame.dirty.swap(false, AcqRel) {
+                let page = frame.page.load(Relaxed);
+                self.write_page(page, &latch.node)?;
+            }
+        }
+        self.file.sync_data()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,19 @@
+use db_test::core_workload::int::unif::*;
+use std::path::PathBuf;
+use typing::rw::*;
+
+/// a fresh path in the temporary directory, tagged for telling files apart
+pub fn tmp_path(tag: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("db-core-{tag}-{}", rand::random::<u64>()))
+}
+
+/// read the value of a key, none if it is absent or deleted
+pub fn get<D: RWDurable<U64Tup, U64Txn> + ?Sized>(dur: &D, k: u64) -> Option<u64> {
+    let U64Map(map) = dur.rd(U64Prp(k)).unwrap_or_else(|_| panic!("fail to read"));
+    map.and_then(|(_, v)| v).map(|U64Tup(_, v)| v)
+}
+
+/// transactions for driving an engine by hand, each one may read, write and commit
+pub fn txns() -> U64Gen {
+    U64Gen::new(0, (1, 1, 1, 1), 100)
+}
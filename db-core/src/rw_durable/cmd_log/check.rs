@@ -0,0 +1,99 @@
+use super::*;
+use crate::rw_control::{KVSparkle, KVSparkleTx, Serial};
+use crate::rw_durable::check_util::*;
+use crate::rw_durable::null::*;
+use crate::rw_durable::wal::*;
+use crate::tx_service::m_thread::*;
+use db_test::core_workload::int::unif::*;
+use typing::rw::*;
+use typing::tx::*;
+
+const N_TXN: u64 = 2000;
+const RWAC: (u64, u64, u64, u64) = (15, 15, 1, 4);
+const VRNG: u64 = 64;
+const SEED: u64 = 1145141919810;
+
+fn workload() -> Vec<U64Txn> {
+    let mut workload = U64Gen::new(SEED, RWAC, VRNG);
+    // transaction ids start from 1
+    (0..=N_TXN).map(|_| workload.get()).filter(|txn| txn.id() != 0).collect()
+}
+
+fn run(mut service: impl TxService<U64Txn, U64Tup>, txns: Vec<U64Txn>) -> Vec<Option<u64>> {
+    replay_cmds::<U64Tup, _, _>(txns, &mut service).unwrap_or_else(|_| panic!("fail to run service"))
+}
+
+fn state(path: &std::path::Path) -> Vec<Option<u64>> {
+    let wal = Wal::<U64Tup, U64Txn>::new(path).unwrap();
+    (0..VRNG).map(|k| {
+        let U64Map(map) = wal.rd(U64Prp(k)).unwrap();
+        map.and_then(|(_, v)| v).map(|U64Tup(_, v)| v)
+    }).collect()
+}
+
+#[test]
+fn is_replay_same_as_value_log() {
+    let (val_path, cmd_path, rep_path) = (tmp_path("val"), tmp_path("cmd"), tmp_path("rep"));
+    // value logging run
+    let dur = Wal::<U64Tup, U64Txn>::new(&val_path).unwrap();
+    let val_out = run(MThreadService::new(4, |x| x, Serial::<U64Txn, U64Tup>::new(), dur), workload());
+    // command logging run, values are kept in memory only
+    let dur = CmdLog::<U64Tup, U64Txn, _>::new(&cmd_path, Null::<U64Tup, U64Txn>::new(0, 0, false)).unwrap();
+    let cmd_out = run(MThreadService::new(4, |x| x, Serial::<U64Txn, U64Tup>::new(), dur), workload());
+    assert!(val_out == cmd_out);
+    // recovery, replay commands through the same protocol
+    let cmds = read_cmds::<U64Tup, U64Txn>(&cmd_path).unwrap();
+    assert!(cmds.len() == N_TXN as usize);
+    let dur = Wal::<U64Tup, U64Txn>::new(&rep_path).unwrap();
+    let rep_out = run(MThreadService::new(4, |x| x, Serial::<U64Txn, U64Tup>::new(), dur), cmds);
+    assert!(val_out == rep_out);
+    assert!(state(&val_path) == state(&rep_path));
+    for path in [val_path, cmd_path, rep_path] {
+        std::fs::remove_file(path).unwrap();
+    }
+}
+
+#[test]
+fn is_sparkle_replay_same_as_value_log() {
+    type Txn = KVSparkleTx<U64Tup, U64Txn>;
+    let (val_path, cmd_path, rep_path) = (tmp_path("val"), tmp_path("cmd"), tmp_path("rep"));
+    let sparkle = || KVSparkle::<U64Txn, U64Tup>::new(SEED);
+    let state = |path| {
+        let wal = Wal::<U64Tup, Txn>::new(path).unwrap();
+        (0..VRNG).map(|k| {
+            let U64Map(map) = wal.rd(U64Prp(k)).unwrap();
+            map.and_then(|(_, v)| v).map(|U64Tup(_, v)| v)
+        }).collect::<Vec<_>>()
+    };
+    let dur = Wal::<U64Tup, Txn>::new(&val_path).unwrap();
+    let val_out = run(MThreadService::new(4, KVSparkleTx::new, sparkle(), dur), workload());
+    let dur = CmdLog::<U64Tup, Txn, _>::new(&cmd_path, Null::<U64Tup, Txn>::new(0, 0, false)).unwrap();
+    let cmd_out = run(MThreadService::new(4, KVSparkleTx::new, sparkle(), dur), workload());
+    assert!(val_out == cmd_out);
+    // commands are logged without auxiliary information, so they decode as plain transactions
+    let cmds = read_cmds::<U64Tup, U64Txn>(&cmd_path).unwrap();
+    assert!(cmds.len() == N_TXN as usize);
+    let dur = Wal::<U64Tup, Txn>::new(&rep_path).unwrap();
+    let rep_out = run(MThreadService::new(4, KVSparkleTx::new, sparkle(), dur), cmds);
+    assert!(val_out == rep_out);
+    assert!(state(&val_path) == state(&rep_path));
+    for path in [val_path, cmd_path, rep_path] {
+        std::fs::remove_file(path).unwrap();
+    }
+}
+
+#[test]
+fn is_gap_dropped() {
+    let path = tmp_path("gap");
+    let dur = CmdLog::<U64Tup, U64Txn, _>::new(&path, Null::<U64Tup, U64Txn>::new(0, 0, false)).unwrap();
+    for txn in workload().into_iter().take(5) {
+        // transaction 3 is lost, so transactions after it are never done
+        if txn.id() == 3 { continue }
+        dur.open(&txn).unwrap();
+    }
+    dur.done(&workload()[0], End::Ready).unwrap();
+    let cmds = read_cmds::<U64Tup, U64Txn>(&path).unwrap();
+    assert!(cmds.iter().map(|txn| txn.id()).collect::<Vec<_>>() == vec![1, 2]);
+    assert!(cmds[1] == workload()[1]);
+    std::fs::remove_file(path).unwrap();
+}
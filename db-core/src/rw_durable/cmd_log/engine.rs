@@ -0,0 +1,114 @@
+use super::error::*;
+use crate::rw_durable::snapshot::Dump;
+use crate::utilities::*;
+use parking_lot::Mutex;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Read, Write};
+use std::marker::PhantomData;
+use std::path::Path;
+use typing::constraint::*;
+use typing::rw::*;
+use typing::tx::*;
+
+pub struct CmdLog<V, T, D>
+where
+    T: Tx<V> + TxCmd,
+    D: RWDurable<V, T>,
+{
+    inner: D,
+    // buffered appending handle
+    buf: Mutex<BufWriter<File>>,
+    // a cloned handle, so syncing doesn't block appending
+    file: File,
+    phant: PhantomData<(V, T)>,
+}
+
+impl<V, T, D> CmdLog<V, T, D>
+where
+    T: Tx<V> + TxCmd,
+    D: RWDurable<V, T>,
+{
+    /// open a command log file (create it if absent) in front of an inner engine
+    /// a torn tail is dropped, commands are appended after the existing ones
+    /// the inner engine is not rebuilt here, recover it with read_cmds and replay_cmds beforehand
+    pub fn new(path: impl AsRef<Path>, inner: D) -> Result<Self, CmdLogErr<D::Err>> {
+        let mut file = OpenOptions::new()
+            .read(true).append(true).create(true)
+            .open(path)?;
+        let mut bytes = vec![];
+        file.read_to_end(&mut bytes)?;
+        // drop a torn tail, so new commands are appended right after the last valid one
+        let mut cur = &bytes[..];
+        while let Ok(Some(_)) = get_frame(&mut cur) {}
+        file.set_len((bytes.len() - cur.len()) as u64)?;
+        file.sync_all()?;
+        Ok(Self {
+            inner,
+            file: file.try_clone()?,
+            buf: Mutex::new(BufWriter::new(file)),
+            phant: PhantomData,
+        })
+    }
+    /// the inner durable engine
+    pub fn inner(&self) -> &D {
+        &self.inner
+    }
+}
+
+impl<V, T, D> RWDurable<V, T> for CmdLog<V, T, D>
+where
+    T: Tx<V> + TxCmd,
+    D: RWDurable<V, T>,
+{
+    type Err = CmdLogErr<D::Err>;
+    fn open(&self, txn: &T) -> Result<(), Self::Err> {
+        let mut payload = vec![];
+        txn.put_cmd(&mut payload);
+        let mut frame = vec![];
+        put_frame(&payload, &mut frame);
+        self.buf.lock().write_all(&frame)?;
+        self.inner.open(txn).map_err(CmdLogErr::Inner)
+    }
+    fn done(&self, txn: &T, end: End) -> Result<(), Self::Err> {
+        self.inner.done(txn, end).map_err(CmdLogErr::Inner)?;
+        // an aborted transaction is also replayed, it aborts again deterministically
+        self.buf.lock().flush()?;
+        self.file.sync_data()?;
+        Ok(())
+    }
+    fn rd(&self, prp: T::Prp) -> Result<T::Map, Self::Err> {
+        self.inner.rd(prp).map_err(CmdLogErr::Inner)
+    }
+    fn wr(&self, txn: &T, map: T::Map) -> Result<(), Self::Err> {
+        self.inner.wr(txn, map).map_err(CmdLogErr::Inner)
+    }
+    fn rd_batch(&self, prps: Vec<T::Prp>) -> Result<Vec<T::Map>, Self::Err> {
+        self.inner.rd_batch(prps).map_err(CmdLogErr::Inner)
+    }
+    fn wr_batch(&self, txn: &T, maps: Vec<T::Map>) -> Result<(), Self::Err> {
+        self.inner.wr_batch(txn, maps).map_err(CmdLogErr::Inner)
+    }
+    fn epoch(&self) -> u64 {
+        self.inner.epoch()
+    }
+    fn durable_epoch(&self) -> u64 {
+        self.inner.durable_epoch()
+    }
+    fn submit(&self, prp: &T::Prp) -> Option<u64> {
+        self.inner.submit(prp)
+    }
+    fn arrived(&self, ticket: u64) -> bool {
+        self.inner.arrived(ticket)
+    }
+}
+
+impl<V: Id, T, D> Dump<V> for CmdLog<V, T, D>
+where
+    T: Tx<V> + TxCmd,
+    D: RWDurable<V, T> + Dump<V>,
+{
+    type Err = <D as Dump<V>>::Err;
+    fn dump(&self, f: &mut dyn FnMut(&V::I, &V)) -> Result<(), Self::Err> {
+        self.inner.dump(f)
+    }
+}
@@ -0,0 +1,15 @@
+#[derive(Debug)]
+pub enum CmdLogErr<E> {
+    // file system error
+    Io(std::io::Error),
+    // inner durable engine error
+    Inner(E),
+    // a command at a given offset passes its checksum but cannot be decoded
+    Corrupt(u64),
+}
+
+impl<E> From<std::io::Error> for CmdLogErr<E> {
+    fn from(e: std::io::Error) -> Self {
+        CmdLogErr::Io(e)
+    }
+}
@@ -0,0 +1,21 @@
+//! ## Command Log
+//!
+//! A durable mode for deterministic protocols (e.g. Serial, KVSparkle), which commit transactions in id order.
+//! Instead of written values, only transaction inputs are logged, one checksummed frame per transaction when it is opened.
+//! The log is synced when a transaction is done, so all commands up to a done transaction are durable.
+//! Reads and writes go to an inner durable engine, which usually holds state in memory only.
+//! To recover, decode the gap-free prefix of logged commands and replay them through the same protocol.
+
+// command log error
+mod error;
+// core command log engine implementation
+mod engine;
+// reading and replaying commands
+mod replay;
+
+pub use error::*;
+pub use engine::*;
+pub use replay::*;
+
+#[cfg(test)]
+mod check; // replay checks
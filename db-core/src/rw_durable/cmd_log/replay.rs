@@ -0,0 +1,64 @@
+use super::error::*;
+use crate::utilities::*;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::path::Path;
+use typing::constraint::*;
+use typing::tx::*;
+
+/// read commands from a log file, keep the longest prefix of consecutive ids from the first one
+/// a transaction after a gap is never done, because transactions are done in id order
+pub fn read_cmds<V, T>(path: impl AsRef<Path>) -> Result<Vec<T>, CmdLogErr<()>>
+where
+    T: Tx<V> + TxCmd,
+    T::I: Nat + Eq + Hash,
+{
+    let bytes = std::fs::read(path)?;
+    let mut cur = &bytes[..];
+    let mut cmds = HashMap::new();
+    loop {
+        let offset = (bytes.len() - cur.len()) as u64;
+        match get_frame(&mut cur) {
+            Ok(Some(mut payload)) => {
+                let txn = T::get_cmd(&mut payload).ok_or(CmdLogErr::Corrupt(offset))?;
+                cmds.insert(txn.id(), txn);
+            }
+            Ok(None) | Err(_) => break,
+        }
+    }
+    let mut prefix = vec![];
+    let mut next = T::I::zero().succ();
+    while let Some(txn) = cmds.remove(&next) {
+        next = next.succ();
+        prefix.push(txn);
+    }
+    Ok(prefix)
+}
+
+/// replay commands through a service in id order, return their outputs
+/// the service should run the same protocol as the logging run, over the inner engine rather than the same command log
+pub fn replay_cmds<V, T, S>(cmds: Vec<T>, service: &mut S) -> Result<Vec<Option<T::Out>>, S::Err>
+where
+    T: Tx<V>,
+    T::I: Clone,
+    S: TxService<T, V>,
+{
+    service.start()?;
+    let ids = cmds.iter().map(|txn| txn.id()).collect::<Vec<_>>();
+    for txn in cmds {
+        service.put(txn)?;
+    }
+    let mut output = vec![];
+    for i in ids {
+        // an error means the transaction is pending
+        loop {
+            if let Ok(x) = service.get(i.clone()) {
+                output.push(x);
+                break;
+            }
+            std::thread::yield_now();
+        }
+    }
+    service.close()?;
+    Ok(output)
+}
@@ -0,0 +1,109 @@
+use super::*;
+use crate::rw_durable::btree::PAGE_SIZE;
+use crate::rw_durable::check_util::*;
+use crate::utilities::PosIo;
+use db_test::core_workload::int::unif::*;
+use std::ops::Bound;
+use typing::rw::*;
+use typing::tx::*;
+
+// keys in a pseudo random order
+fn shuffled(n: u64) -> impl Iterator<Item = u64> {
+    (0..n).map(move |i| i * 7919 % n)
+}
+
+fn rows(keys: impl Iterator<Item = u64>, f: impl Fn(u64) -> Option<U64Tup>) -> Vec<(u64, Option<U64Tup>)> {
+    keys.map(|k| (k, f(k))).collect()
+}
+
+#[test]
+fn is_committed_and_reopened() {
+    let path = tmp_path("cow-btree");
+    {
+        let tree = CowBTree::<U64Tup, U64Txn>::new(&path).unwrap();
+        for batch in rows(shuffled(5000), |k| Some(U64Tup(k, k * 2))).chunks(500) {
+            tree.commit(batch.to_vec()).unwrap();
+        }
+        tree.commit(rows((0..5000).step_by(3), |_| None)).unwrap();
+    }
+    let tree = CowBTree::<U64Tup, U64Txn>::new(&path).unwrap();
+    let snap = tree.snapshot();
+    for k in 0..5000u64 {
+        assert!(snap.get(&k).unwrap().map(|v| v.1) == if k % 3 == 0 { None } else { Some(k * 2) });
+    }
+    let mut keys = vec![];
+    snap.scan(Bound::Excluded(&100), Bound::Included(&200), |k, _| keys.push(*k)).unwrap();
+    assert!(keys == (101..=200).filter(|k| k % 3 != 0).collect::<Vec<_>>());
+    drop(snap);
+    drop(tree);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn is_snapshot_isolated() {
+    let path = tmp_path("cow-btree");
+    let tree = CowBTree::<U64Tup, U64Txn>::new(&path).unwrap();
+    tree.commit(rows(0..2000, |k| Some(U64Tup(k, 0)))).unwrap();
+    let old = tree.snapshot();
+    let grown = tree.npage();
+    for round in 1..=20 {
+        tree.commit(rows(0..10, |k| Some(U64Tup(k, round)))).unwrap();
+    }
+    // replaced pages are kept for the old snapshot
+    assert!(tree.npage() > grown);
+    assert!((0..10).all(|k| old.get(&k).unwrap().unwrap().1 == 0));
+    assert!((0..10).all(|k| tree.snapshot().get(&k).unwrap().unwrap().1 == 20));
+    drop(old);
+    // once the snapshot is gone, replaced pages are recycled and the file stops growing
+    tree.commit(rows(0..10, |k| Some(U64Tup(k, 21)))).unwrap();
+    let grown = tree.npage();
+    for round in 22..=40 {
+        tree.commit(rows(0..10, |k| Some(U64Tup(k, round)))).unwrap();
+    }
+    assert!(tree.npage() == grown);
+    drop(tree);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn is_torn_meta_recovered() {
+    let path = tmp_path("cow-btree");
+    let txn = {
+        let tree = CowBTree::<U64Tup, U64Txn>::new(&path).unwrap();
+        tree.commit(rows(0..100, |k| Some(U64Tup(k, 1)))).unwrap();
+        tree.commit(rows(0..100, |k| Some(U64Tup(k, 2)))).unwrap();
+        let txn = tree.snapshot().txn();
+        txn
+    };
+    // tear the meta page of the latest commit
+    let file = std::fs::OpenOptions::new().write(true).open(&path).unwrap();
+    file.write_all_at(&[0xff; 64], (txn % 2) * PAGE_SIZE as u64).unwrap();
+    drop(file);
+    let tree = CowBTree::<U64Tup, U64Txn>::new(&path).unwrap();
+    let snap = tree.snapshot();
+    assert!(snap.txn() == txn - 1);
+    assert!((0..100).all(|k| snap.get(&k).unwrap().unwrap().1 == 1));
+    drop(snap);
+    drop(tree);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn is_aborted_discarded() {
+    let path = tmp_path("cow-btree");
+    let tree = CowBTree::<U64Tup, U64Txn>::new(&path).unwrap();
+    let mut gen = txns();
+    let (ready, abort) = (gen.get(), gen.get());
+    tree.open(&ready).unwrap();
+    tree.open(&abort).unwrap();
+    tree.wr(&ready, U64Map(Some((1, Some(U64Tup(1, 1)))))).unwrap();
+    tree.wr(&abort, U64Map(Some((2, Some(U64Tup(2, 2)))))).unwrap();
+    // nothing is visible before commit
+    assert!(tree.rd(U64Prp(1)).unwrap().0.is_none());
+    tree.done(&abort, End::Abort).unwrap();
+    tree.done(&ready, End::Ready).unwrap();
+    assert!(tree.rd(U64Prp(1)).unwrap().0.and_then(|(_, v)| v).map(|v| v.1) == Some(1));
+    assert!(tree.rd(U64Prp(2)).unwrap().0.is_none());
+    drop(tree);
+    std::fs::remove_file(&path).unwrap();
+}
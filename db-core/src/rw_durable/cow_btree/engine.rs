@@ -0,0 +1,400 @@
+use super::error::*;
+use crate::rw_durable::btree::node::*;
+use crate::rw_durable::snapshot::Dump;
+use crate::utilities::*;
+use dashmap::DashMap;
+use parking_lot::Mutex;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::{File, OpenOptions};
+use std::hash::Hash;
+use std::marker::PhantomData;
+use std::ops::{Bound, RangeBounds};
+use std::path::Path;
+use typing::constraint::*;
+use typing::rw::*;
+use typing::tx::*;
+
+// meta page: a checksummed frame of [magic] [txn: u64] [root: u32] [number of pages: u32]
+// commit n writes its meta into page n % 2, data pages start from page 2
+const MAGIC: &[u8; 8] = b"COWBTREE";
+
+fn read_node<V: Codec>(file: &File, id: u32) -> Result<Node<V>, CowErr>
+where
+    V::I: Ord + Clone,
+{
+    let mut page = vec![0u8; PAGE_SIZE];
+    file.read_exact_at(&mut page, id as u64 * PAGE_SIZE as u64)?;
+    Node::decode(&page, id).map_err(|_| CowErr::Corrupt(id))
+}
+
+fn read_meta(file: &File, slot: u32) -> Option<(u64, u32, u32)> {
+    let mut page = vec![0u8; PAGE_SIZE];
+    file.read_exact_at(&mut page, slot as u64 * PAGE_SIZE as u64).ok()?;
+    let buf = &mut get_frame(&mut &page[..]).ok()??;
+    if take(buf, MAGIC.len())? != &MAGIC[..] { return None }
+    Some((get_u64(buf)?, get_u32(buf)?, get_u32(buf)?))
+}
+
+fn write_meta(file: &File, txn: u64, root: u32, npage: u32) -> std::io::Result<()> {
+    let mut payload = vec![];
+    payload.extend(MAGIC);
+    payload.extend(txn.to_le_bytes());
+    payload.extend(root.to_le_bytes());
+    payload.extend(npage.to_le_bytes());
+    let mut page = vec![];
+    put_frame(&payload, &mut page);
+    page.resize(PAGE_SIZE, 0);
+    file.write_all_at(&page, (txn % 2) * PAGE_SIZE as u64)?;
+    file.sync_data()
+}
+
+// rows to apply in a commit, none for removal
+type Rows<V> = Vec<(<V as Id>::I, Option<V>)>;
+
+// the latest commit and pinned snapshots
+struct Readers {
+    root: u32,
+    txn: u64,
+    // snapshot txn -> the number of readers on it
+    pins: BTreeMap<u64, usize>,
+}
+
+// writer state, only one commit runs at a time
+struct Writer {
+    root: u32,
+    txn: u64,
+    npage: u32,
+    // pages that can be reused now
+    free: Vec<u32>,
+    // pages replaced by a commit, they are reused once no snapshot is older than that commit
+    freed: Vec<(u64, u32)>,
+}
+
+impl Writer {
+    fn alloc(&mut self) -> u32 {
+        self.free.pop().unwrap_or_else(|| { self.npage += 1; self.npage - 1 })
+    }
+}
+
+// pages copied by an ongoing commit, and the pages they replace
+struct Dirty<V: Codec> {
+    nodes: HashMap<u32, Node<V>>,
+    freed: Vec<u32>,
+}
+
+pub struct CowBTree<V: Id, T: Tx<V>>
+where
+    V: Sync + Send + Clone + Codec,
+    V::I: Ord + Hash + Sync + Send + Clone,
+    T::I: Eq + Hash,
+    T::Prp: Filter<V>,
+    T::Map: Mapper<V::I, V>,
+{
+    file: File,
+    // lock order: writer, readers
+    writer: Mutex<Writer>,
+    readers: Mutex<Readers>,
+    // writes of open transactions, applied in one commit when ready
+    pending: DashMap<T::I, Rows<V>>,
+    phant: PhantomData<T>,
+}
+
+/// a consistent view of a committed tree, pages it reaches are not recycled until it is dropped
+pub struct Snapshot<'a, V: Id, T: Tx<V>>
+where
+    V: Sync + Send + Clone + Codec,
+    V::I: Ord + Hash + Sync + Send + Clone,
+    T::I: Eq + Hash,
+    T::Prp: Filter<V>,
+    T::Map: Mapper<V::I, V>,
+{
+    tree: &'a CowBTree<V, T>,
+    root: u32,
+    txn: u64,
+}
+
+impl<V: Id, T: Tx<V>> CowBTree<V, T>
+where
+    V: Sync + Send + Clone + Codec,
+    V::I: Ord + Hash + Sync + Send + Clone,
+    T::I: Eq + Hash,
+    T::Prp: Filter<V>,
+    T::Map: Mapper<V::I, V>,
+{
+    /// open (or create) a data file, the tree is rooted at the latest valid meta page
+    pub fn new(path: impl AsRef<Path>) -> Result<Self, CowErr> {
+        let file = OpenOptions::new()
+            .read(true).write(true).create(true).truncate(false)
+            .open(path)?;
+        if file.metadata()?.len() == 0 {
+            file.write_all_at(&Node::<V>::leaf(vec![], 0).encode(), 2 * PAGE_SIZE as u64)?;
+            file.sync_data()?;
+            write_meta(&file, 1, 2, 3)?;
+        }
+        let (txn, root, npage) = [0, 1].into_iter()
+            .filter_map(|slot| read_meta(&file, slot))
+            .max_by_key(|(txn, ..)| *txn)
+            .ok_or(CowErr::Corrupt(0))?;
+        // no snapshot survives a restart, so pages unreachable from the root are free
+        let mut live = HashSet::new();
+        let mut todo = vec![root];
+        while let Some(id) = todo.pop() {
+            live.insert(id);
+            if let Body::Inner { kids, .. } = read_node::<V>(&file, id)?.body { todo.extend(kids) }
+        }
+        let free = (2..npage).filter(|id| !live.contains(id)).collect();
+        Ok(Self {
+            file,
+            writer: Mutex::new(Writer { root, txn, npage, free, freed: vec![] }),
+            readers: Mutex::new(Readers { root, txn, pins: BTreeMap::new() }),
+            pending: DashMap::new(),
+            phant: PhantomData,
+        })
+    }
+    /// the number of pages in data file, including meta pages
+    pub fn npage(&self) -> u32 {
+        self.writer.lock().npage
+    }
+    /// pin the latest commit as a snapshot
+    pub fn snapshot(&self) -> Snapshot<'_, V, T> {
+        let mut readers = self.readers.lock();
+        let (root, txn) = (readers.root, readers.txn);
+        *readers.pins.entry(txn).or_insert(0) += 1;
+        Snapshot { tree: self, root, txn }
+    }
+    fn unpin(&self, txn: u64) {
+        let mut readers = self.readers.lock();
+        let count = readers.pins.get_mut(&txn).unwrap();
+        *count -= 1;
+        if *count == 0 { readers.pins.remove(&txn); }
+    }
+    // copy a page into dirty pages unless it is already there, return the id of the copy
+    fn touch(&self, writer: &mut Writer, dirty: &mut Dirty<V>, id: u32) -> Result<u32, CowErr> {
+        if dirty.nodes.contains_key(&id) { return Ok(id) }
+        let node = read_node(&self.file, id)?;
+        let new = writer.alloc();
+        dirty.nodes.insert(new, node);
+        dirty.freed.push(id);
+        Ok(new)
+    }
+    // insert, replace or remove a row by copying the path to its leaf, return the new root
+    fn put(&self, writer: &mut Writer, dirty: &mut Dirty<V>, root: u32, key: V::I, val: Option<V>) -> Result<u32, CowErr> {
+        let mut root = self.touch(writer, dirty, root)?;
+        let mut path = vec![];
+        let mut page = root;
+        while let Body::Inner { .. } = &dirty.nodes[&page].body {
+            let child = dirty.nodes[&page].child(&key);
+            let copy = self.touch(writer, dirty, child)?;
+            dirty.nodes.get_mut(&page).unwrap().set_child(&key, copy);
+            path.push(page);
+            page = copy;
+        }
+        let leaf = dirty.nodes.get_mut(&page).unwrap();
+        match val {
+            Some(v) => leaf.put(key, v),
+            None => { leaf.del(&key); return Ok(root) }
+        }
+        // split overflowed nodes bottom up
+        while dirty.nodes[&page].size > PAGE_SIZE {
+            let right = writer.alloc();
+            let (sep, half) = dirty.nodes.get_mut(&page).unwrap().split(0);
+            dirty.nodes.insert(right, half);
+            match path.pop() {
+                Some(parent) => {
+                    dirty.nodes.get_mut(&parent).unwrap().put_child(sep, right);
+                    page = parent;
+                }
+                None => {
+                    root = writer.alloc();
+                    dirty.nodes.insert(root, Node::inner(vec![sep], vec![page, right]));
+                    break;
+                }
+            }
+        }
+        Ok(root)
+    }
+    /// apply rows (none for removal) as one commit, the new root takes effect once its meta page is durable
+    pub fn commit(&self, rows: Rows<V>) -> Result<(), CowErr> {
+        // a row must fit in half a page, so a split always makes room
+        for (k, v) in rows.iter() {
+            if let Some(v) = v {
+                if 2 * row_len(k, v) + NODE_HEAD > PAGE_SIZE { return Err(CowErr::TooLarge) }
+            }
+        }
+        let mut writer = self.writer.lock();
+        // a page replaced by commit n is only reachable from snapshots before n
+        let oldest = self.readers.lock().pins.keys().next().copied().unwrap_or(u64::MAX);
+        let (ready, wait) = std::mem::take(&mut writer.freed).into_iter().partition::<Vec<_>, _>(|(txn, _)| *txn <= oldest);
+        writer.free.extend(ready.into_iter().map(|(_, id)| id));
+        writer.freed = wait;
+        // on failure, pages allocated by this commit are lost until the file is reopened
+        let txn = writer.txn + 1;
+        let mut dirty = Dirty { nodes: HashMap::new(), freed: vec![] };
+        let mut root = writer.root;
+        for (key, val) in rows {
+            root = self.put(&mut writer, &mut dirty, root, key, val)?;
+        }
+        for (id, node) in dirty.nodes.iter() {
+            self.file.write_all_at(&node.encode(), *id as u64 * PAGE_SIZE as u64)?;
+        }
+        self.file.sync_data()?;
+        write_meta(&self.file, txn, root, writer.npage)?;
+        writer.root = root;
+        writer.txn = txn;
+        writer.freed.extend(dirty.freed.into_iter().map(|id| (txn, id)));
+        let mut readers = self.readers.lock();
+        readers.root = root;
+        readers.txn = txn;
+        Ok(())
+    }
+}
+
+impl<V: Id, T: Tx<V>> Snapshot<'_, V, T>
+where
+    V: Sync + Send + Clone + Codec,
+    V::I: Ord + Hash + Sync + Send + Clone,
+    T::I: Eq + Hash,
+    T::Prp: Filter<V>,
+    T::Map: Mapper<V::I, V>,
+{
+    /// the commit that this snapshot sees
+    pub fn txn(&self) -> u64 {
+        self.txn
+    }
+    /// get the value of a key
+    pub fn get(&self, key: &V::I) -> Result<Option<V>, CowErr> {
+        let mut page = self.root;
+        loop {
+            let node = read_node::<V>(&self.tree.file, page)?;
+            match &node.body {
+                Body::Leaf { rows, .. } => {
+                    let i = rows.binary_search_by(|(k, _)| k.cmp(key));
+                    return Ok(i.ok().map(|i| rows[i].1.clone()));
+                }
+                Body::Inner { .. } => page = node.child(key),
+            }
+        }
+    }
+    /// visit rows with keys in a range in key order
+    pub fn scan(&self, lo: Bound<&V::I>, hi: Bound<&V::I>, mut f: impl FnMut(&V::I, &V)) -> Result<(), CowErr> {
+        self.visit(self.root, lo, hi, &mut f)
+    }
+    fn visit(&self, page: u32, lo: Bound<&V::I>, hi: Bound<&V::I>, f: &mut impl FnMut(&V::I, &V)) -> Result<(), CowErr> {
+        match read_node::<V>(&self.tree.file, page)?.body {
+            Body::Leaf { rows, .. } => {
+                for (k, v) in rows.iter().filter(|(k, _)| (lo, hi).contains(k)) { f(k, v) }
+            }
+            Body::Inner { keys, kids } => {
+                // kids[i] holds keys in [keys[i-1], keys[i])
+                for (i, kid) in kids.into_iter().enumerate() {
+                    let below = i > 0 && match hi {
+                        Bound::Included(h) => h < &keys[i - 1],
+                        Bound::Excluded(h) => h <= &keys[i - 1],
+                        Bound::Unbounded => false,
+                    };
+                    let above = i < keys.len() && match lo {
+                        Bound::Included(l) | Bound::Excluded(l) => l >= &keys[i],
+                        Bound::Unbounded => false,
+                    };
+                    if !below && !above { self.visit(kid, lo, hi, f)? }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<V: Id, T: Tx<V>> Drop for Snapshot<'_, V, T>
+where
+    V: Sync + Send + Clone + Codec,
+    V::I: Ord + Hash + Sync + Send + Clone,
+    T::I: Eq + Hash,
+    T::Prp: Filter<V>,
+    T::Map: Mapper<V::I, V>,
+{
+    fn drop(&mut self) {
+        self.tree.unpin(self.txn);
+    }
+}
+
+impl<V: Id, T: Tx<V>> RWDurable<V, T> for CowBTree<V, T>
+where
+    V: Sync + Send + Clone + Codec,
+    V::I: Ord + Hash + Sync + Send + Clone,
+    T::I: Eq + Hash,
+    T::Prp: Filter<V> + MaybeIndexer<V::I> + MaybeRanger<V::I>,
+    T::Map: Mapper<V::I, V>,
+{
+    type Err = CowErr;
+    fn done(&self, txn: &T, end: End) -> Result<(), Self::Err> {
+        let Some((_, rows)) = self.pending.remove(&txn.id()) else { return Ok(()) };
+        if matches!(end, End::Abort) || rows.is_empty() { return Ok(()) }
+        self.commit(rows)
+    }
+    fn open(&self, txn: &T) -> Result<(), Self::Err> {
+        self.pending.insert(txn.id(), vec![]);
+        Ok(())
+    }
+    fn rd(&self, prp: T::Prp) -> Result<T::Map, Self::Err> {
+        // reads see the latest commit, not writes of open transactions
+        read_snap(&self.snapshot(), prp)
+    }
+    fn wr(&self, txn: &T, map: T::Map) -> Result<(), Self::Err> {
+        self.pending.entry(txn.id()).or_default().extend(map.into_mapping());
+        Ok(())
+    }
+    // a batch is read from one snapshot, so all of its maps see the same commit
+    fn rd_batch(&self, prps: Vec<T::Prp>) -> Result<Vec<T::Map>, Self::Err> {
+        let snap = self.snapshot();
+        prps.into_iter().map(|prp| read_snap(&snap, prp)).collect()
+    }
+    // a batch joins the buffered writes, so it is applied in the commit of its transaction
+    fn wr_batch(&self, txn: &T, maps: Vec<T::Map>) -> Result<(), Self::Err> {
+        self.pending.entry(txn.id()).or_default().extend(maps.iter().flat_map(|map| map.into_mapping()));
+        Ok(())
+    }
+}
+
+// answer a proposition from a snapshot
+fn read_snap<V, T>(snap: &Snapshot<'_, V, T>, prp: T::Prp) -> Result<T::Map, CowErr>
+where
+    V: Id + Sync + Send + Clone + Codec,
+    T: Tx<V>,
+    V::I: Ord + Hash + Sync + Send + Clone,
+    T::I: Eq + Hash,
+    T::Prp: Filter<V> + MaybeIndexer<V::I> + MaybeRanger<V::I>,
+    T::Map: Mapper<V::I, V>,
+{
+    let mut map = vec![];
+    if let Some(prp_iter) = prp.tryc_indexer() {
+        for i in prp_iter {
+            if let Some(v) = snap.get(&i)? {
+                map.push((i, Some(v)));
+            }
+        }
+    } else {
+        // without a range, all leaves are scanned
+        let (lo, hi) = prp.tryc_ranger().unwrap_or((Bound::Unbounded, Bound::Unbounded));
+        let filter = prp.into_filter();
+        snap.scan(lo.as_ref(), hi.as_ref(), |i, v| {
+            if (filter)(v) { map.push((i.clone(), Some(v.clone()))) }
+        })?;
+    }
+    Ok(Mapper::from_mapping(map.into_iter()))
+}
+
+impl<V: Id, T: Tx<V>> Dump<V> for CowBTree<V, T>
+where
+    V: Sync + Send + Clone + Codec,
+    V::I: Ord + Hash + Sync + Send + Clone,
+    T::I: Eq + Hash,
+    T::Prp: Filter<V>,
+    T::Map: Mapper<V::I, V>,
+{
+    type Err = CowErr;
+    fn dump(&self, f: &mut dyn FnMut(&V::I, &V)) -> Result<(), Self::Err> {
+        // a pinned snapshot is a committed state, whatever runs meanwhile
+        let snap = self.snapshot();
+        snap.scan(Bound::Unbounded, Bound::Unbounded, f)
+    }
+}
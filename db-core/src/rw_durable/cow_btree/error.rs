@@ -0,0 +1,15 @@
+#[derive(Debug)]
+pub enum CowErr {
+    // file system error
+    Io(std::io::Error),
+    // a page with a given id cannot be decoded, 0 if no meta page is valid
+    Corrupt(u32),
+    // a row doesn't fit into half a page
+    TooLarge,
+}
+
+impl From<std::io::Error> for CowErr {
+    fn from(e: std::io::Error) -> Self {
+        CowErr::Io(e)
+    }
+}
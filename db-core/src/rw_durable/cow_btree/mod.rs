@@ -0,0 +1,22 @@
+//! ## Copy-on-Write B-Tree
+//!
+//! > Chu, Howard. "MDB: A memory-mapped database and backend for OpenLDAP." LDAPCon (2011).
+//!
+//! An append-only (copy-on-write) B-tree in the style of LMDB. Pages are never modified in place:
+//! a commit copies every page on the paths it touches, writes them to free pages, then writes a new root into one of two meta pages.
+//! The meta pages alternate between commits, so a torn meta page leaves the previous commit intact, and no log is needed.
+//! Readers pin a committed root as a snapshot and read it without any lock, writers go on meanwhile.
+//! A page replaced by a commit is recycled once no pinned snapshot is older than that commit.
+//! Writes of a transaction are buffered until it is ready, then applied as a single commit.
+//! Nodes share the page layout of the B+tree engine, leaves are not linked since a copy would break the links.
+
+// copy-on-write b-tree error
+mod error;
+// core copy-on-write b-tree engine implementation
+mod engine;
+
+pub use error::*;
+pub use engine::*;
+
+#[cfg(test)]
+mod check; // snapshot and recovery checks
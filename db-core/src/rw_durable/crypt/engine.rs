@@ -0,0 +1,144 @@
+use super::error::*;
+use super::file::*;
+use crate::rw_durable::snapshot::Dump;
+use crate::rw_durable::wal::{get_record, put_record, Mapping};
+use dashmap::DashMap;
+use std::hash::Hash;
+use crate::utilities::FileIo;
+use std::fs::File;
+use std::marker::PhantomData;
+use std::path::Path;
+use typing::constraint::*;
+use typing::rw::*;
+use typing::tx::*;
+
+/// a null engine backed by an encrypted log, the table is in memory and every ready transaction is a sealed record
+/// the log is a file on disk by default, or any file with the same surface
+pub struct CryptNull<V: Id, T: Tx<V>, F: FileIo = File>
+where
+    V: Sync + Clone + Codec,
+    V::I: Eq + Hash + Sync + Clone,
+    T::I: Eq + Hash,
+    T::Prp: Filter<V>,
+    T::Map: Mapper<V::I, V>,
+{
+    table: DashMap<V::I, V>,
+    log: CryptFile<F>,
+    // writes of open transactions, logged and applied when they are ready
+    pending: DashMap<T::I, Mapping<V>>,
+    phant: PhantomData<T>,
+}
+
+impl<V: Id, T: Tx<V>> CryptNull<V, T>
+where
+    V: Sync + Clone + Codec,
+    V::I: Eq + Hash + Sync + Clone,
+    T::I: Eq + Hash,
+    T::Prp: Filter<V>,
+    T::Map: Mapper<V::I, V>,
+{
+    /// open a log file (create it if absent) with a key, and rebuild the table by replaying it
+    pub fn new(path: impl AsRef<Path>, key: &Key) -> Result<Self, CryptErr> {
+        CryptNull::replay(CryptFile::open(path, key)?)
+    }
+}
+
+impl<V: Id, T: Tx<V>, F: FileIo> CryptNull<V, T, F>
+where
+    V: Sync + Clone + Codec,
+    V::I: Eq + Hash + Sync + Clone,
+    T::I: Eq + Hash,
+    T::Prp: Filter<V>,
+    T::Map: Mapper<V::I, V>,
+{
+    /// like CryptNull::new, but over an opened log file, e.g. a file on a simulated device
+    pub fn with_file(file: F, key: &Key) -> Result<Self, CryptErr> {
+        CryptNull::replay(CryptFile::with_file(file, key)?)
+    }
+    // rebuild the table by replaying a log
+    fn replay(log: CryptFile<F>) -> Result<Self, CryptErr> {
+        let table = DashMap::new();
+        for (at, record) in log.records()? {
+            for (i, v) in get_record::<V>(&record).ok_or(CryptErr::Corrupt(at))? {
+                match v {
+                    Some(v) => {table.insert(i, v);},
+                    None => {table.remove(&i);},
+                }
+            }
+        }
+        Ok(Self { table, log, pending: DashMap::new(), phant: PhantomData })
+    }
+    // seal the writes of a transaction as one record, sync it, then apply them to the table
+    fn append(&self, map: Mapping<V>) -> Result<(), CryptErr> {
+        let mut record = vec![];
+        put_record(&map, &mut record);
+        self.log.append(&record)?;
+        self.log.sync()?;
+        for (i, v) in map {
+            match v {
+                Some(v) => {self.table.insert(i, v);},
+                None => {self.table.remove(&i);},
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<V: Id, T: Tx<V>, F: FileIo> RWDurable<V, T> for CryptNull<V, T, F>
+where
+    V: Sync + Clone + Codec,
+    V::I: Eq + Hash + Sync + Clone,
+    T::I: Eq + Hash,
+    T::Prp: Filter<V> + MaybeIndexer<V::I>,
+    T::Map: Mapper<V::I, V>,
+{
+    type Err = CryptErr;
+    fn done(&self, txn: &T, end: End) -> Result<(), Self::Err> {
+        let writes = self.pending.remove(&txn.id()).map(|(_, writes)| writes);
+        if matches!(end, End::Abort) { return Ok(()) }
+        self.append(writes.unwrap_or_default())
+    }
+    fn open(&self, _txn: &T) -> Result<(), Self::Err> {
+        Ok(())
+    }
+    fn rd(&self, prp: T::Prp) -> Result<T::Map, Self::Err> {
+        let mut map = vec![];
+        if let Some(prp_iter) = prp.tryc_indexer() {
+            for i in prp_iter {
+                if let Some(v) = self.table.get(&i) {
+                    map.push((i, Some(v.clone())));
+                }
+            }
+        } else {
+            let prp = prp.into_filter();
+            for e in self.table.iter() {
+                if (prp)(e.value()) { map.push((e.key().clone(), Some(e.value().clone()))) }
+            }
+        }
+        Ok(Mapper::from_mapping(map.into_iter()))
+    }
+    fn wr(&self, txn: &T, map: T::Map) -> Result<(), Self::Err> {
+        self.pending.entry(txn.id()).or_default().extend(map.into_mapping());
+        Ok(())
+    }
+    // a batch joins the buffered writes, so it is sealed in the record of its transaction
+    fn wr_batch(&self, txn: &T, maps: Vec<T::Map>) -> Result<(), Self::Err> {
+        self.pending.entry(txn.id()).or_default().extend(maps.iter().flat_map(|map| map.into_mapping()));
+        Ok(())
+    }
+}
+
+impl<V: Id, T: Tx<V>, F: FileIo> Dump<V> for CryptNull<V, T, F>
+where
+    V: Sync + Clone + Codec,
+    V::I: Eq + Hash + Sync + Clone,
+    T::I: Eq + Hash,
+    T::Prp: Filter<V>,
+    T::Map: Mapper<V::I, V>,
+{
+    type Err = CryptErr;
+    fn dump(&self, f: &mut dyn FnMut(&V::I, &V)) -> Result<(), Self::Err> {
+        for e in self.table.iter() { f(e.key(), e.value()) }
+        Ok(())
+    }
+}
@@ -0,0 +1,13 @@
+#[derive(Debug)]
+pub enum CryptErr {
+    // file system error
+    Io(std::io::Error),
+    // a page or record at a given offset fails authentication, or cannot be decoded once opened
+    Corrupt(u64),
+}
+
+impl From<std::io::Error> for CryptErr {
+    fn from(e: std::io::Error) -> Self {
+        CryptErr::Io(e)
+    }
+}
@@ -0,0 +1,144 @@
+use super::error::*;
+use crate::utilities::FileIo;
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Nonce};
+use parking_lot::Mutex;
+use std::fs::{File, OpenOptions};
+use std::io::ErrorKind;
+use std::path::Path;
+
+/// the size of a nonce and an authentication tag stored with each page or record
+pub const NONCE: usize = 12;
+pub const TAG: usize = 16;
+
+/// a 256-bit key
+#[derive(Clone)]
+pub struct Key([u8; 32]);
+
+impl Key {
+    pub fn new(bytes: [u8; 32]) -> Self {
+        Key(bytes)
+    }
+    pub fn random() -> Self {
+        Key(rand::random())
+    }
+}
+
+// a key never shows up in logs
+impl std::fmt::Debug for Key {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Key(..)")
+    }
+}
+
+/// a file of sealed pages, or of sealed log records, not both, over a file on disk by default
+///     page: [nonce] [ciphertext] [tag] at id * (page length + NONCE + TAG)
+///     record: [length: u32] [nonce] [ciphertext] [tag], length covers nonce, ciphertext and tag
+///     a log starts with an empty record as a key check, so a wrong key is told apart from a torn tail
+pub struct CryptFile<F: FileIo = File> {
+    file: F,
+    cipher: ChaCha20Poly1305,
+    // the end of valid records, where the next one is appended
+    end: Mutex<u64>,
+}
+
+impl CryptFile {
+    /// open a file (create it if absent), for a log call records before appending
+    pub fn open(path: impl AsRef<Path>, key: &Key) -> Result<Self, CryptErr> {
+        let file = OpenOptions::new()
+            .read(true).write(true).create(true).truncate(false)
+            .open(path)?;
+        CryptFile::with_file(file, key)
+    }
+}
+
+impl<F: FileIo> CryptFile<F> {
+    /// seal an opened file, e.g. a file on a simulated device
+    pub fn with_file(file: F, key: &Key) -> Result<Self, CryptErr> {
+        let end = Mutex::new(file.size()?);
+        Ok(CryptFile { file, cipher: ChaCha20Poly1305::new(&key.0.into()), end })
+    }
+    // [nonce] [ciphertext] [tag]
+    fn seal(&self, aad: &[u8], plain: &[u8]) -> Vec<u8> {
+        let nonce = rand::random::<[u8; NONCE]>();
+        let mut sealed = nonce.to_vec();
+        sealed.extend(self.cipher.encrypt(Nonce::from_slice(&nonce), Payload { msg: plain, aad })
+            .expect("a buffer in memory is always sealed"));
+        sealed
+    }
+    fn unseal(&self, aad: &[u8], sealed: &[u8]) -> Option<Vec<u8>> {
+        if sealed.len() < NONCE + TAG { return None }
+        let (nonce, msg) = sealed.split_at(NONCE);
+        self.cipher.decrypt(Nonce::from_slice(nonce), Payload { msg, aad }).ok()
+    }
+    /// write a page, all pages of a file have the same length
+    pub fn write_page(&self, id: u64, page: &[u8]) -> Result<(), CryptErr> {
+        let slot = (page.len() + NONCE + TAG) as u64;
+        self.file.write_all_at(&self.seal(&id.to_le_bytes(), page), id * slot)?;
+        Ok(())
+    }
+    /// read a page of a given length, None if it is never written
+    pub fn read_page(&self, id: u64, len: usize) -> Result<Option<Vec<u8>>, CryptErr> {
+        let slot = len + NONCE + TAG;
+        let mut sealed = vec![0u8; slot];
+        match self.file.read_exact_at(&mut sealed, id * slot as u64) {
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            res => res?,
+        }
+        // a hole left by writing a later page
+        if sealed.iter().all(|b| *b == 0) { return Ok(None) }
+        self.unseal(&id.to_le_bytes(), &sealed).map(Some).ok_or(CryptErr::Corrupt(id * slot as u64))
+    }
+    /// append a record, it is durable once the file is synced
+    pub fn append(&self, record: &[u8]) -> Result<(), CryptErr> {
+        self.append_at(&mut self.end.lock(), record)
+    }
+    // append a record at the end of valid records, with the end locked
+    fn append_at(&self, end: &mut u64, record: &[u8]) -> Result<(), CryptErr> {
+        let len = (record.len() + NONCE + TAG) as u32;
+        let mut frame = len.to_le_bytes().to_vec();
+        frame.extend(self.seal(&record_aad(*end, len), record));
+        self.file.write_all_at(&frame, *end)?;
+        *end += frame.len() as u64;
+        Ok(())
+    }
+    /// read all records with their offsets, a torn tail is truncated so new records follow the last valid one
+    /// the last record failing authentication is a torn tail, any record before it (or the key check) is corrupt
+    pub fn records(&self) -> Result<Vec<(u64, Vec<u8>)>, CryptErr> {
+        let mut end = self.end.lock();
+        let mut bytes = vec![0u8; self.file.size()? as usize];
+        self.file.read_exact_at(&mut bytes, 0)?;
+        let (mut records, mut at) = (vec![], 0);
+        while bytes.len() - at >= 4 {
+            let len = u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap());
+            let Some(sealed) = bytes.get(at + 4..at + 4 + len as usize) else { break };
+            // a zero-filled tail left by a crash
+            if (len as usize) < NONCE + TAG { break }
+            let next = at + 4 + len as usize;
+            match self.unseal(&record_aad(at as u64, len), sealed) {
+                Some(record) => records.push((at as u64, record)),
+                None if at > 0 && next == bytes.len() => break,
+                None => return Err(CryptErr::Corrupt(at as u64)),
+            }
+            at = next;
+        }
+        self.file.set_len(at as u64)?;
+        *end = at as u64;
+        // a new log gets its key check
+        if records.is_empty() { self.append_at(&mut end, &[])? }
+        self.file.sync_data()?;
+        Ok(records.into_iter().skip(1).collect())
+    }
+    pub fn sync(&self) -> Result<(), CryptErr> {
+        self.file.sync_data()?;
+        Ok(())
+    }
+}
+
+// a record is bound to its offset and length
+fn record_aad(at: u64, len: u32) -> [u8; 12] {
+    let mut aad = [0u8; 12];
+    aad[..8].copy_from_slice(&at.to_le_bytes());
+    aad[8..].copy_from_slice(&len.to_le_bytes());
+    aad
+}
@@ -0,0 +1,24 @@
+//! ## Encryption at Rest
+//!
+//! > Nir, Yoav, and Adam Langley. "ChaCha20 and Poly1305 for IETF Protocols." RFC 8439 (2018).
+//!
+//! A byte-level file wrapper that seals pages and log records with ChaCha20-Poly1305, under a key supplied at open time.
+//! Every page or record gets a fresh random nonce, stored next to its ciphertext and authentication tag.
+//! The position of a page (its id) or a record (its offset and length) is authenticated as associated data, so sealed bytes cannot be moved around.
+//! A failed authentication (a wrong key, or tampered bytes) is reported as corruption, while the last record of a log, cut short or failing authentication, is a torn tail and truncated.
+//! A log starts with an empty record as a key check, so a wrong key is never mistaken for a torn tail.
+//! An on-disk engine opts in by going through a CryptFile for its pages or its log, the first user is an encrypted, file-backed null engine that logs a record per ready transaction.
+
+// encryption error
+mod error;
+// sealed pages and log records in a file
+mod file;
+// encrypted file-backed null engine
+mod engine;
+
+pub use error::*;
+pub use file::*;
+pub use engine::*;
+
+#[cfg(test)]
+mod check; // round trip, tampering and wrong key checks
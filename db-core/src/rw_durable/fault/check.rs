@@ -0,0 +1,154 @@
+use super::*;
+use crate::rw_durable::btree::BTree;
+use crate::rw_durable::check_util::*;
+use crate::rw_durable::latency::Dist;
+use crate::rw_durable::null::Null;
+use crate::rw_durable::wal::{GroupCommit, Wal};
+use std::time::Duration;
+use db_test::core_workload::int::unif::*;
+use typing::rw::*;
+use typing::tx::*;
+
+type Durable = Fault<U64Tup, U64Txn, Wal<U64Tup, U64Txn>>;
+
+fn put(k: u64, v: u64) -> U64Map {
+    U64Map(Some((k, Some(U64Tup(k, v)))))
+}
+
+#[test]
+fn is_schedule_seeded() {
+    let run = |seed| {
+        let conf = FaultConf { seed, error: 0.3, ..Default::default() };
+        let dur = Fault::new(Null::<U64Tup, U64Txn>::new(0, 0, false), conf);
+        (0..200).map(|k| dur.rd(U64Prp(k)).is_ok()).collect::<Vec<_>>()
+    };
+    assert!(run(1) == run(1));
+    assert!(run(1) != run(2));
+    assert!(run(1).contains(&true) && run(1).contains(&false));
+}
+
+#[test]
+fn is_unsynced_dropped() {
+    let path = tmp_path("fault");
+    let mut workload = txns();
+    let (txn_a, txn_b) = (workload.get(), workload.get());
+    {
+        let dur: Durable = Fault::new(Wal::new(&path).unwrap(), FaultConf::default());
+        dur.open(&txn_a).unwrap();
+        dur.wr(&txn_a, put(1, 10)).unwrap();
+        dur.done(&txn_a, End::Ready).unwrap();
+        dur.open(&txn_b).unwrap();
+        dur.wr(&txn_b, put(2, 20)).unwrap();
+        dur.crash();
+        assert!(matches!(dur.done(&txn_b, End::Ready), Err(FaultErr::Crashed)));
+        assert!(matches!(dur.rd(U64Prp(1)), Err(FaultErr::Crashed)));
+        dur.into_inner().crash().unwrap();
+    }
+    let wal = Wal::<U64Tup, U64Txn>::new(&path).unwrap();
+    assert!(get(&wal, 1) == Some(10));
+    assert!(get(&wal, 2).is_none());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn is_write_torn() {
+    let path = tmp_path("fault");
+    let txn = txns().get();
+    {
+        // a b+tree applies writes in place, so the prefix it got is written back on drop
+        let conf = FaultConf { seed: 7, torn: 1.0, delay: Dist::Uniform(Duration::ZERO, Duration::from_micros(50)), ..Default::default() };
+        let dur = Fault::new(BTree::<U64Tup, U64Txn>::new(&path, 16).unwrap(), conf);
+        dur.open(&txn).unwrap();
+        for k in 0..10 {
+            dur.wr(&txn, put(k, k)).unwrap();
+        }
+        assert!(matches!(dur.done(&txn, End::Ready), Err(FaultErr::Crashed)));
+        assert!(dur.is_crashed());
+        drop(dur.into_inner());
+    }
+    // the inner engine got a non-empty strict prefix of the writes
+    let tree = BTree::<U64Tup, U64Txn>::new(&path, 16).unwrap();
+    let cut = (0..10u64).take_while(|&k| get(&tree, k) == Some(k)).count();
+    assert!(0 < cut && cut < 10 && (cut as u64..10).all(|k| get(&tree, k).is_none()));
+    drop(tree);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn is_record_torn() {
+    let path = tmp_path("fault");
+    let mut workload = txns();
+    let (txn_a, txn_b) = (workload.get(), workload.get());
+    {
+        let wal = Wal::<U64Tup, U64Txn>::new(&path).unwrap();
+        wal.open(&txn_a).unwrap();
+        wal.wr_batch(&txn_a, (0..10).map(|k| put(k, k)).collect()).unwrap();
+        wal.done(&txn_a, End::Ready).unwrap();
+    }
+    let before = std::fs::metadata(&path).unwrap().len();
+    {
+        // the record of txn_b is synced, then cut inside its bytes
+        let conf = FaultConf { seed: 7, torn: 1.0, file: Some(path.clone()), ..Default::default() };
+        let dur: Durable = Fault::new(Wal::new(&path).unwrap(), conf);
+        dur.open(&txn_b).unwrap();
+        dur.wr_batch(&txn_b, (10..20).map(|k| put(k, k)).collect()).unwrap();
+        assert!(matches!(dur.done(&txn_b, End::Ready), Err(FaultErr::Crashed)));
+        dur.into_inner().crash().unwrap();
+    }
+    assert!(std::fs::metadata(&path).unwrap().len() > before);
+    // recovery drops the torn record as a whole
+    let wal = Wal::<U64Tup, U64Txn>::new(&path).unwrap();
+    assert!((0..10).all(|k| get(&wal, k) == Some(k)));
+    assert!((10..20).all(|k| get(&wal, k).is_none()));
+    assert!(std::fs::metadata(&path).unwrap().len() == before);
+    drop(wal);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn is_unsynced_log_lost() {
+    let path = tmp_path("fault");
+    let mut workload = txns();
+    let group = GroupCommit { size: 2, wait: Duration::from_secs(1) };
+    {
+        let dur: Durable = Fault::new(Wal::with_group(&path, group).unwrap(), FaultConf::default());
+        // two transactions fill a group and are synced, the third one is only logged
+        for k in 0..3 {
+            let txn = workload.get();
+            dur.open(&txn).unwrap();
+            dur.wr(&txn, put(k, k)).unwrap();
+            dur.done(&txn, End::Ready).unwrap();
+        }
+        assert!(dur.durable_epoch() < dur.epoch());
+        dur.crash();
+        dur.into_inner().crash().unwrap();
+    }
+    let wal = Wal::<U64Tup, U64Txn>::new(&path).unwrap();
+    assert!(get(&wal, 0) == Some(0) && get(&wal, 1) == Some(1));
+    assert!(get(&wal, 2).is_none());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn is_batch_unsynced() {
+    let path = tmp_path("fault");
+    let mut workload = txns();
+    let (txn_a, txn_b) = (workload.get(), workload.get());
+    {
+        let dur: Durable = Fault::new(Wal::new(&path).unwrap(), FaultConf::default());
+        dur.open(&txn_a).unwrap();
+        dur.wr_batch(&txn_a, vec![put(1, 10), put(2, 20)]).unwrap();
+        // a batch is buffered until its transaction is done
+        assert!(dur.rd_batch(vec![U64Prp(1), U64Prp(2)]).unwrap().iter().all(|U64Map(map)| map.is_none()));
+        dur.done(&txn_a, End::Ready).unwrap();
+        dur.open(&txn_b).unwrap();
+        dur.wr_batch(&txn_b, vec![put(3, 30)]).unwrap();
+        dur.crash();
+        assert!(matches!(dur.rd_batch(vec![U64Prp(1)]), Err(FaultErr::Crashed)));
+        dur.into_inner().crash().unwrap();
+    }
+    let wal = Wal::<U64Tup, U64Txn>::new(&path).unwrap();
+    assert!(get(&wal, 1) == Some(10) && get(&wal, 2) == Some(20));
+    assert!(get(&wal, 3).is_none());
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,191 @@
+use super::error::*;
+use crate::rw_durable::latency::{wait, Dist};
+use crate::rw_durable::snapshot::Dump;
+use dashmap::DashMap;
+use parking_lot::Mutex;
+use rand::{Rng, SeedableRng};
+use rand_xoshiro::Xoshiro256PlusPlus;
+use std::hash::Hash;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering::*};
+use typing::constraint::*;
+use typing::rw::*;
+use typing::tx::*;
+
+/// a fault schedule, rates are probabilities per call
+#[derive(Debug)]
+pub struct FaultConf {
+    /// seed of the schedule, the same seed gives the same faults when calls come in the same order
+    pub seed: u64,
+    /// rate of injected errors on rd, wr, open and done
+    pub error: f64,
+    /// rate of crashes when a ready transaction is synced
+    pub crash: f64,
+    /// rate of torn writes when a ready transaction is synced
+    pub torn: f64,
+    /// the delay before a sync, a timed wait drawn from a distribution
+    pub delay: Dist,
+    /// the file that the inner engine appends records to, if any
+    /// a torn write then hands all writes to the inner engine, and cuts the bytes it appended for them
+    pub file: Option<PathBuf>,
+}
+
+impl Default for FaultConf {
+    fn default() -> Self {
+        FaultConf { seed: 0, error: 0.0, crash: 0.0, torn: 0.0, delay: Dist::Zero, file: None }
+    }
+}
+
+// the length of a file, a missing one is empty
+fn len<E>(file: &Path) -> Result<u64, FaultErr<E>> {
+    match std::fs::metadata(file) {
+        Ok(meta) => Ok(meta.len()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(0),
+        Err(e) => Err(FaultErr::Io(e)),
+    }
+}
+
+pub struct Fault<V, T: Tx<V>, D: RWDurable<V, T>>
+where
+    T::I: Eq + Hash,
+{
+    inner: D,
+    conf: FaultConf,
+    rng: Mutex<Xoshiro256PlusPlus>,
+    // writes of open transactions, not handed to the inner engine yet
+    unsynced: DashMap<T::I, Vec<T::Map>>,
+    crashed: AtomicBool,
+    phant: PhantomData<V>,
+}
+
+impl<V, T: Tx<V>, D: RWDurable<V, T>> Fault<V, T, D>
+where
+    T::I: Eq + Hash,
+{
+    pub fn new(inner: D, conf: FaultConf) -> Self {
+        let rng = Mutex::new(Xoshiro256PlusPlus::seed_from_u64(conf.seed));
+        Fault { inner, conf, rng, unsynced: DashMap::new(), crashed: AtomicBool::new(false), phant: PhantomData }
+    }
+    /// whether a crash happened, the inner engine should then be recovered from its files
+    pub fn is_crashed(&self) -> bool {
+        self.crashed.load(Acquire)
+    }
+    /// crash now, unsynced writes are dropped and every later call fails
+    pub fn crash(&self) {
+        self.crashed.store(true, Release);
+        self.unsynced.clear();
+    }
+    /// take out the inner engine, e.g. to crash it as well after a crash (see Wal::crash)
+    pub fn into_inner(self) -> D {
+        self.inner
+    }
+    // fail a call if crashed, or by the error rate
+    fn check(&self) -> Result<(), FaultErr<D::Err>> {
+        if self.is_crashed() { return Err(FaultErr::Crashed) }
+        if self.rng.lock().gen::<f64>() < self.conf.error { return Err(FaultErr::Injected) }
+        Ok(())
+    }
+}
+
+impl<V, T: Tx<V>, D: RWDurable<V, T>> RWDurable<V, T> for Fault<V, T, D>
+where
+    T::I: Eq + Hash,
+{
+    type Err = FaultErr<D::Err>;
+    fn done(&self, txn: &T, end: End) -> Result<(), Self::Err> {
+        self.check()?;
+        let maps = self.unsynced.remove(&txn.id()).map(|(_, maps)| maps).unwrap_or_default();
+        if let End::Abort = end {
+            return self.inner.done(txn, end).map_err(FaultErr::External);
+        }
+        // all draws are taken up front, so a schedule does not depend on which faults fire
+        let (delay, crash, torn, cut, frac) = {
+            let mut rng = self.rng.lock();
+            let delay = self.conf.delay.sample(&mut *rng);
+            let crash = rng.gen::<f64>() < self.conf.crash;
+            let torn = rng.gen::<f64>() < self.conf.torn;
+            // a torn write keeps a non-empty strict prefix, unless there is a single write
+            let cut = if maps.len() < 2 { 0 } else { rng.gen_range(1..maps.len()) };
+            let frac = rng.gen::<f64>();
+            (delay, crash, torn, cut, frac)
+        };
+        wait(delay);
+        if crash {
+            self.crash();
+            return Err(FaultErr::Crashed);
+        }
+        if let (true, Some(file)) = (torn, &self.conf.file) {
+            // the inner engine syncs all writes, but only a strict prefix of the appended bytes reaches the file
+            let before = len(file)?;
+            self.inner.wr_batch(txn, maps).map_err(FaultErr::External)?;
+            self.inner.done(txn, end).map_err(FaultErr::External)?;
+            let added = len(file)?.saturating_sub(before);
+            if added > 1 {
+                let keep = 1 + ((added - 1) as f64 * frac) as u64;
+                std::fs::OpenOptions::new().write(true).open(file)
+                    .and_then(|file| file.set_len(before + keep))
+                    .map_err(FaultErr::Io)?;
+            }
+            self.crash();
+            return Err(FaultErr::Crashed);
+        }
+        if torn {
+            // only a strict prefix reaches the inner engine, and the transaction is never done
+            let maps = maps.into_iter().take(cut).collect();
+            self.inner.wr_batch(txn, maps).map_err(FaultErr::External)?;
+            self.crash();
+            return Err(FaultErr::Crashed);
+        }
+        self.inner.wr_batch(txn, maps).map_err(FaultErr::External)?;
+        self.inner.done(txn, end).map_err(FaultErr::External)
+    }
+    fn open(&self, txn: &T) -> Result<(), Self::Err> {
+        self.check()?;
+        self.inner.open(txn).map_err(FaultErr::External)?;
+        self.unsynced.insert(txn.id(), vec![]);
+        Ok(())
+    }
+    fn rd(&self, prp: T::Prp) -> Result<T::Map, Self::Err> {
+        self.check()?;
+        self.inner.rd(prp).map_err(FaultErr::External)
+    }
+    fn wr(&self, txn: &T, map: T::Map) -> Result<(), Self::Err> {
+        self.check()?;
+        self.unsynced.entry(txn.id()).or_default().push(map);
+        Ok(())
+    }
+    // a batch is one call, so it fails or succeeds as a whole
+    fn rd_batch(&self, prps: Vec<T::Prp>) -> Result<Vec<T::Map>, Self::Err> {
+        self.check()?;
+        self.inner.rd_batch(prps).map_err(FaultErr::External)
+    }
+    fn wr_batch(&self, txn: &T, maps: Vec<T::Map>) -> Result<(), Self::Err> {
+        self.check()?;
+        self.unsynced.entry(txn.id()).or_default().extend(maps);
+        Ok(())
+    }
+    fn epoch(&self) -> u64 {
+        self.inner.epoch()
+    }
+    fn durable_epoch(&self) -> u64 {
+        self.inner.durable_epoch()
+    }
+    fn submit(&self, prp: &T::Prp) -> Option<u64> {
+        self.inner.submit(prp)
+    }
+    fn arrived(&self, ticket: u64) -> bool {
+        self.inner.arrived(ticket)
+    }
+}
+
+impl<V: Id, T: Tx<V>, D: RWDurable<V, T> + Dump<V>> Dump<V> for Fault<V, T, D>
+where
+    T::I: Eq + Hash,
+{
+    type Err = FaultErr<<D as Dump<V>>::Err>;
+    fn dump(&self, f: &mut dyn FnMut(&V::I, &V)) -> Result<(), Self::Err> {
+        if self.is_crashed() { return Err(FaultErr::Crashed) }
+        self.inner.dump(f).map_err(FaultErr::External)
+    }
+}
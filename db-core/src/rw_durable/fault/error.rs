@@ -0,0 +1,11 @@
+#[derive(Debug)]
+pub enum FaultErr<DErr> {
+    // an error returned by the inner engine
+    External(DErr),
+    // an injected error, the call had no effect and can be retried
+    Injected,
+    // the engine crashed, every call fails until it is recovered
+    Crashed,
+    // the file of the inner engine cannot be torn
+    Io(std::io::Error),
+}
@@ -0,0 +1,21 @@
+//! ## Fault Injection
+//!
+//! > Pillai, Thanumalayan Sankaranarayana, et al. "All file systems are not created equal: On the complexity of crafting crash-consistent applications." OSDI 2014.
+//!
+//! A wrapper around any durable engine that injects faults under a seeded schedule, for hardening protocols and recovery code.
+//! Writes of a transaction stay in the wrapper (unsynced) until the transaction is done, and are then handed to the inner engine, which is the sync point.
+//! Faults are returned errors on any call, simulated crashes that drop all unsynced state and fail every later call, torn writes that hand only a prefix of the writes to the inner engine before crashing, and delayed syncs.
+//! Given the file that the inner engine appends to, a torn write is torn at byte level instead: all writes are synced, then the file is cut inside the bytes appended for them, as a sector that never reached the disk.
+//! After a crash the inner engine is taken out and crashed too, so whatever it has not synced is lost, since dropping an engine usually flushes it (e.g. Wal::crash rather than drop).
+//! Recovery then opens a new one from its files, as after a power loss.
+
+// fault error
+mod error;
+// core fault injection wrapper
+mod engine;
+
+pub use error::*;
+pub use engine::*;
+
+#[cfg(test)]
+mod check; // crash and schedule checks
@@ -0,0 +1,96 @@
+use super::*;
+use crate::rw_durable::check_util::*;
+use crate::rw_durable::cow_btree::CowBTree;
+use crate::rw_durable::wal::Wal;
+use db_test::core_workload::int::unif::*;
+use std::ops::Bound;
+use typing::rw::*;
+use typing::tx::*;
+
+// index data items by value, big endian so bytes sort like numbers
+fn by_value(U64Tup(_, v): &U64Tup) -> Option<Vec<u8>> {
+    Some(v.to_be_bytes().to_vec())
+}
+
+fn put(k: u64, v: u64) -> U64Map {
+    U64Map(Some((k, Some(U64Tup(k, v)))))
+}
+
+fn keys<D: RWDurable<U64Tup, U64Txn>>(dur: &Indexed<U64Tup, U64Txn, D>, lo: u64, hi: u64) -> Vec<u64> {
+    let (lo, hi) = (lo.to_be_bytes(), hi.to_be_bytes());
+    let rows = dur.lookup(0, Bound::Included(&lo[..]), Bound::Excluded(&hi[..])).unwrap_or_else(|_| panic!("fail to look up"));
+    rows.into_iter().map(|(k, _)| k).collect()
+}
+
+#[test]
+fn is_index_maintained() {
+    let path = tmp_path("index");
+    let txn = txns().get();
+    {
+        let dur = Indexed::new(Wal::<U64Tup, U64Txn>::new(&path).unwrap()).with_index(by_value);
+        dur.open(&txn).unwrap();
+        for k in 0..100 {
+            dur.wr(&txn, put(k, k % 10)).unwrap();
+        }
+        dur.done(&txn, End::Ready).unwrap();
+        assert!(keys(&dur, 3, 4) == (3..100).step_by(10).collect::<Vec<_>>());
+        dur.open(&txn).unwrap();
+        dur.wr(&txn, put(3, 50)).unwrap();
+        dur.wr(&txn, U64Map(Some((13, None)))).unwrap();
+        dur.done(&txn, End::Ready).unwrap();
+        assert!(keys(&dur, 3, 4) == (23..100).step_by(10).collect::<Vec<_>>());
+        // rows come in value order
+        assert!(keys(&dur, 9, 51) == (9..100).step_by(10).chain([3]).collect::<Vec<_>>());
+    }
+    // an index over a recovered engine is loaded from its rows
+    let wal = Wal::<U64Tup, U64Txn>::new(&path).unwrap();
+    let rows = (0..100).filter_map(|k| wal.rd(U64Prp(k)).unwrap().0.and_then(|(_, v)| v)).collect::<Vec<_>>();
+    let dur = Indexed::new(wal).with_index(by_value);
+    dur.load(rows);
+    assert!(keys(&dur, 3, 4) == (23..100).step_by(10).collect::<Vec<_>>());
+    assert!(keys(&dur, 0, u64::MAX).len() == 99);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn is_abort_discarded() {
+    let path = tmp_path("index");
+    let mut workload = txns();
+    let (txn_a, txn_b) = (workload.get(), workload.get());
+    let dur = Indexed::new(CowBTree::<U64Tup, U64Txn>::new(&path).unwrap()).with_index(by_value);
+    dur.open(&txn_a).unwrap();
+    for k in 0..10 {
+        dur.wr(&txn_a, put(k, 1)).unwrap();
+    }
+    dur.done(&txn_a, End::Ready).unwrap();
+    dur.open(&txn_b).unwrap();
+    dur.wr(&txn_b, put(4, 2)).unwrap();
+    // uncommitted writes are not indexed, so a row is still found under its committed key
+    assert!(keys(&dur, 2, 3).is_empty());
+    assert!(keys(&dur, 1, 2) == (0..10).collect::<Vec<_>>());
+    dur.done(&txn_b, End::Abort).unwrap();
+    assert!(keys(&dur, 1, 2) == (0..10).collect::<Vec<_>>());
+    assert!(keys(&dur, 2, 3).is_empty());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn is_batch_indexed() {
+    let path = tmp_path("index");
+    let mut workload = txns();
+    let (txn_a, txn_b) = (workload.get(), workload.get());
+    let dur = Indexed::new(CowBTree::<U64Tup, U64Txn>::new(&path).unwrap()).with_index(by_value);
+    dur.open(&txn_a).unwrap();
+    dur.wr_batch(&txn_a, (0..10).map(|k| put(k, k % 2)).collect()).unwrap();
+    dur.done(&txn_a, End::Ready).unwrap();
+    assert!(keys(&dur, 1, 2) == (1..10).step_by(2).collect::<Vec<_>>());
+    let maps = dur.rd_batch(vec![U64Prp(3), U64Prp(4), U64Prp(10)]).unwrap();
+    assert!(maps.iter().map(|U64Map(map)| map.is_some()).collect::<Vec<_>>() == vec![true, true, false]);
+    // no map of an aborted batch is indexed
+    dur.open(&txn_b).unwrap();
+    dur.wr_batch(&txn_b, vec![put(3, 2), put(4, 2)]).unwrap();
+    dur.done(&txn_b, End::Abort).unwrap();
+    assert!(keys(&dur, 2, 3).is_empty());
+    assert!(keys(&dur, 0, 2).len() == 10);
+    std::fs::remove_file(&path).unwrap();
+}
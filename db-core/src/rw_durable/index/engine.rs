@@ -0,0 +1,153 @@
+use super::tree::*;
+use crate::rw_durable::snapshot::Dump;
+use crate::rw_durable::wal::Mapping;
+use dashmap::DashMap;
+use parking_lot::RwLock;
+use std::hash::Hash;
+use std::marker::PhantomData;
+use std::ops::{Bound, RangeBounds};
+use typing::constraint::*;
+use typing::rw::*;
+use typing::tx::*;
+
+pub struct Indexed<V: Id, T: Tx<V>, D: RWDurable<V, T>>
+where
+    V::I: Ord + Hash + Clone,
+    T::I: Eq + Hash,
+{
+    inner: D,
+    indexes: Vec<RwLock<Index<V>>>,
+    // writes of open transactions, indexed once the inner engine commits them
+    touched: DashMap<T::I, Mapping<V>>,
+    phant: PhantomData<T>,
+}
+
+impl<V: Id, T: Tx<V>, D: RWDurable<V, T>> Indexed<V, T, D>
+where
+    V: Clone,
+    V::I: Ord + Hash + Clone,
+    T::I: Eq + Hash,
+    T::Prp: MaybeIndexer<V::I>,
+    T::Map: Mapper<V::I, V>,
+{
+    /// wrap an engine without indexes
+    pub fn new(inner: D) -> Self {
+        Indexed { inner, indexes: vec![], touched: DashMap::new(), phant: PhantomData }
+    }
+    /// declare the next index, indexes are numbered from 0 in declaration order
+    pub fn with_index(mut self, key: impl Fn(&V) -> Option<Vec<u8>> + Send + Sync + 'static) -> Self {
+        self.indexes.push(RwLock::new(Index::new(Box::new(key))));
+        self
+    }
+    /// index rows already held by the inner engine, e.g. after recovery, before any transaction runs
+    pub fn load(&self, rows: impl IntoIterator<Item = V>) {
+        for v in rows {
+            self.set(&v.id(), Some(&v));
+        }
+    }
+    pub fn into_inner(self) -> D {
+        self.inner
+    }
+    // index the value of a primary key in every index
+    fn set(&self, id: &V::I, v: Option<&V>) {
+        for index in &self.indexes {
+            index.write().set(id, v);
+        }
+    }
+    // keep the writes of a map as index deltas of its transaction
+    fn touch(&self, txn: &T, map: &T::Map) {
+        self.touched.entry(txn.id()).or_default().extend(map.into_mapping());
+    }
+    // read the value of a primary key from the inner engine
+    fn get(&self, id: &V::I) -> Result<Option<V>, D::Err> {
+        let map = self.inner.rd(MaybeIndexer::from_indexer(std::iter::once(id.clone())))?;
+        let v = map.into_mapping().find(|(i, _)| i == id).and_then(|(_, v)| v);
+        Ok(v)
+    }
+    /// rows with secondary keys of the n-th index in a range, in secondary key order
+    pub fn lookup(&self, n: usize, lo: Bound<&[u8]>, hi: Bound<&[u8]>) -> Result<Vec<(V::I, V)>, D::Err> {
+        let index = &self.indexes[n];
+        let ids = index.read().range(lo, hi);
+        let mut rows = vec![];
+        for id in ids {
+            let Some(v) = self.get(&id)? else { continue };
+            // an entry may lag behind a commit in flight, and point to a row that does not match
+            if index.read().key(&v).is_some_and(|k| RangeBounds::<[u8]>::contains(&(lo, hi), k.as_slice())) {
+                rows.push((id, v));
+            }
+        }
+        Ok(rows)
+    }
+}
+
+impl<V: Id, T: Tx<V>, D: RWDurable<V, T>> RWDurable<V, T> for Indexed<V, T, D>
+where
+    V: Clone,
+    V::I: Ord + Hash + Clone,
+    T::I: Eq + Hash,
+    T::Prp: MaybeIndexer<V::I> + MaybeLookup,
+    T::Map: Mapper<V::I, V>,
+{
+    type Err = D::Err;
+    fn done(&self, txn: &T, end: End) -> Result<(), Self::Err> {
+        // deltas of an aborted or failed transaction are dropped without touching the indexes
+        let deltas = self.touched.remove(&txn.id()).map(|(_, deltas)| deltas).unwrap_or_default();
+        self.inner.done(txn, end)?;
+        if let End::Ready = end {
+            for (i, v) in deltas {
+                self.set(&i, v.as_ref());
+            }
+        }
+        Ok(())
+    }
+    fn open(&self, txn: &T) -> Result<(), Self::Err> {
+        self.inner.open(txn)?;
+        self.touched.insert(txn.id(), vec![]);
+        Ok(())
+    }
+    fn rd(&self, prp: T::Prp) -> Result<T::Map, Self::Err> {
+        let Some((n, lo, hi)) = prp.tryc_lookup() else { return self.inner.rd(prp) };
+        let rows = self.lookup(n, lo.as_ref().map(Vec::as_slice), hi.as_ref().map(Vec::as_slice))?;
+        Ok(Mapper::from_mapping(rows.into_iter().map(|(i, v)| (i, Some(v)))))
+    }
+    fn wr(&self, txn: &T, map: T::Map) -> Result<(), Self::Err> {
+        self.touch(txn, &map);
+        self.inner.wr(txn, map)
+    }
+    // lookups are served by the indexes, so a batch goes to the inner engine only without them
+    fn rd_batch(&self, prps: Vec<T::Prp>) -> Result<Vec<T::Map>, Self::Err> {
+        if prps.iter().any(|prp| prp.tryc_lookup().is_some()) {
+            return prps.into_iter().map(|prp| self.rd(prp)).collect();
+        }
+        self.inner.rd_batch(prps)
+    }
+    fn wr_batch(&self, txn: &T, maps: Vec<T::Map>) -> Result<(), Self::Err> {
+        for map in &maps {
+            self.touch(txn, map);
+        }
+        self.inner.wr_batch(txn, maps)
+    }
+    fn epoch(&self) -> u64 {
+        self.inner.epoch()
+    }
+    fn durable_epoch(&self) -> u64 {
+        self.inner.durable_epoch()
+    }
+    fn submit(&self, prp: &T::Prp) -> Option<u64> {
+        self.inner.submit(prp)
+    }
+    fn arrived(&self, ticket: u64) -> bool {
+        self.inner.arrived(ticket)
+    }
+}
+
+impl<V: Id, T: Tx<V>, D: RWDurable<V, T> + Dump<V>> Dump<V> for Indexed<V, T, D>
+where
+    V::I: Ord + Hash + Clone,
+    T::I: Eq + Hash,
+{
+    type Err = <D as Dump<V>>::Err;
+    fn dump(&self, f: &mut dyn FnMut(&V::I, &V)) -> Result<(), Self::Err> {
+        self.inner.dump(f)
+    }
+}
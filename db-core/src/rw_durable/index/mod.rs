@@ -0,0 +1,21 @@
+//! ## Secondary Index
+//!
+//! > Graefe, Goetz. "Modern B-tree techniques." Foundations and Trends in Databases 3.4 (2011), Section 4.
+//!
+//! A wrapper around any durable engine that keeps ordered secondary indexes, declared as extractor functions over data items.
+//! An extractor maps a data item to a secondary key (bytes, compared in order), or to None to leave it out of the index.
+//! Written items are kept as index deltas of their transaction, and applied once the inner engine has committed it, so lookups never see uncommitted writes.
+//! A reverse map from primary keys drops stale entries, and the deltas of an aborted transaction are discarded.
+//! A proposition with a lookup (MaybeLookup) is answered by a range scan over the index, then a read of each primary key, e.g. customers by warehouse, district and last name in tpc-c.
+//! Rows whose current secondary key falls outside the range are dropped, so a lagging index never returns wrong rows.
+
+// an ordered index over secondary keys
+mod tree;
+// core indexing wrapper
+mod engine;
+
+pub use tree::*;
+pub use engine::*;
+
+#[cfg(test)]
+mod check; // index maintenance and lookup checks
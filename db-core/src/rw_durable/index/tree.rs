@@ -0,0 +1,45 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::hash::Hash;
+use std::ops::Bound;
+use typing::constraint::*;
+
+/// an extractor from a data item to its secondary key, None leaves it out of the index
+pub type Extractor<V> = Box<dyn Fn(&V) -> Option<Vec<u8>> + Send + Sync>;
+
+/// an ordered index from secondary keys to primary keys
+pub struct Index<V: Id> {
+    key: Extractor<V>,
+    // primary keys of each secondary key, a secondary key is not unique
+    tree: BTreeMap<Vec<u8>, BTreeSet<V::I>>,
+    // the secondary key of each indexed primary key
+    rev: HashMap<V::I, Vec<u8>>,
+}
+
+impl<V: Id> Index<V>
+where
+    V::I: Ord + Hash + Clone,
+{
+    pub fn new(key: Extractor<V>) -> Self {
+        Index { key, tree: BTreeMap::new(), rev: HashMap::new() }
+    }
+    /// the secondary key of a data item
+    pub fn key(&self, v: &V) -> Option<Vec<u8>> {
+        (self.key)(v)
+    }
+    /// index the current value of a primary key, None if it is removed
+    pub fn set(&mut self, id: &V::I, v: Option<&V>) {
+        if let Some(old) = self.rev.remove(id) {
+            let ids = self.tree.get_mut(&old).expect("reverse map and tree should agree");
+            ids.remove(id);
+            if ids.is_empty() { self.tree.remove(&old); }
+        }
+        if let Some(new) = v.and_then(|v| self.key(v)) {
+            self.tree.entry(new.clone()).or_default().insert(id.clone());
+            self.rev.insert(id.clone(), new);
+        }
+    }
+    /// primary keys with secondary keys in a range, in secondary key order
+    pub fn range(&self, lo: Bound<&[u8]>, hi: Bound<&[u8]>) -> Vec<V::I> {
+        self.tree.range::<[u8], _>((lo, hi)).flat_map(|(_, ids)| ids.iter().cloned()).collect()
+    }
+}
@@ -0,0 +1,69 @@
+use super::*;
+use crate::rw_durable::check_util::*;
+use crate::rw_durable::null::Null;
+use db_test::core_workload::int::unif::*;
+use rand::SeedableRng;
+use rand_xoshiro::Xoshiro256PlusPlus;
+use std::time::{Duration, Instant};
+use typing::rw::*;
+
+fn null() -> Null<U64Tup, U64Txn> {
+    Null::new(0, 0, false)
+}
+
+fn ms(n: u64) -> Duration {
+    Duration::from_millis(n)
+}
+
+#[test]
+fn is_distribution_sampled() {
+    let draw = |dist: &Dist, seed| {
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(seed);
+        (0..10001).map(|_| dist.sample(&mut rng)).collect::<Vec<_>>()
+    };
+    let uniform = Dist::Uniform(ms(1), ms(3));
+    assert!(draw(&uniform, 1) == draw(&uniform, 1));
+    assert!(draw(&uniform, 1).iter().all(|lat| ms(1) <= *lat && *lat <= ms(3)));
+    let mut lats = draw(&Dist::LogNormal(ms(10), 0.5), 1);
+    lats.sort();
+    assert!(ms(9) < lats[5000] && lats[5000] < ms(11));
+    assert!(lats[9900] > ms(25));
+    let path = tmp_path("latency");
+    std::fs::write(&path, "# ssd read latencies in microseconds\n80\n\n120.5\n").unwrap();
+    let trace = Dist::Trace(Trace::load(&path).unwrap());
+    let lats = draw(&trace, 1);
+    assert!(lats[0] == Duration::from_micros(80) && lats[1] == Duration::from_nanos(120500) && lats[2] == lats[0]);
+    // a latency that is not a finite non-negative number is invalid data, not a panic
+    for bad in ["-5\n", "NaN\n", "inf\n", "1e300\n"] {
+        std::fs::write(&path, bad).unwrap();
+        assert!(Trace::load(&path).err().map(|e| e.kind()) == Some(std::io::ErrorKind::InvalidData));
+    }
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn is_delay_timed() {
+    let dur = Latency::new(null(), 0).with_rd(Dist::Fixed(ms(2)));
+    let start = Instant::now();
+    for k in 0..20 { dur.rd(U64Prp(k)).unwrap(); }
+    let elapsed = start.elapsed();
+    assert!(ms(40) <= elapsed && elapsed < ms(400));
+    assert!(dur.elapsed().is_none());
+    // a virtual clock only counts the delays
+    let dur = Latency::new(null(), 0).with_rd(Dist::Fixed(ms(2))).with_virtual_clock();
+    let start = Instant::now();
+    for k in 0..20 { dur.rd(U64Prp(k)).unwrap(); }
+    assert!(start.elapsed() < ms(40));
+    assert!(dur.elapsed() == Some(ms(40)));
+}
+
+#[test]
+fn is_batch_one_round_trip() {
+    let dur = Latency::new(null(), 0).with_rd(Dist::Fixed(ms(2))).with_wr(Dist::Fixed(ms(3))).with_virtual_clock();
+    let txn = txns().get();
+    dur.wr_batch(&txn, (0..20).map(|k| U64Map(Some((k, Some(U64Tup(k, k)))))).collect()).unwrap();
+    let maps = dur.rd_batch((0..20).map(U64Prp).collect()).unwrap();
+    let vals = maps.into_iter().map(|U64Map(map)| map.and_then(|(_, v)| v).map(|U64Tup(_, v)| v)).collect::<Vec<_>>();
+    assert!(vals == (0..20).map(Some).collect::<Vec<_>>());
+    assert!(dur.elapsed() == Some(ms(5)));
+}
@@ -0,0 +1,69 @@
+use rand::Rng;
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering::*};
+use std::time::Duration;
+
+/// a latency distribution
+#[derive(Debug)]
+pub enum Dist {
+    /// no delay
+    Zero,
+    /// always the same latency
+    Fixed(Duration),
+    /// uniform between two latencies, both included
+    Uniform(Duration, Duration),
+    /// lognormal with a median and the standard deviation of its logarithm
+    LogNormal(Duration, f64),
+    /// measured latencies replayed in order, wrapping around at the end
+    Trace(Trace),
+}
+
+/// a replayed trace of latencies, shared by all callers
+#[derive(Debug)]
+pub struct Trace {
+    lats: Vec<Duration>,
+    next: AtomicUsize,
+}
+
+impl Trace {
+    pub fn new(lats: Vec<Duration>) -> Self {
+        assert!(!lats.is_empty(), "a latency trace should not be empty");
+        Trace { lats, next: AtomicUsize::new(0) }
+    }
+    /// load a trace with one latency in microseconds per line, blank lines and lines starting with # are skipped
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut lats = vec![];
+        for line in std::fs::read_to_string(path)?.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') { continue }
+            let us = line.parse::<f64>().map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            // Duration::from_secs_f64 panics on negative, non-finite or overflowing values
+            let lat = Duration::try_from_secs_f64(us / 1e6)
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, format!("invalid latency {line}")))?;
+            lats.push(lat);
+        }
+        if lats.is_empty() { return Err(io::Error::new(io::ErrorKind::InvalidData, "empty latency trace")) }
+        Ok(Trace::new(lats))
+    }
+    fn next(&self) -> Duration {
+        self.lats[self.next.fetch_add(1, Relaxed) % self.lats.len()]
+    }
+}
+
+impl Dist {
+    /// draw a latency
+    pub fn sample(&self, rng: &mut impl Rng) -> Duration {
+        match self {
+            Dist::Zero => Duration::ZERO,
+            Dist::Fixed(lat) => *lat,
+            Dist::Uniform(lo, hi) => rng.gen_range(*lo..=*hi),
+            Dist::LogNormal(median, sigma) => {
+                // box-muller transform of two uniform draws into a standard normal one
+                let (u, v) = (1.0 - rng.gen::<f64>(), rng.gen::<f64>());
+                let z = (-2.0 * u.ln()).sqrt() * (std::f64::consts::TAU * v).cos();
+                median.mul_f64((sigma * z).exp())
+            }
+            Dist::Trace(trace) => trace.next(),
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use super::dist::*;
+use crate::rw_durable::snapshot::Dump;
+use parking_lot::Mutex;
+use rand::SeedableRng;
+use rand_xoshiro::Xoshiro256PlusPlus;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicU64, Ordering::*};
+use std::time::{Duration, Instant};
+use typing::constraint::*;
+use typing::rw::*;
+use typing::tx::*;
+
+// below this, a wait spins instead of sleeping, as sleeps overshoot by about a scheduler tick
+const SPIN: Duration = Duration::from_micros(100);
+
+// wait until a deadline, sleeping for the bulk and spinning for the rest
+pub(crate) fn wait(lat: Duration) {
+    let deadline = Instant::now() + lat;
+    if lat > SPIN { std::thread::sleep(lat - SPIN) }
+    while Instant::now() < deadline { std::hint::spin_loop() }
+}
+
+pub struct Latency<V, T: Tx<V>, D: RWDurable<V, T>> {
+    inner: D,
+    rd: Dist,
+    wr: Dist,
+    done: Dist,
+    rng: Mutex<Xoshiro256PlusPlus>,
+    // total delay in nanoseconds, only accumulated on a virtual clock
+    clock: Option<AtomicU64>,
+    phant: PhantomData<(V, T)>,
+}
+
+impl<V, T: Tx<V>, D: RWDurable<V, T>> Latency<V, T, D> {
+    /// wrap an engine without delays, the seed drives all random distributions
+    pub fn new(inner: D, seed: u64) -> Self {
+        let rng = Mutex::new(Xoshiro256PlusPlus::seed_from_u64(seed));
+        Latency { inner, rd: Dist::Zero, wr: Dist::Zero, done: Dist::Zero, rng, clock: None, phant: PhantomData }
+    }
+    pub fn with_rd(mut self, dist: Dist) -> Self {
+        self.rd = dist;
+        self
+    }
+    pub fn with_wr(mut self, dist: Dist) -> Self {
+        self.wr = dist;
+        self
+    }
+    pub fn with_done(mut self, dist: Dist) -> Self {
+        self.done = dist;
+        self
+    }
+    /// accumulate delays on a virtual clock instead of waiting
+    pub fn with_virtual_clock(mut self) -> Self {
+        self.clock = Some(AtomicU64::new(0));
+        self
+    }
+    /// the total delay on the virtual clock, None if delays are real waits
+    pub fn elapsed(&self) -> Option<Duration> {
+        self.clock.as_ref().map(|clock| Duration::from_nanos(clock.load(Acquire)))
+    }
+    pub fn into_inner(self) -> D {
+        self.inner
+    }
+    fn delay(&self, dist: &Dist) {
+        if let Dist::Zero = dist { return }
+        let lat = dist.sample(&mut *self.rng.lock());
+        match &self.clock {
+            Some(clock) => { clock.fetch_add(lat.as_nanos() as u64, AcqRel); },
+            None => wait(lat),
+        }
+    }
+}
+
+impl<V, T: Tx<V>, D: RWDurable<V, T>> RWDurable<V, T> for Latency<V, T, D> {
+    type Err = D::Err;
+    fn done(&self, txn: &T, end: End) -> Result<(), Self::Err> {
+        self.delay(&self.done);
+        self.inner.done(txn, end)
+    }
+    fn open(&self, txn: &T) -> Result<(), Self::Err> {
+        self.inner.open(txn)
+    }
+    fn rd(&self, prp: T::Prp) -> Result<T::Map, Self::Err> {
+        self.delay(&self.rd);
+        self.inner.rd(prp)
+    }
+    fn wr(&self, txn: &T, map: T::Map) -> Result<(), Self::Err> {
+        self.delay(&self.wr);
+        self.inner.wr(txn, map)
+    }
+    // a batch is one round trip
+    fn rd_batch(&self, prps: Vec<T::Prp>) -> Result<Vec<T::Map>, Self::Err> {
+        self.delay(&self.rd);
+        self.inner.rd_batch(prps)
+    }
+    fn wr_batch(&self, txn: &T, maps: Vec<T::Map>) -> Result<(), Self::Err> {
+        self.delay(&self.wr);
+        self.inner.wr_batch(txn, maps)
+    }
+    fn epoch(&self) -> u64 {
+        self.inner.epoch()
+    }
+    fn durable_epoch(&self) -> u64 {
+        self.inner.durable_epoch()
+    }
+    fn submit(&self, prp: &T::Prp) -> Option<u64> {
+        self.inner.submit(prp)
+    }
+    fn arrived(&self, ticket: u64) -> bool {
+        self.inner.arrived(ticket)
+    }
+}
+
+impl<V: Id, T: Tx<V>, D: RWDurable<V, T> + Dump<V>> Dump<V> for Latency<V, T, D> {
+    type Err = <D as Dump<V>>::Err;
+    fn dump(&self, f: &mut dyn FnMut(&V::I, &V)) -> Result<(), Self::Err> {
+        self.inner.dump(f)
+    }
+}
@@ -0,0 +1,19 @@
+//! ## Latency Model
+//!
+//! > Dean, Jeffrey, and Luiz André Barroso. "The tail at scale." Communications of the ACM 56.2 (2013): 74-80.
+//!
+//! A wrapper around any durable engine that delays rd, wr and done by latencies drawn from a seeded distribution.
+//! A distribution is fixed, uniform, lognormal (a common fit for device latencies, with a long tail), or a trace of measured latencies replayed in order.
+//! Delays are timed waits against a monotonic clock, sleeping for the bulk and spinning for the rest, so they map to real time regardless of scheduler load.
+//! Alternatively a virtual clock only accumulates the delays, so a benchmark can report device time without waiting for it.
+
+// latency distributions and traces
+mod dist;
+// core latency wrapper
+mod engine;
+
+pub use dist::*;
+pub use engine::*;
+
+#[cfg(test)]
+mod check; // distribution and delay checks
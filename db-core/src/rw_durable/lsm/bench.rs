@@ -0,0 +1,82 @@
+const N_TXN: u64 = 20000;
+// write heavy: few reads, many writes
+const RWAC: (u64, u64, u64, u64) = (1, 15, 1, 4);
+const VRNG: u64 = 200000;
+const SEED: u64 = 1145141919810;
+const NR_WORKERS: usize = 4;
+
+use crate::rw_durable::check_util::tmp_path;
+use db_test::core_workload::int::unif::*;
+use typing::tx::*;
+
+// run a write heavy workload to completion, return the elapsed seconds
+fn write_heavy(mut service: impl TxService<U64Txn, U64Tup>) -> f64 {
+    use std::time::*;
+    let mut workload = U64Gen::new(SEED, RWAC, VRNG);
+    service.start().unwrap_or_else(|_| panic!("fail to start service"));
+    let start_time = SystemTime::now();
+    let mut last = 0;
+    for _ in 0..=N_TXN {
+        let txn = workload.get();
+        if txn.id() == 0 { continue; }
+        last = txn.id();
+        service.put(txn).unwrap_or_else(|_| panic!("fail to put transaction {last}"));
+    }
+    for i in 1..=last {
+        while service.get(i).is_err() {
+            std::thread::sleep(Duration::from_nanos(10));
+        }
+    }
+    let elapsed = start_time.elapsed().unwrap().as_secs_f64();
+    service.close().unwrap_or_else(|_| panic!("fail to close service"));
+    elapsed
+}
+
+#[test]
+fn run_u64_write_heavy() {
+    // find this test easily
+    println!("{}:{}", file!(), line!());
+    // dependencies
+    use crate::rw_control::Serial;
+    use crate::rw_durable::btree::*;
+    use crate::tx_service::m_thread::*;
+    use super::*;
+    // durability control, lsm in this module
+    let path = tmp_path("lsm");
+    let dur = Lsm::<U64Tup, U64Txn>::new(&path, LsmConf { memtable: 64 << 10, ..LsmConf::default() }).unwrap();
+    let srv = MThreadService::new(NR_WORKERS, |x| x, Serial::<U64Txn, U64Tup>::new(), dur);
+    let lsm = write_heavy(srv);
+    std::fs::remove_dir_all(&path).unwrap();
+    // durability control, b+tree as the baseline
+    let path = tmp_path("btree");
+    let dur = BTree::<U64Tup, U64Txn>::new(&path, 256).unwrap();
+    let srv = MThreadService::new(NR_WORKERS, |x| x, Serial::<U64Txn, U64Tup>::new(), dur);
+    let btree = write_heavy(srv);
+    std::fs::remove_file(&path).unwrap();
+    println!("lsm   elapsed {lsm:.4} (sec) throughput {:.4} (txn/sec)", N_TXN as f64 / lsm);
+    println!("btree elapsed {btree:.4} (sec) throughput {:.4} (txn/sec)", N_TXN as f64 / btree);
+}
+
+#[test]
+fn run_u64_compression() {
+    // find this test easily
+    println!("{}:{}", file!(), line!());
+    // dependencies
+    use crate::rw_control::Serial;
+    use crate::rw_durable::block::*;
+    use crate::tx_service::m_thread::*;
+    use super::*;
+    // the same workload with each codec, storage size against elapsed time
+    let codecs: [(&str, &'static dyn Compressor); 3] = [("raw", &Raw), ("rle", &Rle), ("lz", &Lz)];
+    for (tag, compress) in codecs {
+        let path = tmp_path(tag);
+        let dur = Lsm::<U64Tup, U64Txn>::new(&path, LsmConf { memtable: 64 << 10, compress, ..LsmConf::default() }).unwrap();
+        let srv = MThreadService::new(NR_WORKERS, |x| x, Serial::<U64Txn, U64Tup>::new(), dur);
+        let elapsed = write_heavy(srv);
+        let size: u64 = std::fs::read_dir(&path).unwrap().map(|e| e.unwrap().path())
+            .filter(|p| p.extension().is_some_and(|e| e == "sst"))
+            .map(|p| std::fs::metadata(p).unwrap().len()).sum();
+        std::fs::remove_dir_all(&path).unwrap();
+        println!("{tag:<4} elapsed {elapsed:.4} (sec) throughput {:.4} (txn/sec) sstables {size} (bytes)", N_TXN as f64 / elapsed);
+    }
+}
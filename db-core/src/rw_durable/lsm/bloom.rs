@@ -0,0 +1,50 @@
+use crate::utilities::*;
+use typing::constraint::*;
+
+// about 1% false positive rate with 10 bits per key and 7 probes
+const BITS_PER_KEY: usize = 10;
+const PROBES: u32 = 7;
+
+/// a bloom filter over encoded keys
+pub(super) struct Bloom {
+    bits: Vec<u64>,
+}
+
+// two hashes for double hashing
+fn hashes(key: &[u8]) -> (u64, u64) {
+    let h1 = crc32c(key) as u64;
+    let mut salted = key.to_vec();
+    salted.push(0xa5);
+    (h1, crc32c(&salted) as u64 | 1)
+}
+
+impl Bloom {
+    pub fn new(nkey: usize) -> Self {
+        Bloom { bits: vec![0; (nkey * BITS_PER_KEY).div_ceil(64).max(1)] }
+    }
+    fn probes(&self, key: &[u8]) -> impl Iterator<Item = usize> {
+        let m = self.bits.len() as u64 * 64;
+        let (h1, h2) = hashes(key);
+        (0..PROBES as u64).map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % m) as usize)
+    }
+    pub fn add(&mut self, key: &[u8]) {
+        for b in self.probes(key).collect::<Vec<_>>() {
+            self.bits[b / 64] |= 1 << (b % 64);
+        }
+    }
+    /// false if the key is definitely absent
+    pub fn may_have(&self, key: &[u8]) -> bool {
+        self.probes(key).all(|b| self.bits[b / 64] & (1 << (b % 64)) != 0)
+    }
+    /// [words: u32] [word: u64]*
+    pub fn put(&self, buf: &mut Vec<u8>) {
+        buf.extend((self.bits.len() as u32).to_le_bytes());
+        for w in &self.bits { buf.extend(w.to_le_bytes()) }
+    }
+    pub fn get(buf: &mut &[u8]) -> Option<Self> {
+        let n = get_u32(buf)?;
+        let bits = (0..n).map(|_| get_u64(buf)).collect::<Option<Vec<_>>>()?;
+        if bits.is_empty() { return None }
+        Some(Bloom { bits })
+    }
+}
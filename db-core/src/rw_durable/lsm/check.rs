@@ -0,0 +1,156 @@
+use super::*;
+use crate::rw_durable::block::*;
+use crate::rw_durable::check_util::*;
+use db_test::core_workload::int::unif::*;
+use std::ops::Bound;
+use typing::rw::*;
+use typing::tx::*;
+
+// a tiny configuration, so a few thousand keys go through several flushes and compactions
+fn tiny() -> LsmConf {
+    LsmConf { memtable: 1 << 10, l0_files: 2, level_base: 4 << 10, fanout: 2, file: 2 << 10, block: 256, compress: &Raw }
+}
+
+// keys in a pseudo random order
+fn shuffled(n: u64) -> impl Iterator<Item = u64> {
+    (0..n).map(move |i| i * 7919 % n)
+}
+
+// write rows in transactions of a few keys each, so memtable is flushed many times
+fn commit(lsm: &Lsm<U64Tup, U64Txn>, rows: impl Iterator<Item = (u64, Option<U64Tup>)>) {
+    let txn = txns().get();
+    for chunk in rows.collect::<Vec<_>>().chunks(32) {
+        for (k, v) in chunk {
+            lsm.wr(&txn, U64Map(Some((*k, *v)))).unwrap();
+        }
+        lsm.done(&txn, End::Ready).unwrap();
+    }
+}
+
+fn value(lsm: &Lsm<U64Tup, U64Txn>, k: u64) -> Option<u64> {
+    let U64Map(map) = lsm.rd(U64Prp(k)).unwrap();
+    map.and_then(|(_, v)| v).map(|U64Tup(_, v)| v)
+}
+
+#[test]
+fn is_recovered_from_log() {
+    let path = tmp_path("lsm");
+    let txn = txns().get();
+    {
+        // memtable is never flushed, everything comes back from the log
+        let lsm = Lsm::<U64Tup, U64Txn>::new(&path, LsmConf::default()).unwrap();
+        for k in 0..100 {
+            lsm.wr(&txn, U64Map(Some((k, Some(U64Tup(k, k + 1)))))).unwrap();
+        }
+        for k in (0..100).step_by(2) {
+            lsm.wr(&txn, U64Map(Some((k, None)))).unwrap();
+        }
+        lsm.done(&txn, End::Ready).unwrap();
+        assert!(lsm.shape() == vec![0]);
+    }
+    let lsm = Lsm::<U64Tup, U64Txn>::new(&path, LsmConf::default()).unwrap();
+    for k in 0..100 {
+        assert!(value(&lsm, k) == if k % 2 == 0 { None } else { Some(k + 1) });
+    }
+    drop(lsm);
+    std::fs::remove_dir_all(&path).unwrap();
+}
+
+#[test]
+fn is_compacted_with_tombstones() {
+    let path = tmp_path("lsm");
+    {
+        let lsm = Lsm::<U64Tup, U64Txn>::new(&path, tiny()).unwrap();
+        for round in 0..3 {
+            commit(&lsm, shuffled(2000).map(|k| (k, Some(U64Tup(k, k * 10 + round)))));
+        }
+        commit(&lsm, (0..2000).step_by(3).map(|k| (k, None)));
+        // data has been pushed below level 0
+        assert!(lsm.shape().len() > 2);
+        let mut keys = vec![];
+        lsm.scan(Bound::Included(&500), Bound::Excluded(&700), |k, v| {
+            assert!(v.1 == k * 10 + 2);
+            keys.push(*k);
+        }).unwrap();
+        assert!(keys == (500..700).filter(|k| k % 3 != 0).collect::<Vec<_>>());
+    }
+    let lsm = Lsm::<U64Tup, U64Txn>::new(&path, tiny()).unwrap();
+    for k in 0..2000 {
+        assert!(value(&lsm, k) == if k % 3 == 0 { None } else { Some(k * 10 + 2) });
+    }
+    let mut count = 0;
+    lsm.scan(Bound::Unbounded, Bound::Unbounded, |_, _| count += 1).unwrap();
+    assert!(count == (0..2000).filter(|k| k % 3 != 0).count());
+    // no orphan sstables are left behind
+    let files = std::fs::read_dir(&path).unwrap()
+        .filter(|e| e.as_ref().unwrap().file_name().to_string_lossy().ends_with(".sst"))
+        .count();
+    assert!(files == lsm.shape().iter().sum::<usize>());
+    drop(lsm);
+    std::fs::remove_dir_all(&path).unwrap();
+}
+
+#[test]
+fn is_block_compressed_and_verified() {
+    let (raw, lz) = (tmp_path("lsm"), tmp_path("lsm"));
+    let sst_size = |path: &std::path::Path| -> u64 {
+        std::fs::read_dir(path).unwrap().map(|e| e.unwrap().path())
+            .filter(|p| p.extension().is_some_and(|e| e == "sst"))
+            .map(|p| std::fs::metadata(p).unwrap().len()).sum()
+    };
+    for (path, compress) in [(&raw, &Raw as &'static dyn Compressor), (&lz, &Lz)] {
+        let lsm = Lsm::<U64Tup, U64Txn>::new(path, LsmConf { compress, ..tiny() }).unwrap();
+        commit(&lsm, shuffled(3000).map(|k| (k, Some(U64Tup(k, k % 10)))));
+    }
+    assert!(sst_size(&lz) * 3 < sst_size(&raw) * 2);
+    // a built-in codec is read whatever the configured one is
+    let lsm = Lsm::<U64Tup, U64Txn>::new(&lz, tiny()).unwrap();
+    assert!((0..3000).all(|k| value(&lsm, k) == Some(k % 10)));
+    drop(lsm);
+    // a flipped bit in a data block is an error on read
+    let sst = std::fs::read_dir(&lz).unwrap().map(|e| e.unwrap().path())
+        .find(|p| p.extension().is_some_and(|e| e == "sst")).unwrap();
+    let mut bytes = std::fs::read(&sst).unwrap();
+    bytes[20] ^= 1;
+    std::fs::write(&sst, bytes).unwrap();
+    let lsm = Lsm::<U64Tup, U64Txn>::new(&lz, tiny()).unwrap();
+    let errs = (0..3000).filter(|k| matches!(lsm.rd(U64Prp(*k)), Err(LsmErr::Corrupt(_)))).count();
+    assert!(errs > 0);
+    std::fs::remove_dir_all(&raw).unwrap();
+    std::fs::remove_dir_all(&lz).unwrap();
+}
+
+#[test]
+fn is_transaction_atomic() {
+    let path = tmp_path("lsm");
+    let mut gen = txns();
+    let (first, second, open) = (gen.get(), gen.get(), gen.get());
+    {
+        let lsm = Lsm::<U64Tup, U64Txn>::new(&path, LsmConf::default()).unwrap();
+        for (txn, v) in [(&first, 1), (&second, 2)] {
+            let maps = (0..50).map(|k| U64Map(Some((k, Some(U64Tup(k, v)))))).collect::<Vec<_>>();
+            // a batch goes in the record of its transaction like single writes
+            if v == 1 {
+                for map in maps { lsm.wr(txn, map).unwrap() }
+            } else {
+                lsm.wr_batch(txn, maps).unwrap();
+            }
+            lsm.done(txn, End::Ready).unwrap();
+        }
+        // writes of an unfinished transaction are neither visible nor logged
+        lsm.wr(&open, U64Map(Some((50, Some(U64Tup(50, 3)))))).unwrap();
+        assert!(value(&lsm, 50).is_none());
+        assert!((0..50).all(|k| value(&lsm, k) == Some(2)));
+    }
+    // a crash tears the record of the second transaction
+    let log = path.join("log");
+    let len = std::fs::metadata(&log).unwrap().len();
+    std::fs::OpenOptions::new().write(true).open(&log).unwrap().set_len(len - 10).unwrap();
+    let lsm = Lsm::<U64Tup, U64Txn>::new(&path, LsmConf::default()).unwrap();
+    assert!((0..50).all(|k| value(&lsm, k) == Some(1)));
+    assert!(value(&lsm, 50).is_none());
+    let maps = lsm.rd_batch(vec![U64Prp(3), U64Prp(50), U64Prp(7)]).unwrap();
+    assert!(maps.iter().map(|U64Map(map)| map.as_ref().and_then(|(_, v)| v.as_ref()).map(|v| v.1)).collect::<Vec<_>>() == vec![Some(1), None, Some(1)]);
+    drop(lsm);
+    std::fs::remove_dir_all(&path).unwrap();
+}
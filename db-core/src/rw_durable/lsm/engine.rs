@@ -0,0 +1,376 @@
+use super::error::*;
+use super::sst::*;
+use crate::rw_durable::block::*;
+use crate::rw_durable::snapshot::Dump;
+use crate::rw_durable::wal::{put_record, replay, Mapping};
+use crate::utilities::*;
+use parking_lot::{Mutex, RwLock};
+use std::collections::{BTreeMap, HashSet};
+use std::fs::{File, OpenOptions};
+use std::hash::Hash;
+use std::io::{BufWriter, Read, Write};
+use std::marker::PhantomData;
+use std::ops::{Bound, RangeBounds};
+use std::path::{Path, PathBuf};
+use typing::constraint::*;
+use typing::rw::*;
+use typing::tx::*;
+
+/// lsm configuration
+#[derive(Debug, Clone, Copy)]
+pub struct LsmConf {
+    // the size of memtable in bytes that triggers a flush
+    pub memtable: usize,
+    // the number of level 0 files that triggers a compaction into level 1
+    pub l0_files: usize,
+    // the size budget of level 1 in bytes, each deeper level has fanout times the budget
+    pub level_base: u64,
+    pub fanout: u64,
+    // the target size of an sstable and a data block in bytes
+    pub file: usize,
+    pub block: usize,
+    // the codec of data blocks in new sstables, existing ones keep theirs
+    pub compress: &'static dyn Compressor,
+}
+
+impl Default for LsmConf {
+    fn default() -> Self {
+        LsmConf {
+            memtable: 1 << 20,
+            l0_files: 4,
+            level_base: 4 << 20,
+            fanout: 10,
+            file: 2 << 20,
+            block: 4 << 10,
+            compress: &Raw,
+        }
+    }
+}
+
+// memtable and sstables in levels
+struct State<V: Codec> {
+    mem: BTreeMap<V::I, Option<V>>,
+    // the encoded size of memtable
+    mem_size: usize,
+    // level 0 is ordered from newest to oldest, deeper levels are ordered by key
+    levels: Vec<Vec<Sst<V>>>,
+    // the id of the next sstable
+    next_id: u64,
+}
+
+pub struct Lsm<V: Id, T: Tx<V>>
+where
+    V: Sync + Send + Clone + Codec,
+    V::I: Ord + Hash + Sync + Send + Clone,
+    T::I: Eq + Hash,
+    T::Prp: Filter<V>,
+    T::Map: Mapper<V::I, V>,
+{
+    dir: PathBuf,
+    conf: LsmConf,
+    // lock order: log, state
+    log: Mutex<BufWriter<File>>,
+    // a cloned handle of log, so syncing doesn't block appending
+    file: File,
+    state: RwLock<State<V>>,
+    // written mappings of each open transaction, logged as one record when it is ready
+    pending: dashmap::DashMap<T::I, Mapping<V>>,
+    phant: PhantomData<T>,
+}
+
+// manifest: a checksummed frame of [next id: u64] [levels: u32] ([files: u32] [id: u64]*)*
+fn write_manifest<V: Codec>(dir: &Path, state: &State<V>) -> std::io::Result<()> {
+    let mut payload = vec![];
+    payload.extend(state.next_id.to_le_bytes());
+    payload.extend((state.levels.len() as u32).to_le_bytes());
+    for level in &state.levels {
+        payload.extend((level.len() as u32).to_le_bytes());
+        for sst in level { payload.extend(sst.id.to_le_bytes()) }
+    }
+    let mut frame = vec![];
+    put_frame(&payload, &mut frame);
+    let tmp = dir.join("manifest.tmp");
+    {
+        let mut file = File::create(&tmp)?;
+        file.write_all(&frame)?;
+        file.sync_data()?;
+    }
+    std::fs::rename(tmp, dir.join("manifest"))?;
+    File::open(dir)?.sync_all()
+}
+
+fn read_manifest(dir: &Path) -> Result<(u64, Vec<Vec<u64>>), LsmErr> {
+    let bytes = match std::fs::read(dir.join("manifest")) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok((1, vec![vec![]])),
+        Err(e) => return Err(e.into()),
+    };
+    let corrupt = || LsmErr::Corrupt(0);
+    let buf = &mut get_frame(&mut &bytes[..]).map_err(|_| corrupt())?.ok_or_else(corrupt)?;
+    let next_id = get_u64(buf).ok_or_else(corrupt)?;
+    let mut levels = vec![];
+    for _ in 0..get_u32(buf).ok_or_else(corrupt)? {
+        let n = get_u32(buf).ok_or_else(corrupt)?;
+        levels.push((0..n).map(|_| get_u64(buf)).collect::<Option<Vec<_>>>().ok_or_else(corrupt)?);
+    }
+    Ok((next_id, levels))
+}
+
+impl<V: Id, T: Tx<V>> Lsm<V, T>
+where
+    V: Sync + Send + Clone + Codec,
+    V::I: Ord + Hash + Sync + Send + Clone,
+    T::I: Eq + Hash,
+    T::Prp: Filter<V>,
+    T::Map: Mapper<V::I, V>,
+{
+    /// open (or create) an engine in a directory, rebuild memtable by replaying the log
+    pub fn new(dir: impl AsRef<Path>, conf: LsmConf) -> Result<Self, LsmErr> {
+        let dir = dir.as_ref().to_path_buf();
+        std::fs::create_dir_all(&dir)?;
+        let (next_id, ids) = read_manifest(&dir)?;
+        let levels = ids.iter()
+            .map(|level| level.iter().map(|id| Sst::open(&dir, *id, conf.compress)).collect::<Result<Vec<_>, _>>())
+            .collect::<Result<Vec<_>, _>>()?;
+        // remove sstables left by an unfinished flush or compaction
+        let live = ids.into_iter().flatten().collect::<HashSet<_>>();
+        for entry in std::fs::read_dir(&dir)? {
+            let name = entry?.file_name().to_string_lossy().to_string();
+            let Some(id) = name.strip_suffix(".sst").and_then(|id| id.parse::<u64>().ok()) else { continue };
+            if !live.contains(&id) { std::fs::remove_file(sst_path(&dir, id))? }
+        }
+        let mut file = OpenOptions::new()
+            .read(true).append(true).create(true)
+            .open(dir.join("log"))?;
+        let mut bytes = vec![];
+        file.read_to_end(&mut bytes)?;
+        let mut state = State { mem: BTreeMap::new(), mem_size: 0, levels, next_id };
+        let valid = replay::<V>(&bytes, |map| for (i, v) in map {
+            state.mem_size += entry_len(&i, &v);
+            state.mem.insert(i, v);
+        }).map_err(|_| LsmErr::Corrupt(0))?;
+        // drop a torn tail, so new records are appended right after the last valid one
+        file.set_len(valid)?;
+        file.sync_all()?;
+        Ok(Self {
+            dir, conf,
+            file: file.try_clone()?,
+            log: Mutex::new(BufWriter::new(file)),
+            state: RwLock::new(state),
+            pending: dashmap::DashMap::new(),
+            phant: PhantomData,
+        })
+    }
+    /// the number of sstables in each level
+    pub fn shape(&self) -> Vec<usize> {
+        self.state.read().levels.iter().map(|level| level.len()).collect()
+    }
+    /// get the value of a key
+    pub fn get(&self, key: &V::I) -> Result<Option<V>, LsmErr> {
+        Self::get_in(&self.state.read(), key)
+    }
+    // look up a key in memtable, then in sstables from the newest to the oldest
+    fn get_in(state: &State<V>, key: &V::I) -> Result<Option<V>, LsmErr> {
+        if let Some(v) = state.mem.get(key) { return Ok(v.clone()) }
+        for level in &state.levels {
+            for sst in level.iter().filter(|sst| sst.first() <= key && key <= &sst.last) {
+                if let Some(v) = sst.get(key)? { return Ok(v) }
+            }
+        }
+        Ok(None)
+    }
+    /// visit rows with keys in a range in key order
+    pub fn scan(&self, lo: Bound<&V::I>, hi: Bound<&V::I>, mut f: impl FnMut(&V::I, &V)) -> Result<(), LsmErr> {
+        let state = self.state.read();
+        let mut rows = BTreeMap::new();
+        // from the oldest to the newest, so newer entries overwrite older ones
+        for level in state.levels.iter().rev() {
+            for sst in level.iter().rev().filter(|sst| sst.overlaps(lo, hi)) {
+                rows.extend(sst.rows(lo, hi)?);
+            }
+        }
+        rows.extend(state.mem.iter().filter(|(k, _)| (lo, hi).contains(*k)).map(|(k, v)| (k.clone(), v.clone())));
+        for (k, v) in rows {
+            if let Some(v) = v { f(&k, &v) }
+        }
+        Ok(())
+    }
+    // log the writes of a transaction as one record and apply them to memtable
+    fn append(&self, map: Mapping<V>) -> Result<(), LsmErr> {
+        if map.is_empty() { return Ok(()) }
+        let mut payload = vec![];
+        put_record(&map, &mut payload);
+        let mut frame = vec![];
+        put_frame(&payload, &mut frame);
+        // hold the log while applying, so the log order is the memtable order
+        let mut log = self.log.lock();
+        log.write_all(&frame)?;
+        let mut state = self.state.write();
+        for (i, v) in map {
+            state.mem_size += entry_len(&i, &v);
+            state.mem.insert(i, v);
+        }
+        if state.mem_size >= self.conf.memtable {
+            self.flush(&mut log, &mut state)?;
+        }
+        Ok(())
+    }
+    // flush memtable into level 0, compact levels, then drop the log
+    fn flush(&self, log: &mut BufWriter<File>, state: &mut State<V>) -> Result<(), LsmErr> {
+        let rows = std::mem::take(&mut state.mem).into_iter().collect::<Vec<_>>();
+        state.mem_size = 0;
+        let mut obsolete = vec![];
+        if !rows.is_empty() {
+            state.next_id += 1;
+            let sst = Sst::write(&self.dir, state.next_id - 1, &rows, self.conf.block, self.conf.compress)?;
+            state.levels[0].insert(0, sst);
+            obsolete = self.compact(state)?;
+        }
+        write_manifest(&self.dir, state)?;
+        for id in obsolete { std::fs::remove_file(sst_path(&self.dir, id))? }
+        // everything in the log is in sstables now
+        log.flush()?;
+        self.file.set_len(0)?;
+        self.file.sync_all()?;
+        Ok(())
+    }
+    // compact levels over their budgets, return ids of replaced sstables
+    fn compact(&self, state: &mut State<V>) -> Result<Vec<u64>, LsmErr> {
+        let mut obsolete = vec![];
+        if state.levels[0].len() >= self.conf.l0_files {
+            obsolete.extend(self.merge(state, 0)?);
+        }
+        let mut budget = self.conf.level_base;
+        let mut n = 1;
+        while n < state.levels.len() {
+            if state.levels[n].iter().map(|sst| sst.size).sum::<u64>() > budget {
+                obsolete.extend(self.merge(state, n)?);
+            }
+            budget = budget.saturating_mul(self.conf.fanout);
+            n += 1;
+        }
+        Ok(obsolete)
+    }
+    // merge level n into level n+1, which becomes a single sorted run
+    fn merge(&self, state: &mut State<V>, n: usize) -> Result<Vec<u64>, LsmErr> {
+        if state.levels.len() == n + 1 { state.levels.push(vec![]) }
+        let upper = std::mem::take(&mut state.levels[n]);
+        let lower = std::mem::take(&mut state.levels[n + 1]);
+        let mut rows = BTreeMap::new();
+        for sst in lower.iter().chain(upper.iter().rev()) {
+            rows.extend(sst.rows(Bound::Unbounded, Bound::Unbounded)?);
+        }
+        // tombstones are dropped once there is nothing older below
+        let bottom = state.levels[n + 2..].iter().all(|level| level.is_empty());
+        let mut run = vec![];
+        let mut rows = rows.into_iter().filter(|(_, v)| !bottom || v.is_some()).peekable();
+        while rows.peek().is_some() {
+            let mut size = 0;
+            let mut file = vec![];
+            while let Some((k, v)) = rows.next_if(|_| size < self.conf.file) {
+                size += entry_len(&k, &v);
+                file.push((k, v));
+            }
+            state.next_id += 1;
+            run.push(Sst::write(&self.dir, state.next_id - 1, &file, self.conf.block, self.conf.compress)?);
+        }
+        state.levels[n + 1] = run;
+        Ok(upper.iter().chain(lower.iter()).map(|sst| sst.id).collect())
+    }
+}
+
+impl<V: Id, T: Tx<V>> Drop for Lsm<V, T>
+where
+    V: Sync + Send + Clone + Codec,
+    V::I: Ord + Hash + Sync + Send + Clone,
+    T::I: Eq + Hash,
+    T::Prp: Filter<V>,
+    T::Map: Mapper<V::I, V>,
+{
+    fn drop(&mut self) {
+        self.log.lock().flush().unwrap_or(());
+        self.file.sync_data().unwrap_or(());
+    }
+}
+
+impl<V: Id, T: Tx<V>> RWDurable<V, T> for Lsm<V, T>
+where
+    V: Sync + Send + Clone + Codec,
+    V::I: Ord + Hash + Sync + Send + Clone,
+    T::I: Eq + Hash,
+    T::Prp: Filter<V> + MaybeIndexer<V::I> + MaybeRanger<V::I>,
+    T::Map: Mapper<V::I, V>,
+{
+    type Err = LsmErr;
+    fn done(&self, txn: &T, end: End) -> Result<(), Self::Err> {
+        let writes = self.pending.remove(&txn.id()).map(|(_, writes)| writes);
+        if matches!(end, End::Abort) { return Ok(()) }
+        self.append(writes.unwrap_or_default())?;
+        self.log.lock().flush()?;
+        self.file.sync_data()?;
+        Ok(())
+    }
+    fn open(&self, _txn: &T) -> Result<(), Self::Err> {
+        Ok(())
+    }
+    fn rd(&self, prp: T::Prp) -> Result<T::Map, Self::Err> {
+        let mut map = vec![];
+        if let Some(prp_iter) = prp.tryc_indexer() {
+            for i in prp_iter {
+                if let Some(v) = self.get(&i)? {
+                    map.push((i, Some(v)));
+                }
+            }
+        } else {
+            // without a range, all sstables are scanned
+            let (lo, hi) = prp.tryc_ranger().unwrap_or((Bound::Unbounded, Bound::Unbounded));
+            let filter = prp.into_filter();
+            self.scan(lo.as_ref(), hi.as_ref(), |i, v| {
+                if (filter)(v) { map.push((i.clone(), Some(v.clone()))) }
+            })?;
+        }
+        Ok(Mapper::from_mapping(map.into_iter()))
+    }
+    // writes are buffered until the transaction is done, and they are visible to readers after that
+    fn wr(&self, txn: &T, map: T::Map) -> Result<(), Self::Err> {
+        self.pending.entry(txn.id()).or_default().extend(map.into_mapping());
+        Ok(())
+    }
+    // point reads of a batch are served in one pass under a single lock of the tree state
+    fn rd_batch(&self, prps: Vec<T::Prp>) -> Result<Vec<T::Map>, Self::Err> {
+        if prps.iter().any(|prp| prp.tryc_indexer().is_none()) {
+            return prps.into_iter().map(|prp| self.rd(prp)).collect();
+        }
+        let state = self.state.read();
+        let mut maps = vec![];
+        for prp in prps {
+            let mut map = vec![];
+            for i in prp.tryc_indexer().into_iter().flatten() {
+                if let Some(v) = Self::get_in(&state, &i)? {
+                    map.push((i, Some(v)));
+                }
+            }
+            maps.push(Mapper::from_mapping(map.into_iter()));
+        }
+        Ok(maps)
+    }
+    // a batch joins the buffered writes, so it is logged in the record of its transaction
+    fn wr_batch(&self, txn: &T, maps: Vec<T::Map>) -> Result<(), Self::Err> {
+        self.pending.entry(txn.id()).or_default().extend(maps.iter().flat_map(|map| map.into_mapping()));
+        Ok(())
+    }
+}
+
+impl<V: Id, T: Tx<V>> Dump<V> for Lsm<V, T>
+where
+    V: Sync + Send + Clone + Codec,
+    V::I: Ord + Hash + Sync + Send + Clone,
+    T::I: Eq + Hash,
+    T::Prp: Filter<V>,
+    T::Map: Mapper<V::I, V>,
+{
+    type Err = LsmErr;
+    fn dump(&self, f: &mut dyn FnMut(&V::I, &V)) -> Result<(), Self::Err> {
+        self.scan(Bound::Unbounded, Bound::Unbounded, f)
+    }
+}
@@ -0,0 +1,13 @@
+#[derive(Debug)]
+pub enum LsmErr {
+    // file system error
+    Io(std::io::Error),
+    // a file with a given id cannot be decoded, 0 for the manifest or the log
+    Corrupt(u64),
+}
+
+impl From<std::io::Error> for LsmErr {
+    fn from(e: std::io::Error) -> Self {
+        LsmErr::Io(e)
+    }
+}
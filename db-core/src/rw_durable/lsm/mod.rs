@@ -0,0 +1,27 @@
+//! ## LSM-Tree
+//!
+//! > O'Neil, Patrick, et al. "The log-structured merge-tree (LSM-tree)." Acta Informatica 33.4 (1996): 351-385.
+//!
+//! A log-structured merge tree. Writes of a transaction are buffered until it is ready, then logged as one record and applied to an in-memory memtable, deletes are kept as tombstones (None).
+//! A full memtable is flushed as an immutable sorted string table (SSTable) file into level 0.
+//! An SSTable is made of checksummed data blocks (compressed by a configurable codec, see block), a block index and a bloom filter, so a point read touches at most one block per file.
+//! Compaction is leveled: level 0 files may overlap, a level above its size budget is merged into the next one, which holds one sorted run.
+//! A manifest file lists the SSTables in each level, it is replaced atomically after every flush or compaction.
+
+// lsm error
+mod error;
+// bloom filter
+mod bloom;
+// sorted string table files
+mod sst;
+// core lsm engine implementation
+mod engine;
+
+pub use error::*;
+pub use engine::*;
+
+#[cfg(test)]
+mod check; // recovery and compaction checks
+
+#[cfg(test)]
+mod bench; // inlined benchmark module against b+tree
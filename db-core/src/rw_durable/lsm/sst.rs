@@ -0,0 +1,185 @@
+use super::bloom::*;
+use super::error::*;
+use crate::rw_durable::block::*;
+use crate::rw_durable::wal::{get_record, put_record};
+use crate::utilities::*;
+use std::fs::File;
+use std::io::Write;
+use std::ops::{Bound, RangeBounds};
+use std::path::{Path, PathBuf};
+use typing::constraint::*;
+
+// footer: [index offset: u64] [index length: u32] [bloom offset: u64] [bloom length: u32] [magic]
+const MAGIC: &[u8; 8] = b"LSMTABLE";
+const FOOTER: usize = 8 + 4 + 8 + 4 + 8;
+
+/// sorted entries, None is a tombstone
+pub(super) type Rows<V> = Vec<(<V as Id>::I, Option<V>)>;
+
+/// the encoded size of an entry in a data block
+pub(super) fn entry_len<V: Codec>(key: &V::I, val: &Option<V>) -> usize {
+    let mut buf = vec![];
+    V::put_id(key, &mut buf);
+    if let Some(v) = val { v.put(&mut buf) }
+    buf.len() + 1
+}
+
+pub(super) fn sst_path(dir: &Path, id: u64) -> PathBuf {
+    dir.join(format!("{id}.sst"))
+}
+
+/// an immutable sorted string table, its index and bloom filter are kept in memory
+pub(super) struct Sst<V: Codec> {
+    pub id: u64,
+    file: File,
+    // the first key, offset and length of each data block
+    index: Vec<(V::I, u64, u32)>,
+    bloom: Bloom,
+    // the codec of data blocks
+    comp: &'static dyn Compressor,
+    // the last key in this table
+    pub last: V::I,
+    // the size of this file
+    pub size: u64,
+}
+
+impl<V: Codec> Sst<V>
+where
+    V::I: Ord + Clone,
+{
+    /// write sorted entries (tombstones included) into a new file, block is the target size of a data block
+    pub fn write(dir: &Path, id: u64, rows: &[(V::I, Option<V>)], block: usize, comp: &'static dyn Compressor) -> Result<Self, LsmErr> {
+        let mut data = vec![];
+        let mut index = vec![];
+        let mut bloom = Bloom::new(rows.len());
+        let mut start = 0;
+        let mut size = 0;
+        for (n, (k, v)) in rows.iter().enumerate() {
+            let mut key = vec![];
+            V::put_id(k, &mut key);
+            bloom.add(&key);
+            size += entry_len(k, v);
+            if size < block && n + 1 < rows.len() { continue }
+            // a data block is a log record in a checksummed (and maybe compressed) block
+            let mut payload = vec![];
+            put_record(&rows[start..=n], &mut payload);
+            let offset = data.len() as u64;
+            put_block(comp, &payload, &mut data);
+            index.push((rows[start].0.clone(), offset, (data.len() as u64 - offset) as u32));
+            (start, size) = (n + 1, 0);
+        }
+        let mut payload = vec![];
+        payload.extend((index.len() as u32).to_le_bytes());
+        for (k, offset, len) in &index {
+            V::put_id(k, &mut payload);
+            payload.extend(offset.to_le_bytes());
+            payload.extend(len.to_le_bytes());
+        }
+        let index_at = data.len() as u64;
+        put_frame(&payload, &mut data);
+        let bloom_at = data.len() as u64;
+        let mut payload = vec![];
+        bloom.put(&mut payload);
+        put_frame(&payload, &mut data);
+        let end = data.len() as u64;
+        data.extend(index_at.to_le_bytes());
+        data.extend(((bloom_at - index_at) as u32).to_le_bytes());
+        data.extend(bloom_at.to_le_bytes());
+        data.extend(((end - bloom_at) as u32).to_le_bytes());
+        data.extend(MAGIC);
+        let mut file = File::create(sst_path(dir, id))?;
+        file.write_all(&data)?;
+        file.sync_all()?;
+        let last = rows.last().expect("an sstable is never empty").0.clone();
+        Ok(Sst { id, file: File::open(sst_path(dir, id))?, index, bloom, comp, last, size: data.len() as u64 })
+    }
+    /// open an existing file, load its index and bloom filter
+    pub fn open(dir: &Path, id: u64, comp: &'static dyn Compressor) -> Result<Self, LsmErr> {
+        let corrupt = || LsmErr::Corrupt(id);
+        let file = File::open(sst_path(dir, id))?;
+        let size = file.metadata()?.len();
+        if size < FOOTER as u64 { return Err(corrupt()) }
+        let mut footer = [0u8; FOOTER];
+        file.read_exact_at(&mut footer, size - FOOTER as u64)?;
+        let buf = &mut &footer[..];
+        let (index_at, index_len) = (get_u64(buf).ok_or_else(corrupt)?, get_u32(buf).ok_or_else(corrupt)?);
+        let (bloom_at, bloom_len) = (get_u64(buf).ok_or_else(corrupt)?, get_u32(buf).ok_or_else(corrupt)?);
+        if *buf != &MAGIC[..] { return Err(corrupt()) }
+        let read = |at: u64, len: u32| -> Result<Vec<u8>, LsmErr> {
+            let mut frame = vec![0u8; len as usize];
+            file.read_exact_at(&mut frame, at)?;
+            let payload = get_frame(&mut &frame[..]).map_err(|_| corrupt())?.ok_or_else(corrupt)?;
+            Ok(payload.to_vec())
+        };
+        let payload = read(index_at, index_len)?;
+        let buf = &mut &payload[..];
+        let mut index = vec![];
+        for _ in 0..get_u32(buf).ok_or_else(corrupt)? {
+            let k = V::get_id(buf).ok_or_else(corrupt)?;
+            index.push((k, get_u64(buf).ok_or_else(corrupt)?, get_u32(buf).ok_or_else(corrupt)?));
+        }
+        let payload = read(bloom_at, bloom_len)?;
+        let bloom = Bloom::get(&mut &payload[..]).ok_or_else(corrupt)?;
+        let (_, offset, len) = index.last().ok_or_else(corrupt)?;
+        let mut frame = vec![0u8; *len as usize];
+        file.read_exact_at(&mut frame, *offset)?;
+        let last = get_block(&mut &frame[..], comp).ok()
+            .and_then(|payload| get_record::<V>(&payload))
+            .and_then(|rows| rows.last().map(|(k, _)| k.clone()))
+            .ok_or_else(corrupt)?;
+        Ok(Sst { id, file, index, bloom, comp, last, size })
+    }
+    /// the first key in this table
+    pub fn first(&self) -> &V::I {
+        &self.index[0].0
+    }
+    /// whether a key range may hold keys of this table
+    pub fn overlaps(&self, lo: Bound<&V::I>, hi: Bound<&V::I>) -> bool {
+        let above = match lo {
+            Bound::Included(lo) => lo > &self.last,
+            Bound::Excluded(lo) => lo >= &self.last,
+            Bound::Unbounded => false,
+        };
+        let below = match hi {
+            Bound::Included(hi) => hi < self.first(),
+            Bound::Excluded(hi) => hi <= self.first(),
+            Bound::Unbounded => false,
+        };
+        !above && !below
+    }
+    fn block(&self, n: usize) -> Result<Rows<V>, LsmErr> {
+        let (_, offset, len) = &self.index[n];
+        let mut frame = vec![0u8; *len as usize];
+        self.file.read_exact_at(&mut frame, *offset)?;
+        let corrupt = || LsmErr::Corrupt(self.id);
+        let payload = get_block(&mut &frame[..], self.comp).map_err(|_| corrupt())?;
+        get_record::<V>(&payload).ok_or_else(corrupt)
+    }
+    /// look up a key, Some(None) is a tombstone
+    pub fn get(&self, key: &V::I) -> Result<Option<Option<V>>, LsmErr> {
+        let mut buf = vec![];
+        V::put_id(key, &mut buf);
+        if !self.bloom.may_have(&buf) { return Ok(None) }
+        let n = self.index.partition_point(|(k, ..)| k <= key);
+        if n == 0 { return Ok(None) }
+        let mut rows = self.block(n - 1)?;
+        Ok(rows.binary_search_by(|(k, _)| k.cmp(key)).ok().map(|i| rows.swap_remove(i).1))
+    }
+    /// entries with keys in a range in key order, only blocks that overlap the range are read
+    pub fn rows(&self, lo: Bound<&V::I>, hi: Bound<&V::I>) -> Result<Rows<V>, LsmErr> {
+        let start = match lo {
+            Bound::Included(lo) | Bound::Excluded(lo) => self.index.partition_point(|(k, ..)| k <= lo).saturating_sub(1),
+            Bound::Unbounded => 0,
+        };
+        let end = match hi {
+            Bound::Included(hi) => self.index.partition_point(|(k, ..)| k <= hi),
+            Bound::Excluded(hi) => self.index.partition_point(|(k, ..)| k < hi),
+            Bound::Unbounded => self.index.len(),
+        };
+        let mut rows = vec![];
+        for n in start..end {
+            rows.extend(self.block(n)?.into_iter().filter(|(k, _)| (lo, hi).contains(k)));
+        }
+        Ok(rows)
+    }
+}
@@ -1 +1,21 @@
-pub mod null;
\ No newline at end of file
+pub mod null;
+pub mod wal;
+pub mod aries;
+pub mod cmd_log;
+pub mod btree;
+pub mod lsm;
+pub mod bitcask;
+pub mod cow_btree;
+pub mod mpt;
+pub mod fault;
+pub mod latency;
+pub mod index;
+pub mod snapshot;
+pub mod block;
+pub mod crypt;
+pub mod aio_log;
+pub mod replica;
+pub mod partition;
+
+#[cfg(test)]
+pub(crate) mod check_util; // helpers shared by durable engine checks
@@ -0,0 +1,136 @@
+use super::*;
+use crate::rw_control::{KVSparkle, KVSparkleTx, KVSplice, KVSpliceTx, Serial};
+use crate::rw_durable::check_util::*;
+use crate::rw_durable::cmd_log::*;
+use crate::tx_service::m_thread::*;
+use db_test::core_workload::int::unif::*;
+use typing::tx::*;
+
+// storage roots computed by an independent ethereum trie implementation
+const EMPTY: &str = "56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421";
+const ONE: &str = "f38f9f63c760d088d7dd04f743619b6291f63beebd8bdf530628f90e9cfa52d7";
+const THREE: &str = "56d6d7425506973c272d8ed11c093290626ec25f1b1ba85e69115ed8f8ac04d1";
+const MANY: &str = "9f46891318639093c894b8d2033f066925e329754166db40c26458659a24f309";
+const MANY_DEL: &str = "11e2107ee244058dcedf210eda20fb52a4677980c95b92c7fe267c53a730e302";
+
+fn hex(root: [u8; 32]) -> String {
+    root.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn put(k: u64, v: u64) -> (u64, Option<U64Tup>) {
+    (k, Some(U64Tup(k, v)))
+}
+
+#[test]
+fn is_ethereum_root() {
+    let path = tmp_path("mpt");
+    let mpt = Mpt::<U64Tup, U64Txn>::new(&path).unwrap();
+    assert!(hex(mpt.root()) == EMPTY);
+    mpt.commit(vec![put(1, 1)]).unwrap();
+    assert!(hex(mpt.root()) == ONE);
+    mpt.commit(vec![put(2, 0x1234), put(3, u64::MAX)]).unwrap();
+    assert!(hex(mpt.root()) == THREE);
+    // removing keys, or writing zeros, brings back earlier roots
+    mpt.commit(vec![(2, None), put(3, 0)]).unwrap();
+    assert!(hex(mpt.root()) == ONE);
+    mpt.commit(vec![(1, None)]).unwrap();
+    assert!(hex(mpt.root()) == EMPTY);
+    drop(mpt);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn is_root_recovered() {
+    let path = tmp_path("mpt");
+    {
+        let mpt = Mpt::<U64Tup, U64Txn>::new(&path).unwrap();
+        for batch in (0..1000).map(|k| put(k, k * 2 + 1)).collect::<Vec<_>>().chunks(100) {
+            mpt.commit(batch.to_vec()).unwrap();
+        }
+        assert!(hex(mpt.seal().1) == MANY);
+        mpt.commit((0..1000).step_by(3).map(|k| (k, None)).collect()).unwrap();
+        assert!(hex(mpt.seal().1) == MANY_DEL);
+        assert!(mpt.roots().len() == 2);
+    }
+    let mpt = Mpt::<U64Tup, U64Txn>::new(&path).unwrap();
+    assert!(hex(mpt.root()) == MANY_DEL);
+    drop(mpt);
+    std::fs::remove_file(&path).unwrap();
+}
+
+// run transactions to completion, the service is closed before its log is read
+fn run<S: TxService<U64Txn, U64Tup>>(mut srv: S, txns: Vec<U64Txn>) {
+    replay_cmds::<U64Tup, _, _>(txns, &mut srv).unwrap_or_else(|_| panic!("fail to run transactions"));
+}
+
+#[test]
+fn is_root_same_across_protocols() {
+    const N_TXN: u64 = 2000;
+    const SEED: u64 = 1145141919810;
+    let txns = || {
+        let mut workload = U64Gen::new(SEED, (15, 15, 1, 4), 256);
+        // transaction ids start from 1
+        (0..=N_TXN).map(|_| workload.get()).filter(|txn| txn.id() != 0).collect::<Vec<_>>()
+    };
+    type Sparkle = KVSparkleTx<U64Tup, U64Txn>;
+    type Splice = KVSpliceTx<U64Tup, U64Txn>;
+    // protocols that run transactions out of order must end in the state of running them one by one
+    let roots = ["serial", "sparkle", "splice"].map(|protocol| {
+        let path = tmp_path("mpt");
+        match protocol {
+            "serial" => run(MThreadService::new(4, |x| x, Serial::<U64Txn, U64Tup>::new(), Mpt::<U64Tup, U64Txn>::new(&path).unwrap()), txns()),
+            "sparkle" => run(MThreadService::new(4, KVSparkleTx::new, KVSparkle::<U64Txn, U64Tup>::new(SEED), Mpt::<U64Tup, Sparkle>::new(&path).unwrap()), txns()),
+            _ => run(MThreadService::new(4, KVSpliceTx::new, KVSplice::<U64Txn, U64Tup>::new(SEED), Mpt::<U64Tup, Splice>::new(&path).unwrap()), txns()),
+        }
+        // roots are taken from the log
+        let root = Mpt::<U64Tup, U64Txn>::new(&path).unwrap().root();
+        std::fs::remove_file(&path).unwrap();
+        root
+    });
+    assert!(hex(roots[0]) != EMPTY);
+    assert!(roots[0] == roots[1] && roots[0] == roots[2]);
+}
+
+#[test]
+fn is_evm_root_same_across_protocols() {
+    use db_test::core_workload::eth::revm_interp::{preset::BYTECODE_TEN_RW, *};
+    use rand::RngCore;
+    use rand_xoshiro::Xoroshiro64StarStar;
+    use revm_interpreter::{analysis::to_analysed, BytecodeLocked};
+    use revm_primitives::{hex, Bytecode, Bytes};
+    const N_TXN: usize = 300;
+    let bytecode = to_analysed(Bytecode::new_raw(hex::decode(BYTECODE_TEN_RW).unwrap().into()));
+    let bytecode = BytecodeLocked::try_from(bytecode).unwrap();
+    // five slots out of sixteen and a value, so calls conflict often
+    fn template(rng: &mut Xoroshiro64StarStar) -> Bytes {
+        let mut args = vec![0u8; 6 * 32];
+        for slot in 0..5 { args[slot * 32 + 31] = (rng.next_u32() % 16) as u8 }
+        rng.fill_bytes(&mut args[5 * 32 + 24..]);
+        [hex::decode("40cb7660").unwrap(), args].concat().into()
+    }
+    let txns = || {
+        let mut workload = REVMInterpGen::new(bytecode.clone(), template, [7, 6, 1, 254, 233, 109, 38, 34]);
+        (0..N_TXN).map(|_| workload.get()).collect::<Vec<_>>()
+    };
+    let roots = ["serial", "sparkle"].map(|protocol| {
+        let path = tmp_path("mpt");
+        let done = match protocol {
+            "serial" => {
+                let dur = Mpt::<EVMU256Tup, REVMInterpTxn>::new(&path).unwrap();
+                let mut srv = MThreadService::new(4, |x| x, Serial::<REVMInterpTxn, EVMU256Tup>::new(), dur);
+                replay_cmds::<EVMU256Tup, _, _>(txns(), &mut srv).is_ok()
+            }
+            _ => {
+                let dur = Mpt::<EVMU256Tup, KVSparkleTx<EVMU256Tup, REVMInterpTxn>>::new(&path).unwrap();
+                let mut srv = MThreadService::new(4, KVSparkleTx::new, KVSparkle::<REVMInterpTxn, EVMU256Tup>::new(1), dur);
+                replay_cmds::<EVMU256Tup, _, _>(txns(), &mut srv).is_ok()
+            }
+        };
+        assert!(done);
+        let root = Mpt::<EVMU256Tup, REVMInterpTxn>::new(&path).unwrap().root();
+        std::fs::remove_file(&path).unwrap();
+        root
+    });
+    assert!(hex(roots[0]) != EMPTY);
+    assert!(roots[0] == roots[1]);
+}
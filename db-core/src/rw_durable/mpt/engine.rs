@@ -0,0 +1,202 @@
+use super::error::*;
+use super::trie::*;
+use crate::rw_durable::snapshot::Dump;
+use crate::rw_durable::wal::{put_record, replay};
+use crate::utilities::*;
+use dashmap::DashMap;
+use parking_lot::{Mutex, RwLock};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::hash::Hash;
+use std::io::{Read, Write};
+use std::marker::PhantomData;
+use std::path::Path;
+use typing::constraint::*;
+use typing::rw::*;
+use typing::tx::*;
+
+// rows of a commit, none for removal
+type Rows<V> = Vec<(<V as Id>::I, Option<V>)>;
+
+// committed state
+struct State<V: Id> {
+    table: HashMap<V::I, V>,
+    trie: Trie,
+    // state roots of sealed blocks
+    roots: Vec<[u8; 32]>,
+}
+
+impl<V: Id + TrieLeaf> State<V>
+where
+    V::I: Eq + Hash,
+{
+    fn apply(&mut self, rows: Rows<V>) {
+        for (i, v) in rows {
+            let path = keccak256(&V::trie_key(&i));
+            let val = v.as_ref().map(|v| v.trie_val()).unwrap_or_default();
+            // an empty value is absent from the trie, as ethereum drops zero slots
+            if val.is_empty() {
+                self.trie.remove(&path);
+            } else {
+                let mut leaf = vec![];
+                rlp_bytes(&val, &mut leaf);
+                self.trie.insert(&path, leaf);
+            }
+            match v {
+                Some(v) => {self.table.insert(i, v);},
+                None => {self.table.remove(&i);},
+            }
+        }
+    }
+}
+
+pub struct Mpt<V: Id, T: Tx<V>>
+where
+    V: Sync + Send + Clone + Codec + TrieLeaf,
+    V::I: Eq + Hash + Sync + Send + Clone,
+    T::I: Eq + Hash,
+    T::Prp: Filter<V>,
+    T::Map: Mapper<V::I, V>,
+{
+    // lock order: log, state
+    log: Mutex<File>,
+    state: RwLock<State<V>>,
+    // writes of open transactions, applied in one commit when ready
+    pending: DashMap<T::I, Rows<V>>,
+    phant: PhantomData<T>,
+}
+
+impl<V: Id, T: Tx<V>> Mpt<V, T>
+where
+    V: Sync + Send + Clone + Codec + TrieLeaf,
+    V::I: Eq + Hash + Sync + Send + Clone,
+    T::I: Eq + Hash,
+    T::Prp: Filter<V>,
+    T::Map: Mapper<V::I, V>,
+{
+    /// open a log file (create it if absent) and rebuild the table and the trie by replaying it
+    pub fn new(path: impl AsRef<Path>) -> Result<Self, MptErr> {
+        let mut file = OpenOptions::new()
+            .read(true).append(true).create(true)
+            .open(path)?;
+        let mut bytes = vec![];
+        file.read_to_end(&mut bytes)?;
+        let mut state = State { table: HashMap::new(), trie: Trie::new(), roots: vec![] };
+        let valid = replay::<V>(&bytes, |rows| state.apply(rows)).map_err(|_| MptErr::Corrupt(0))?;
+        // drop a torn tail, so new records are appended right after the last valid one
+        file.set_len(valid)?;
+        file.sync_all()?;
+        Ok(Self {
+            log: Mutex::new(file),
+            state: RwLock::new(state),
+            pending: DashMap::new(),
+            phant: PhantomData,
+        })
+    }
+    /// apply rows (none for removal) as one commit
+    pub fn commit(&self, rows: Rows<V>) -> Result<(), MptErr> {
+        let mut payload = vec![];
+        put_record(&rows, &mut payload);
+        let mut frame = vec![];
+        put_frame(&payload, &mut frame);
+        // hold the log while applying, so the log order is the commit order
+        let mut log = self.log.lock();
+        log.write_all(&frame)?;
+        log.sync_data()?;
+        self.state.write().apply(rows);
+        Ok(())
+    }
+    /// the state root after all commits so far
+    pub fn root(&self) -> [u8; 32] {
+        self.state.write().trie.root()
+    }
+    /// seal a block with the state root after all commits so far, return the block number and its root
+    pub fn seal(&self) -> (usize, [u8; 32]) {
+        let mut state = self.state.write();
+        let root = state.trie.root();
+        state.roots.push(root);
+        (state.roots.len() - 1, root)
+    }
+    /// state roots of sealed blocks, they are not kept across restarts
+    pub fn roots(&self) -> Vec<[u8; 32]> {
+        self.state.read().roots.clone()
+    }
+}
+
+impl<V: Id, T: Tx<V>> RWDurable<V, T> for Mpt<V, T>
+where
+    V: Sync + Send + Clone + Codec + TrieLeaf,
+    V::I: Eq + Hash + Sync + Send + Clone,
+    T::I: Eq + Hash,
+    T::Prp: Filter<V> + MaybeIndexer<V::I>,
+    T::Map: Mapper<V::I, V>,
+{
+    type Err = MptErr;
+    fn done(&self, txn: &T, end: End) -> Result<(), Self::Err> {
+        let Some((_, rows)) = self.pending.remove(&txn.id()) else { return Ok(()) };
+        if matches!(end, End::Abort) || rows.is_empty() { return Ok(()) }
+        self.commit(rows)
+    }
+    fn open(&self, txn: &T) -> Result<(), Self::Err> {
+        self.pending.insert(txn.id(), vec![]);
+        Ok(())
+    }
+    fn rd(&self, prp: T::Prp) -> Result<T::Map, Self::Err> {
+        Ok(read_state::<V, T>(&self.state.read(), prp))
+    }
+    fn wr(&self, txn: &T, map: T::Map) -> Result<(), Self::Err> {
+        self.pending.entry(txn.id()).or_default().extend(map.into_mapping());
+        Ok(())
+    }
+    // a batch is read under one lock of the state, so all of its maps see the same block
+    fn rd_batch(&self, prps: Vec<T::Prp>) -> Result<Vec<T::Map>, Self::Err> {
+        let state = self.state.read();
+        Ok(prps.into_iter().map(|prp| read_state::<V, T>(&state, prp)).collect())
+    }
+    // a batch joins the buffered writes, so it is applied in the commit of its transaction
+    fn wr_batch(&self, txn: &T, maps: Vec<T::Map>) -> Result<(), Self::Err> {
+        self.pending.entry(txn.id()).or_default().extend(maps.iter().flat_map(|map| map.into_mapping()));
+        Ok(())
+    }
+}
+
+// answer a proposition from the table of a state
+fn read_state<V, T>(state: &State<V>, prp: T::Prp) -> T::Map
+where
+    V: Id + Clone,
+    T: Tx<V>,
+    V::I: Eq + Hash + Clone,
+    T::Prp: Filter<V> + MaybeIndexer<V::I>,
+    T::Map: Mapper<V::I, V>,
+{
+    let mut map = vec![];
+    if let Some(prp_iter) = prp.tryc_indexer() {
+        for i in prp_iter {
+            if let Some(v) = state.table.get(&i) {
+                map.push((i, Some(v.clone())));
+            }
+        }
+    } else {
+        let prp = prp.into_filter();
+        for (i, v) in state.table.iter() {
+            if (prp)(v) { map.push((i.clone(), Some(v.clone()))) }
+        }
+    }
+    Mapper::from_mapping(map.into_iter())
+}
+
+impl<V: Id, T: Tx<V>> Dump<V> for Mpt<V, T>
+where
+    V: Sync + Send + Clone + Codec + TrieLeaf,
+    V::I: Eq + Hash + Sync + Send + Clone,
+    T::I: Eq + Hash,
+    T::Prp: Filter<V>,
+    T::Map: Mapper<V::I, V>,
+{
+    type Err = MptErr;
+    fn dump(&self, f: &mut dyn FnMut(&V::I, &V)) -> Result<(), Self::Err> {
+        // the table only holds committed writes
+        for (i, v) in self.state.read().table.iter() { f(i, v) }
+        Ok(())
+    }
+}
@@ -0,0 +1,13 @@
+#[derive(Debug)]
+pub enum MptErr {
+    // file system error
+    Io(std::io::Error),
+    // a log record at a given offset cannot be decoded
+    Corrupt(u64),
+}
+
+impl From<std::io::Error> for MptErr {
+    fn from(e: std::io::Error) -> Self {
+        MptErr::Io(e)
+    }
+}
@@ -0,0 +1,22 @@
+//! ## Merkle Patricia Trie
+//!
+//! > Wood, Gavin. "Ethereum: A secure decentralised generalised transaction ledger." Ethereum Yellow Paper (2014), Appendix D.
+//!
+//! A durable engine that keeps committed state in an ethereum-compatible (secure) merkle patricia trie, so a state root can be taken at any point.
+//! Like a storage trie, the path of a data item is the keccak hash of its key, and its leaf holds the rlp encoding of its value (see TrieLeaf).
+//! Writes of a transaction are buffered until it is ready, then applied to a table for reads and to the trie, and logged as one record.
+//! Node references (hashes, or inlined rlp of small nodes) are cached and only recomputed along changed paths, so a root costs the trie-update work since the last one.
+//! Sealing a block takes the state root after all transactions committed so far, so parallel protocols can be checked against serial execution.
+
+// mpt error
+mod error;
+// trie nodes, rlp and hex-prefix encoding
+mod trie;
+// core mpt engine implementation
+mod engine;
+
+pub use error::*;
+pub use engine::*;
+
+#[cfg(test)]
+mod check; // state root checks
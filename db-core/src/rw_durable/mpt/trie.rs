@@ -0,0 +1,178 @@
+use crate::utilities::*;
+
+/// rlp encoding of a byte string
+pub(super) fn rlp_bytes(bytes: &[u8], buf: &mut Vec<u8>) {
+    match bytes.len() {
+        1 if bytes[0] < 0x80 => {}
+        n => rlp_head(0x80, n, buf),
+    }
+    buf.extend(bytes);
+}
+
+/// rlp encoding of a list, whose items are already encoded in payload
+fn rlp_list(payload: &[u8], buf: &mut Vec<u8>) {
+    rlp_head(0xc0, payload.len(), buf);
+    buf.extend(payload);
+}
+
+fn rlp_head(base: u8, len: usize, buf: &mut Vec<u8>) {
+    if len < 56 { return buf.push(base + len as u8) }
+    let len = len.to_be_bytes();
+    let len = &len[len.iter().take_while(|b| **b == 0).count()..];
+    buf.push(base + 55 + len.len() as u8);
+    buf.extend(len);
+}
+
+/// hex-prefix encoding of a nibble path, with a flag telling leaves from extensions
+fn hex_prefix(path: &[u8], leaf: bool) -> Vec<u8> {
+    let flag = if leaf { 2 } else { 0 } + path.len() as u8 % 2;
+    let (mut out, rest) = match path.len() % 2 {
+        1 => (vec![flag << 4 | path[0]], &path[1..]),
+        _ => (vec![flag << 4], path),
+    };
+    out.extend(rest.chunks(2).map(|pair| pair[0] << 4 | pair[1]));
+    out
+}
+
+fn nibbles(key: &[u8]) -> Vec<u8> {
+    key.iter().flat_map(|b| [b >> 4, b & 0x0f]).collect()
+}
+
+// all paths in a trie have the same length, so no path is a prefix of another and branches carry no value
+enum Kind {
+    Empty,
+    Leaf(Vec<u8>, Vec<u8>),
+    Ext(Vec<u8>, Box<Node>),
+    Branch(Box<[Node; 16]>),
+}
+
+struct Node {
+    kind: Kind,
+    // the reference of this node from its parent, none if it is changed since the last root
+    memo: Option<Vec<u8>>,
+}
+
+impl Node {
+    fn new(kind: Kind) -> Self {
+        Node { kind, memo: None }
+    }
+    fn empty() -> Self {
+        Node::new(Kind::Empty)
+    }
+    fn leaf(path: &[u8], val: Vec<u8>) -> Self {
+        Node::new(Kind::Leaf(path.to_vec(), val))
+    }
+    // an extension, merged into its child when possible, so the trie stays canonical
+    fn ext(path: &[u8], child: Node) -> Self {
+        if path.is_empty() { return child }
+        match child.kind {
+            Kind::Empty => Node::empty(),
+            Kind::Leaf(rest, val) => Node::new(Kind::Leaf([path, &rest].concat(), val)),
+            Kind::Ext(rest, grand) => Node::new(Kind::Ext([path, &rest].concat(), grand)),
+            kind => Node::new(Kind::Ext(path.to_vec(), Box::new(Node { kind, memo: child.memo }))),
+        }
+    }
+    fn insert(self, path: &[u8], val: Vec<u8>) -> Self {
+        match self.kind {
+            Kind::Empty => Node::leaf(path, val),
+            Kind::Leaf(p, _) if p == path => Node::leaf(path, val),
+            Kind::Leaf(p, v) => {
+                // two leaves under a branch below the common prefix
+                let n = p.iter().zip(path).take_while(|(a, b)| a == b).count();
+                let mut kids: Box<[Node; 16]> = Box::new(std::array::from_fn(|_| Node::empty()));
+                kids[p[n] as usize] = Node::leaf(&p[n + 1..], v);
+                kids[path[n] as usize] = Node::leaf(&path[n + 1..], val);
+                Node::ext(&path[..n], Node::new(Kind::Branch(kids)))
+            }
+            Kind::Ext(p, child) => {
+                let n = p.iter().zip(path).take_while(|(a, b)| a == b).count();
+                if n == p.len() { return Node::ext(&p, child.insert(&path[n..], val)) }
+                // split the extension at the common prefix
+                let mut kids: Box<[Node; 16]> = Box::new(std::array::from_fn(|_| Node::empty()));
+                kids[p[n] as usize] = Node::ext(&p[n + 1..], *child);
+                kids[path[n] as usize] = Node::leaf(&path[n + 1..], val);
+                Node::ext(&path[..n], Node::new(Kind::Branch(kids)))
+            }
+            Kind::Branch(mut kids) => {
+                let i = path[0] as usize;
+                kids[i] = std::mem::replace(&mut kids[i], Node::empty()).insert(&path[1..], val);
+                Node::new(Kind::Branch(kids))
+            }
+        }
+    }
+    fn remove(self, path: &[u8]) -> Self {
+        match self.kind {
+            Kind::Leaf(p, _) if p == path => Node::empty(),
+            Kind::Ext(p, child) if path.starts_with(&p) => Node::ext(&p, child.remove(&path[p.len()..])),
+            Kind::Branch(mut kids) => {
+                let i = path[0] as usize;
+                kids[i] = std::mem::replace(&mut kids[i], Node::empty()).remove(&path[1..]);
+                let mut live = (0..16).filter(|i| !matches!(kids[*i].kind, Kind::Empty));
+                match (live.next(), live.next()) {
+                    (None, _) => Node::empty(),
+                    // a branch with a single child collapses into an extension
+                    (Some(j), None) => Node::ext(&[j as u8], std::mem::replace(&mut kids[j], Node::empty())),
+                    _ => Node::new(Kind::Branch(kids)),
+                }
+            }
+            kind => Node { kind, memo: self.memo },
+        }
+    }
+    // rlp encoding of this node
+    fn encode(&mut self) -> Vec<u8> {
+        let mut payload = vec![];
+        match &mut self.kind {
+            Kind::Empty => return vec![0x80],
+            Kind::Leaf(path, val) => {
+                rlp_bytes(&hex_prefix(path, true), &mut payload);
+                rlp_bytes(val, &mut payload);
+            }
+            Kind::Ext(path, child) => {
+                rlp_bytes(&hex_prefix(path, false), &mut payload);
+                payload.extend(child.reference());
+            }
+            Kind::Branch(kids) => {
+                for kid in kids.iter_mut() { payload.extend(kid.reference()) }
+                payload.push(0x80);
+            }
+        }
+        let mut buf = vec![];
+        rlp_list(&payload, &mut buf);
+        buf
+    }
+    // the reference of this node, its rlp if shorter than 32 bytes, else the rlp of its hash
+    fn reference(&mut self) -> Vec<u8> {
+        if let Some(memo) = &self.memo { return memo.clone() }
+        let enc = self.encode();
+        let memo = if enc.len() < 32 { enc } else {
+            let mut buf = vec![];
+            rlp_bytes(&keccak256(&enc), &mut buf);
+            buf
+        };
+        self.memo = Some(memo.clone());
+        memo
+    }
+}
+
+/// a merkle patricia trie over keys of the same length
+pub(super) struct Trie {
+    root: Node,
+}
+
+impl Trie {
+    pub fn new() -> Self {
+        Trie { root: Node::empty() }
+    }
+    pub fn insert(&mut self, key: &[u8], val: Vec<u8>) {
+        let root = std::mem::replace(&mut self.root, Node::empty());
+        self.root = root.insert(&nibbles(key), val);
+    }
+    pub fn remove(&mut self, key: &[u8]) {
+        let root = std::mem::replace(&mut self.root, Node::empty());
+        self.root = root.remove(&nibbles(key));
+    }
+    /// the root hash, the root is always hashed even if it is small
+    pub fn root(&mut self) -> [u8; 32] {
+        keccak256(&self.root.encode())
+    }
+}
@@ -1,3 +1,4 @@
+use crate::rw_durable::snapshot::Dump;
 use std::hash::Hash;
 use typing::constraint::*;
 use typing::rw::*;
@@ -34,6 +35,38 @@ where
             phant: PhantomData,
         }
     }
+    // read a proposition without latency
+    fn view(&self, prp: T::Prp) -> T::Map
+    where
+        T::Prp: MaybeIndexer<V::I>,
+    {
+        let mut map = vec![];
+        if let Some(prp_iter) = prp.tryc_indexer() {
+            for i in prp_iter {
+                self.table.view(&i, |i, v| {
+                    map.push((i.clone(), Some(v.clone())))
+                });
+            }
+        } else {
+            let prp = prp.into_filter();
+            self.table.alter_all(|i, v| {
+                if (prp)(&v) {
+                    map.push((i.clone(), Some(v.clone())));
+                }
+                return v;
+            });
+        }
+        Mapper::from_mapping(map.into_iter())
+    }
+    // apply a map without latency
+    fn apply(&self, map: T::Map) {
+        for (i, v) in map.into_mapping() {
+            match v {
+                Some(v) => {self.table.insert(i, v);}, 
+                None => {self.table.remove(&i);},
+            }
+        }
+    }
 }
 
 impl<V: Id, T: Tx<V>> RWDurable<V, T> for Null<V, T>
@@ -55,36 +88,46 @@ where
         if self.null_write { 
             return Ok(Mapper::from_mapping([].into_iter())) ;
         }
-        let mut map = vec![];
         for _ in 0..self.latency_rd { std::thread::yield_now() }
-        if let Some(prp_iter) = prp.tryc_indexer() {
-            for i in prp_iter {
-                self.table.view(&i, |i, v| {
-                    map.push((i.clone(), Some(v.clone())))
-                });
-            }
-        } else {
-            let prp = prp.into_filter();
-            self.table.alter_all(|i, v| {
-                if (prp)(&v) {
-                    map.push((i.clone(), Some(v.clone())));
-                }
-                return v;
-            });
-        }
-        Ok(Mapper::from_mapping(map.into_iter()))
+        Ok(self.view(prp))
     }
     fn wr(&self, _txn: &T, map: T::Map) -> Result<(), Self::Err> {
         if self.null_write { 
             return Ok(());
         }
         for _ in 0..self.latency_wr { std::thread::yield_now() }
-        for (i, v) in map.into_mapping() {
-            match v {
-                Some(v) => {self.table.insert(i, v);}, 
-                None => {self.table.remove(&i);},
-            }
+        self.apply(map);
+        Ok(())
+    }
+    fn rd_batch(&self, prps: Vec<T::Prp>) -> Result<Vec<T::Map>, Self::Err> {
+        if self.null_write { 
+            return Ok(prps.iter().map(|_| Mapper::from_mapping([].into_iter())).collect());
         }
+        // latency is paid once for a batch
+        for _ in 0..self.latency_rd { std::thread::yield_now() }
+        Ok(prps.into_iter().map(|prp| self.view(prp)).collect())
+    }
+    fn wr_batch(&self, _txn: &T, maps: Vec<T::Map>) -> Result<(), Self::Err> {
+        if self.null_write { 
+            return Ok(());
+        }
+        for _ in 0..self.latency_wr { std::thread::yield_now() }
+        for map in maps { self.apply(map) }
+        Ok(())
+    }
+}
+
+impl<V: Id, T: Tx<V>> Dump<V> for Null<V, T>
+where
+    V: Sync + Clone,
+    V::I: Eq + Hash + Sync + Clone,
+    T::I: Eq + Hash + Sync + Clone,
+    T::Prp: Filter<V>,
+    T::Map: Mapper<V::I, V>,
+{
+    type Err = ();
+    fn dump(&self, f: &mut dyn FnMut(&V::I, &V)) -> Result<(), Self::Err> {
+        for e in self.table.iter() { f(e.key(), e.value()) }
         Ok(())
     }
 }
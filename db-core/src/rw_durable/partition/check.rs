@@ -0,0 +1,183 @@
+use super::*;
+use super::record::*;
+use crate::rw_control::Serial;
+use crate::rw_durable::check_util::*;
+use crate::rw_durable::cmd_log::replay_cmds;
+use crate::rw_durable::latency::*;
+use crate::rw_durable::null::*;
+use crate::rw_durable::wal::*;
+use crate::tx_service::m_thread::*;
+use crate::utilities::*;
+use db_test::core_workload::int::unif::*;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering::*};
+use std::sync::Arc;
+use std::time::Duration;
+use typing::rw::*;
+use typing::tx::*;
+
+const N_TXN: u64 = 1000;
+const RWAC: (u64, u64, u64, u64) = (15, 15, 1, 4);
+const VRNG: u64 = 64;
+const SEED: u64 = 1145141919810;
+
+type Mem = Null<U64Tup, U64Txn>;
+
+fn mem() -> Mem {
+    Null::new(0, 0, false)
+}
+
+fn wal(path: &Path) -> Wal<U64Tup, U64Txn> {
+    Wal::new(path).unwrap()
+}
+
+fn workload() -> Vec<U64Txn> {
+    let mut workload = U64Gen::new(SEED, RWAC, VRNG);
+    // transaction ids start from 1
+    (0..=N_TXN).map(|_| workload.get()).filter(|txn| txn.id() != 0).collect()
+}
+
+fn run<D>(dur: D, txns: Vec<U64Txn>) -> Vec<Option<u64>>
+where
+    D: RWDurable<U64Tup, U64Txn> + Send + Sync + 'static,
+    D::Err: std::fmt::Debug,
+{
+    let mut service = MThreadService::new(4, |x| x, Serial::<U64Txn, U64Tup>::new(), dur);
+    replay_cmds::<U64Tup, _, _>(txns, &mut service).unwrap_or_else(|_| panic!("fail to run service"))
+}
+
+// commit a transaction putting each key to itself plus an offset
+fn commit<D: RWDurable<U64Tup, U64Txn>>(dur: &D, txn: &U64Txn, keys: &[u64], off: u64) -> Result<(), D::Err> {
+    dur.open(txn)?;
+    for &k in keys { dur.wr(txn, U64Map(Some((k, Some(U64Tup(k, k + off))))))? }
+    dur.done(txn, End::Ready)
+}
+
+// an in-memory engine whose commits fail while it is down
+struct Flaky {
+    inner: Mem,
+    down: Arc<AtomicBool>,
+}
+
+impl RWDurable<U64Tup, U64Txn> for Flaky {
+    type Err = ();
+    fn open(&self, txn: &U64Txn) -> Result<(), ()> { self.inner.open(txn) }
+    fn rd(&self, prp: U64Prp) -> Result<U64Map, ()> { self.inner.rd(prp) }
+    fn wr(&self, txn: &U64Txn, map: U64Map) -> Result<(), ()> { self.inner.wr(txn, map) }
+    fn done(&self, txn: &U64Txn, end: End) -> Result<(), ()> {
+        if self.down.load(Acquire) { return Err(()) }
+        self.inner.done(txn, end)
+    }
+}
+
+#[test]
+fn is_hash_route_fixed() {
+    // routing is part of the on-disk format, so these partitions never change
+    let parts = (0..16u64).map(|k| Route::Hash.part::<U64Tup>(&k, 4)).collect::<Vec<_>>();
+    assert!(parts == vec![2, 1, 0, 3, 3, 0, 1, 2, 0, 3, 2, 1, 1, 2, 3, 0]);
+}
+
+#[test]
+fn is_same_as_one_engine() {
+    let (base, log) = (tmp_path("one"), tmp_path("log"));
+    let paths = (0..3).map(|p| tmp_path(&format!("part{p}"))).collect::<Vec<_>>();
+    let parts = || paths.iter().map(|path| shard(wal(path))).collect::<Vec<_>>();
+    let out = run(Partition::new(&log, Route::Hash, parts()).unwrap().with_parallel(), workload());
+    assert!(out == run(wal(&base), workload()));
+    // partitions recover the same state as one engine
+    let (one, dur) = (wal(&base), Partition::new(&log, Route::Hash, parts()).unwrap());
+    assert!((0..VRNG).all(|k| get(&dur, k) == get(&one, k)));
+    assert!((0..3).all(|p| (0..VRNG).any(|k| get(dur.part(p).as_ref(), k).is_some())));
+    drop((one, dur));
+    for path in paths.into_iter().chain([base, log]) {
+        std::fs::remove_file(path).unwrap();
+    }
+}
+
+#[test]
+fn is_range_tiered() {
+    // a hot tier in memory and a cold tier with a slow device, keys from 8 on are cold
+    let cold = Latency::new(mem(), 0).with_done(Dist::Fixed(Duration::from_millis(1)));
+    let log = tmp_path("tier");
+    let dur = Partition::new(&log, Route::Range(vec![8]), vec![shard(mem()), shard(cold)]).unwrap();
+    let txns = workload();
+    commit(&dur, &txns[0], &[1, 9, 12], 100).unwrap();
+    commit(&dur, &txns[1], &[2], 100).unwrap();
+    assert!(get(dur.part(0).as_ref(), 1) == Some(101) && get(dur.part(0).as_ref(), 9).is_none());
+    assert!(get(dur.part(1).as_ref(), 9) == Some(109) && get(dur.part(1).as_ref(), 1).is_none());
+    // a read spanning both tiers, and an aborted transaction leaving nothing
+    let maps = dur.rd_batch(vec![U64Prp(2), U64Prp(12)]).unwrap();
+    assert!(maps.iter().map(|U64Map(map)| map.and_then(|(_, v)| v).map(|U64Tup(_, v)| v)).collect::<Vec<_>>() == vec![Some(102), Some(112)]);
+    dur.open(&txns[2]).unwrap();
+    dur.wr(&txns[2], U64Map(Some((3, Some(U64Tup(3, 3)))))).unwrap();
+    dur.done(&txns[2], End::Abort).unwrap();
+    assert!(get(&dur, 3).is_none());
+    drop(dur);
+    std::fs::remove_file(log).unwrap();
+}
+
+#[test]
+fn is_torn_commit_redone() {
+    let log = tmp_path("torn");
+    let down = Arc::new(AtomicBool::new(false));
+    let flaky = Flaky { inner: mem(), down: down.clone() };
+    let dur = Partition::new(&log, Route::Range(vec![8]), vec![shard(mem()), shard(flaky)]).unwrap();
+    let txns = workload();
+    commit(&dur, &txns[0], &[0, 9], 100).unwrap();
+    commit(&dur, &txns[1], &[0], 200).unwrap();
+    // the second partition fails, after the first one has committed
+    down.store(true, Release);
+    assert!(matches!(commit(&dur, &txns[2], &[1, 10], 300), Err(PartErr::Inner(1, _))));
+    assert!(get(&dur, 1) == Some(301));
+    drop(dur);
+    // in-memory partitions start empty, so only redone commits show up
+    let dur = Partition::new(&log, Route::Range(vec![8]), vec![shard(mem()), shard(mem())]).unwrap();
+    assert!(get(&dur, 1) == Some(301) && get(&dur, 10) == Some(310));
+    assert!(get(&dur, 0).is_none() && get(&dur, 9).is_none());
+    // the log starts over after recovery
+    drop(dur);
+    let dur = Partition::new(&log, Route::Range(vec![8]), vec![shard(mem()), shard(mem())]).unwrap();
+    assert!(get(&dur, 1).is_none());
+    drop(dur);
+    std::fs::remove_file(log).unwrap();
+}
+
+// the numbers of commit records and applied markers in a commit log
+fn records(log: &Path) -> (usize, usize) {
+    let bytes = std::fs::read(log).unwrap();
+    let (mut cur, mut n) = (&bytes[..], (0, 0));
+    while let Ok(Some(payload)) = get_frame(&mut cur) {
+        match get_commit::<U64Tup, U64Txn>(payload).unwrap() {
+            Record::Commit(..) => n.0 += 1,
+            Record::Applied(_) => n.1 += 1,
+        }
+    }
+    n
+}
+
+#[test]
+fn is_marker_durable() {
+    let log = tmp_path("group");
+    let paths = (0..2).map(|p| tmp_path(&format!("group{p}"))).collect::<Vec<_>>();
+    let group = GroupCommit { size: 1 << 20, wait: Duration::from_secs(1) };
+    let parts = paths.iter().map(|path| shard(Wal::<U64Tup, U64Txn>::with_group(path, group).unwrap())).collect();
+    let dur = Partition::new(&log, Route::Range(vec![8]), parts).unwrap();
+    let txns = workload();
+    commit(&dur, &txns[0], &[1, 9], 100).unwrap();
+    // the commit is not durable in its partitions yet, so it has no marker, and a later commit to one partition is logged too
+    assert!(dur.durable_epoch() < dur.epoch());
+    commit(&dur, &txns[1], &[2], 100).unwrap();
+    assert!(records(&log) == (2, 0));
+    // once partitions sync, both get a marker on the next commit, which goes to its partition alone
+    while dur.durable_epoch() < dur.epoch() { std::thread::yield_now() }
+    commit(&dur, &txns[2], &[3], 100).unwrap();
+    assert!(records(&log) == (2, 2));
+    drop(dur);
+    let parts = paths.iter().map(|path| shard(wal(path))).collect();
+    let dur = Partition::new(&log, Route::Range(vec![8]), parts).unwrap();
+    assert!([1, 9, 2, 3].iter().all(|&k| get(&dur, k) == Some(k + 100)));
+    drop(dur);
+    for path in paths.into_iter().chain([log]) {
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,344 @@
+use super::error::*;
+use super::record::*;
+use super::route::*;
+use crate::utilities::*;
+use dashmap::DashMap;
+use parking_lot::Mutex;
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::hash::Hash;
+use std::io::{BufWriter, Read, Write};
+use std::iter::once;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering::*};
+use typing::constraint::*;
+use typing::rw::*;
+use typing::tx::*;
+
+// the commit log is truncated beyond this size, once no commit is in flight
+const LOG_LIMIT: u64 = 1 << 20;
+
+// the commit log tail
+struct Log {
+    // buffered appending handle
+    buf: BufWriter<File>,
+    // the sequence number of the last commit record
+    seq: u64,
+    // commit records without an applied marker yet
+    inflight: usize,
+    // the size of the log file
+    size: u64,
+}
+
+// a commit not durable in some partition yet, with its commit record if any and the epoch of each partition it writes
+type Wait = (Option<u64>, Vec<(usize, u64)>);
+
+// the epochs of commits, for a combined durable epoch over partitions
+#[derive(Default)]
+struct Epochs {
+    // the epoch of the last commit, every commit has an epoch of its own
+    last: u64,
+    // waiting commits by epoch
+    wait: BTreeMap<u64, Wait>,
+}
+
+pub struct Partition<V, T>
+where
+    V: Codec,
+    T: Tx<V> + TxCmd,
+    T::I: Eq + Hash,
+{
+    parts: Vec<Shard<V, T>>,
+    route: Route<V::I>,
+    // writes of open transactions
+    pending: DashMap<T::I, Writes<V>>,
+    log: Mutex<Log>,
+    epochs: Mutex<Epochs>,
+    // a cloned handle, so syncing doesn't block appending
+    file: File,
+    // whether applied markers are appended but not synced
+    dirty: AtomicBool,
+    // whether partitions are called on threads of their own
+    parallel: bool,
+}
+
+impl<V, T> Partition<V, T>
+where
+    V: Codec + Send + Sync,
+    V::I: Hash + Ord + Clone + Send + Sync,
+    T: Tx<V> + TxCmd + Sync,
+    T::I: Eq + Hash + Send + Sync,
+    T::Prp: MaybeIndexer<V::I> + Clone + Send,
+    T::Map: Mapper<V::I, V> + Send,
+{
+    /// open a commit log file (create it if absent) in front of partitions, in the same order as before a restart
+    /// commits applied in only some of their partitions are redone, then the log starts over
+    pub fn new(path: impl AsRef<Path>, route: Route<V::I>, parts: Vec<Shard<V, T>>) -> Result<Self, PartErr> {
+        assert!(!parts.is_empty(), "a partitioned engine should have a partition");
+        if let Route::Range(bounds) = &route {
+            assert!(bounds.len() + 1 == parts.len(), "there should be one range bound less than partitions");
+            assert!(bounds.is_sorted(), "range bounds should be sorted");
+        }
+        let mut file = OpenOptions::new()
+            .read(true).append(true).create(true)
+            .open(path)?;
+        let mut bytes = vec![];
+        file.read_to_end(&mut bytes)?;
+        let mut cur = &bytes[..];
+        let mut commits = BTreeMap::new();
+        loop {
+            let offset = (bytes.len() - cur.len()) as u64;
+            match get_frame(&mut cur) {
+                Ok(Some(payload)) => match get_commit::<V, T>(payload).ok_or(PartErr::Corrupt(offset))? {
+                    Record::Commit(seq, txn, map) => { commits.insert(seq, (txn, map)); }
+                    Record::Applied(seq) => { commits.remove(&seq); }
+                },
+                // a torn commit record was never synced, so its transaction never committed
+                Ok(None) | Err(_) => break,
+            }
+        }
+        let log = Log { buf: BufWriter::new(file.try_clone()?), seq: 0, inflight: 0, size: 0 };
+        let engine = Partition {
+            parts, route,
+            pending: DashMap::new(),
+            log: Mutex::new(log),
+            epochs: Mutex::new(Epochs::default()),
+            file,
+            dirty: AtomicBool::new(false),
+            parallel: false,
+        };
+        for (_, (txn, map)) in commits {
+            let groups = engine.group(map);
+            let parts = groups.keys().copied().collect::<Vec<_>>();
+            engine.apply(&txn, groups)?;
+            engine.track(None, parts);
+        }
+        while engine.durable_epoch() < engine.epoch() { std::thread::yield_now() }
+        // every commit is durable in all of its partitions now
+        engine.file.set_len(0)?;
+        engine.file.sync_all()?;
+        Ok(engine)
+    }
+    /// call partitions on threads of their own when a read or a commit spans several of them
+    /// a thread costs more than a call to an in-memory engine, so this pays off with slow partitions
+    pub fn with_parallel(mut self) -> Self {
+        self.parallel = true;
+        self
+    }
+    /// the i-th partition
+    pub fn part(&self, i: usize) -> &Shard<V, T> {
+        &self.parts[i]
+    }
+    // the partition of a key
+    fn route(&self, key: &V::I) -> usize {
+        self.route.part::<V>(key, self.parts.len())
+    }
+    // split writes by partition, keeping their order
+    fn group(&self, map: Writes<V>) -> BTreeMap<usize, Writes<V>> {
+        let mut groups = BTreeMap::<usize, Writes<V>>::new();
+        for (i, v) in map {
+            groups.entry(self.route(&i)).or_default().push((i, v));
+        }
+        groups
+    }
+    // run a job on each of some partitions, in parallel if enabled and there are several
+    fn fan<J, R>(&self, jobs: Vec<(usize, J)>, f: impl Fn(&Shard<V, T>, J) -> Result<R, ShardErr> + Sync)
+    -> Result<Vec<R>, PartErr>
+    where
+        J: Send,
+        R: Send,
+    {
+        if !self.parallel || jobs.len() < 2 {
+            return jobs.into_iter()
+                .map(|(p, job)| f(&self.parts[p], job).map_err(|e| PartErr::Inner(p, e)))
+                .collect();
+        }
+        std::thread::scope(|s| {
+            let f = &f;
+            let handles = jobs.into_iter()
+                .map(|(p, job)| (p, s.spawn(move || f(&self.parts[p], job))))
+                .collect::<Vec<_>>();
+            handles.into_iter()
+                .map(|(p, handle)| handle.join().unwrap().map_err(|e| PartErr::Inner(p, e)))
+                .collect()
+        })
+    }
+    // commit a transaction in each partition it writes
+    fn apply(&self, txn: &T, groups: BTreeMap<usize, Writes<V>>) -> Result<(), PartErr> {
+        self.fan(groups.into_iter().collect(), |part, map: Writes<V>| {
+            // a mapping may carry a single entry, so each key is a map
+            let maps = map.into_iter().map(|kv| Mapper::from_mapping(once(kv))).collect();
+            part.open(txn)?;
+            part.wr_batch(txn, maps)?;
+            part.done(txn, End::Ready)
+        })?;
+        Ok(())
+    }
+    // register a commit done in some partitions, with the epoch each of them put it in
+    fn track(&self, seq: Option<u64>, parts: Vec<usize>) {
+        let parts = parts.into_iter().map(|p| (p, self.parts[p].epoch())).collect();
+        let mut epochs = self.epochs.lock();
+        epochs.last += 1;
+        let last = epochs.last;
+        epochs.wait.insert(last, (seq, parts));
+    }
+    // whether every partition a commit writes has made it durable
+    fn covered(&self, parts: &[(usize, u64)]) -> bool {
+        parts.iter().all(|(p, epoch)| self.parts[*p].durable_epoch() >= *epoch)
+    }
+    // mark commits durable in all of their partitions applied, in epoch order
+    fn mark(&self) -> Result<(), PartErr> {
+        let mut epochs = self.epochs.lock();
+        while let Some(entry) = epochs.wait.first_entry() {
+            if !self.covered(&entry.get().1) { break }
+            if let (Some(seq), _) = entry.remove() { self.applied(seq)? }
+        }
+        Ok(())
+    }
+    // whether every commit record has an applied marker
+    fn idle(&self) -> bool {
+        self.log.lock().inflight == 0
+    }
+    // force a commit record, return its sequence number
+    fn commit(&self, txn: &T, map: &Writes<V>) -> Result<u64, PartErr> {
+        let seq = {
+            let mut log = self.log.lock();
+            log.seq += 1;
+            let (mut rec, mut frame) = (vec![], vec![]);
+            put_commit::<V, T>(log.seq, txn, map, &mut rec);
+            put_frame(&rec, &mut frame);
+            log.buf.write_all(&frame)?;
+            log.buf.flush()?;
+            log.size += frame.len() as u64;
+            log.inflight += 1;
+            log.seq
+        };
+        self.file.sync_data()?;
+        Ok(seq)
+    }
+    // mark a commit durable in all of its partitions, the marker is synced lazily by settle
+    fn applied(&self, seq: u64) -> Result<(), PartErr> {
+        let (mut rec, mut frame) = (vec![], vec![]);
+        put_applied(seq, &mut rec);
+        put_frame(&rec, &mut frame);
+        let mut log = self.log.lock();
+        log.inflight -= 1;
+        if log.inflight == 0 && log.size >= LOG_LIMIT {
+            // every logged commit is in all of its partitions, so the log is not needed anymore
+            log.buf.flush()?;
+            self.file.set_len(0)?;
+            self.file.sync_data()?;
+            log.size = 0;
+            self.dirty.store(false, Release);
+            return Ok(());
+        }
+        log.buf.write_all(&frame)?;
+        log.size += frame.len() as u64;
+        self.dirty.store(true, Release);
+        Ok(())
+    }
+    // make applied markers durable before a single partition commit, or recovery could redo an older commit over it
+    fn settle(&self) -> Result<(), PartErr> {
+        if !self.dirty.swap(false, AcqRel) { return Ok(()) }
+        self.log.lock().buf.flush()?;
+        self.file.sync_data()?;
+        Ok(())
+    }
+    // check a filter in every partition
+    fn scan(&self, prp: &T::Prp) -> Result<Writes<V>, PartErr> {
+        let jobs = (0..self.parts.len()).map(|p| (p, prp.clone())).collect();
+        let maps = self.fan(jobs, |part, prp| part.rd(prp))?;
+        Ok(maps.iter().flat_map(|map| map.into_mapping()).collect())
+    }
+}
+
+impl<V, T> RWDurable<V, T> for Partition<V, T>
+where
+    V: Codec + Send + Sync,
+    V::I: Hash + Ord + Clone + Send + Sync,
+    T: Tx<V> + TxCmd + Sync,
+    T::I: Eq + Hash + Send + Sync,
+    T::Prp: MaybeIndexer<V::I> + Clone + Send,
+    T::Map: Mapper<V::I, V> + Send,
+{
+    type Err = PartErr;
+    fn open(&self, _txn: &T) -> Result<(), Self::Err> {
+        // partitions are opened when a transaction commits in them
+        Ok(())
+    }
+    fn done(&self, txn: &T, end: End) -> Result<(), Self::Err> {
+        let map = self.pending.remove(&txn.id()).map(|(_, map)| map).unwrap_or_default();
+        // aborted and read-only transactions leave nothing in partitions
+        if matches!(end, End::Abort) || map.is_empty() { return Ok(()) }
+        self.mark()?;
+        let mut parts = map.iter().map(|(i, _)| self.route(i)).collect::<Vec<_>>();
+        parts.sort();
+        parts.dedup();
+        // a commit writing one partition goes through the commit log too while some commit record is not marked,
+        // so recovery redoes it after them instead of redoing an older commit over it
+        let seq = if parts.len() == 1 && self.idle() {
+            self.settle()?;
+            None
+        } else {
+            Some(self.commit(txn, &map)?)
+        };
+        // on a failure the commit record stays without a marker, and it is redone on startup
+        self.apply(txn, self.group(map))?;
+        self.track(seq, parts);
+        Ok(())
+    }
+    fn rd(&self, prp: T::Prp) -> Result<T::Map, Self::Err> {
+        Ok(self.rd_batch(vec![prp])?.pop().unwrap())
+    }
+    fn wr(&self, txn: &T, map: T::Map) -> Result<(), Self::Err> {
+        self.pending.entry(txn.id()).or_default().extend(map.into_mapping());
+        Ok(())
+    }
+    fn rd_batch(&self, prps: Vec<T::Prp>) -> Result<Vec<T::Map>, Self::Err> {
+        let mut found = prps.iter().map(|_| vec![]).collect::<Vec<Writes<V>>>();
+        // keys of all propositions by partition, each with the index of its proposition
+        let mut groups = BTreeMap::<usize, (Vec<usize>, Vec<T::Prp>)>::new();
+        for (n, prp) in prps.iter().enumerate() {
+            match prp.tryc_indexer() {
+                Some(keys) => for key in keys {
+                    let (idx, prps) = groups.entry(self.route(&key)).or_default();
+                    idx.push(n);
+                    prps.push(MaybeIndexer::from_indexer(once(key)));
+                },
+                None => found[n].extend(self.scan(prp)?),
+            }
+        }
+        let maps = self.fan(groups.into_iter().collect(), |part, (idx, prps): (Vec<usize>, Vec<T::Prp>)| {
+            Ok(idx.into_iter().zip(part.rd_batch(prps)?).collect::<Vec<_>>())
+        })?;
+        for (n, map) in maps.into_iter().flatten() {
+            found[n].extend(map.into_mapping());
+        }
+        Ok(found.into_iter().map(|map| Mapper::from_mapping(map.into_iter())).collect())
+    }
+    fn wr_batch(&self, txn: &T, maps: Vec<T::Map>) -> Result<(), Self::Err> {
+        self.pending.entry(txn.id()).or_default().extend(maps.iter().flat_map(|map| map.into_mapping()));
+        Ok(())
+    }
+    fn epoch(&self) -> u64 {
+        self.epochs.lock().last
+    }
+    // the epoch before the first commit not durable in all of its partitions
+    fn durable_epoch(&self) -> u64 {
+        let epochs = self.epochs.lock();
+        let wait = epochs.wait.iter().find(|(_, (_, parts))| !self.covered(parts));
+        wait.map_or(epochs.last, |(epoch, _)| epoch - 1)
+    }
+    fn submit(&self, prp: &T::Prp) -> Option<u64> {
+        // only a read within one partition is submitted, a ticket carries its partition
+        let parts = prp.tryc_indexer()?.map(|key| self.route(&key)).collect::<Vec<_>>();
+        let p = *parts.first()?;
+        if parts.iter().any(|q| *q != p) { return None }
+        let n = self.parts.len() as u64;
+        self.parts[p].submit(prp).map(|ticket| ticket * n + p as u64)
+    }
+    fn arrived(&self, ticket: u64) -> bool {
+        let n = self.parts.len() as u64;
+        self.parts[(ticket % n) as usize].arrived(ticket / n)
+    }
+}
@@ -0,0 +1,18 @@
+/// an error of a type erased partition
+pub type ShardErr = Box<dyn std::fmt::Debug + Send + Sync>;
+
+#[derive(Debug)]
+pub enum PartErr {
+    // commit log file error
+    Io(std::io::Error),
+    // an inner engine error, with the index of its partition
+    Inner(usize, ShardErr),
+    // a commit record at a given offset passes its checksum but cannot be decoded
+    Corrupt(u64),
+}
+
+impl From<std::io::Error> for PartErr {
+    fn from(e: std::io::Error) -> Self {
+        PartErr::Io(e)
+    }
+}
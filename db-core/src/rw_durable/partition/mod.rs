@@ -0,0 +1,28 @@
+//! ## Key Partitioning
+//!
+//! > Mohan, C., Bruce Lindsay, and Ron Obermarck. "Transaction management in the R* distributed database management system." ACM TODS 11.4 (1986): 378-396.
+//!
+//! A durable engine that partitions keys across inner engines, possibly of different types (e.g. a hot tier in memory and a cold tier on disk), by hash or by key ranges.
+//! The hash is the crc32c of the encoded key, so the routing of keys on disk is the same across runs, builds and platforms.
+//! Reads fan out to the partitions of their keys, a filter goes to every partition. Writes of a transaction stay in the engine until it is done.
+//! A transaction writing one partition commits there alone. A transaction writing several forces a commit record with all its writes to a commit log first,
+//! then commits in every partition it writes (in parallel if enabled), and appends an applied marker once the durable epoch of every such partition covers it.
+//! Aborts leave no record, as in presumed abort. While a commit record has no marker, commits writing one partition are logged as well.
+//! On startup, commit records without an applied marker are redone in every partition, so a transaction is committed in all of its partitions or in none.
+//! The durable epoch of the engine covers a commit once all of its partitions do, and the commit log is truncated whenever no commit is in flight and it grows too long.
+
+// partition error
+mod error;
+// key routing and type erased partitions
+mod route;
+// commit log records
+mod record;
+// core partitioned engine implementation
+mod engine;
+
+pub use error::*;
+pub use route::*;
+pub use engine::*;
+
+#[cfg(test)]
+mod check; // routing, atomic commit and recovery checks
@@ -0,0 +1,54 @@
+use crate::rw_durable::wal::{get_record, put_record};
+use typing::constraint::*;
+
+// tags of a commit log record
+const TAG_APPLIED: u8 = 0;
+const TAG_COMMIT: u8 = 1;
+
+/// the writes of a transaction, in order
+pub type Writes<V> = Vec<(<V as Id>::I, Option<V>)>;
+
+/// a decoded commit log record
+pub enum Record<V: Id, T> {
+    /// a transaction writing several partitions, with its command and writes
+    Commit(u64, T, Writes<V>),
+    /// a commit applied in all of its partitions
+    Applied(u64),
+}
+
+/// encode a commit record payload
+///     [tag: u8] [seq: u64] [command length: u32] [command] [write record]
+pub fn put_commit<V: Codec, T: TxCmd>(seq: u64, txn: &T, map: &[(V::I, Option<V>)], buf: &mut Vec<u8>) {
+    buf.push(TAG_COMMIT);
+    buf.extend(seq.to_le_bytes());
+    let mut cmd = vec![];
+    txn.put_cmd(&mut cmd);
+    buf.extend((cmd.len() as u32).to_le_bytes());
+    buf.extend(cmd);
+    put_record::<V>(map, buf);
+}
+
+/// encode an applied marker payload
+///     [tag: u8] [seq: u64]
+pub fn put_applied(seq: u64, buf: &mut Vec<u8>) {
+    buf.push(TAG_APPLIED);
+    buf.extend(seq.to_le_bytes());
+}
+
+/// decode a commit log record payload, none if the payload is malformed
+pub fn get_commit<V: Codec, T: TxCmd>(mut buf: &[u8]) -> Option<Record<V, T>> {
+    let buf = &mut buf;
+    let tag = take(buf, 1)?[0];
+    let seq = get_u64(buf)?;
+    match tag {
+        TAG_APPLIED if buf.is_empty() => Some(Record::Applied(seq)),
+        TAG_COMMIT => {
+            let len = get_u32(buf)? as usize;
+            let mut cmd = take(buf, len)?;
+            let txn = T::get_cmd(&mut cmd)?;
+            if !cmd.is_empty() { return None }
+            Some(Record::Commit(seq, txn, get_record::<V>(buf)?))
+        }
+        _ => None,
+    }
+}
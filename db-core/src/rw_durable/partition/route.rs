@@ -0,0 +1,94 @@
+use super::error::*;
+use crate::utilities::crc32c;
+use std::marker::PhantomData;
+use typing::constraint::*;
+use typing::rw::*;
+use typing::tx::*;
+
+/// how keys are routed to partitions
+#[derive(Debug, Clone)]
+pub enum Route<I> {
+    /// by the crc32c of a key encoded with Codec::put_id, modulo the number of partitions
+    /// it is part of the on-disk format: partitions keep their keys as long as the key encoding and the number of partitions stay the same
+    Hash,
+    /// by key ranges, partition i holds keys from bound i-1 (included) to bound i (excluded)
+    /// bounds are sorted and there is one bound less than partitions
+    Range(Vec<I>),
+}
+
+impl<I: Ord> Route<I> {
+    /// the partition of a key among n partitions, keys are encoded as ids of V
+    pub fn part<V: Codec<I = I>>(&self, key: &I, n: usize) -> usize {
+        match self {
+            Route::Hash => {
+                // a fixed function of the encoded key, unlike std hashers that may change between releases
+                let mut buf = vec![];
+                V::put_id(key, &mut buf);
+                (crc32c(&buf) as u64 % n as u64) as usize
+            }
+            Route::Range(bounds) => bounds.partition_point(|bound| bound <= key),
+        }
+    }
+}
+
+/// a partition, an inner engine with its error type erased
+pub type Shard<V, T> = Box<dyn RWDurable<V, T, Err = ShardErr> + Send + Sync>;
+
+/// erase the error type of an inner engine, so engines of different types can be partitions together
+pub fn shard<V, T, D>(inner: D) -> Shard<V, T>
+where
+    V: 'static,
+    T: Tx<V> + 'static,
+    D: RWDurable<V, T> + Send + Sync + 'static,
+    D::Err: std::fmt::Debug + Send + Sync + 'static,
+{
+    Box::new(Erased { inner, phant: PhantomData })
+}
+
+struct Erased<V, T, D> {
+    inner: D,
+    phant: PhantomData<fn() -> (V, T)>,
+}
+
+fn erase<E: std::fmt::Debug + Send + Sync + 'static>(e: E) -> ShardErr {
+    Box::new(e)
+}
+
+impl<V, T, D> RWDurable<V, T> for Erased<V, T, D>
+where
+    T: Tx<V>,
+    D: RWDurable<V, T>,
+    D::Err: std::fmt::Debug + Send + Sync + 'static,
+{
+    type Err = ShardErr;
+    fn open(&self, txn: &T) -> Result<(), Self::Err> {
+        self.inner.open(txn).map_err(erase)
+    }
+    fn done(&self, txn: &T, end: End) -> Result<(), Self::Err> {
+        self.inner.done(txn, end).map_err(erase)
+    }
+    fn rd(&self, prp: T::Prp) -> Result<T::Map, Self::Err> {
+        self.inner.rd(prp).map_err(erase)
+    }
+    fn wr(&self, txn: &T, map: T::Map) -> Result<(), Self::Err> {
+        self.inner.wr(txn, map).map_err(erase)
+    }
+    fn rd_batch(&self, prps: Vec<T::Prp>) -> Result<Vec<T::Map>, Self::Err> {
+        self.inner.rd_batch(prps).map_err(erase)
+    }
+    fn wr_batch(&self, txn: &T, maps: Vec<T::Map>) -> Result<(), Self::Err> {
+        self.inner.wr_batch(txn, maps).map_err(erase)
+    }
+    fn epoch(&self) -> u64 {
+        self.inner.epoch()
+    }
+    fn durable_epoch(&self) -> u64 {
+        self.inner.durable_epoch()
+    }
+    fn submit(&self, prp: &T::Prp) -> Option<u64> {
+        self.inner.submit(prp)
+    }
+    fn arrived(&self, ticket: u64) -> bool {
+        self.inner.arrived(ticket)
+    }
+}
@@ -0,0 +1,193 @@
+use super::*;
+use crate::rw_control::Serial;
+use crate::rw_durable::check_util::*;
+use crate::rw_durable::cmd_log::replay_cmds;
+use crate::rw_durable::latency::*;
+use crate::rw_durable::null::*;
+use crate::rw_durable::snapshot::Dump;
+use crate::rw_durable::wal::*;
+use crate::tx_service::m_thread::*;
+use db_test::core_workload::int::unif::*;
+use parking_lot::Mutex;
+use std::sync::Arc;
+use std::time::Duration;
+use typing::rw::*;
+use typing::tx::*;
+
+const N_TXN: u64 = 1000;
+const RWAC: (u64, u64, u64, u64) = (15, 15, 1, 4);
+const VRNG: u64 = 64;
+const SEED: u64 = 1145141919810;
+
+type Mem = Null<U64Tup, U64Txn>;
+
+fn mem() -> Mem {
+    Null::new(0, 0, false)
+}
+
+fn workload() -> Vec<U64Txn> {
+    let mut workload = U64Gen::new(SEED, RWAC, VRNG);
+    // transaction ids start from 1
+    (0..=N_TXN).map(|_| workload.get()).filter(|txn| txn.id() != 0).collect()
+}
+
+fn run<D>(dur: D, txns: Vec<U64Txn>) -> Vec<Option<u64>>
+where
+    D: RWDurable<U64Tup, U64Txn> + Send + Sync + 'static,
+    D::Err: std::fmt::Debug,
+{
+    let mut service = MThreadService::new(4, |x| x, Serial::<U64Txn, U64Tup>::new(), dur);
+    replay_cmds::<U64Tup, _, _>(txns, &mut service).unwrap_or_else(|_| panic!("fail to run service"))
+}
+
+fn state<D: Dump<U64Tup>>(dur: &D) -> Vec<(u64, u64)> {
+    let mut state = vec![];
+    let _ = dur.dump(&mut |k, U64Tup(_, v)| state.push((*k, *v)));
+    state.sort();
+    state
+}
+
+fn wal(path: &std::path::Path) -> Wal<U64Tup, U64Txn> {
+    Wal::new(path).unwrap()
+}
+
+fn put(k: u64) -> U64Map {
+    U64Map(Some((k, Some(U64Tup(k, k)))))
+}
+
+// an engine recording the order transactions are done in
+struct Order<D> {
+    inner: D,
+    done: Mutex<Vec<u64>>,
+}
+
+impl<D: RWDurable<U64Tup, U64Txn>> RWDurable<U64Tup, U64Txn> for Order<D> {
+    type Err = D::Err;
+    fn open(&self, txn: &U64Txn) -> Result<(), Self::Err> { self.inner.open(txn) }
+    fn done(&self, txn: &U64Txn, end: End) -> Result<(), Self::Err> {
+        self.inner.done(txn, end)?;
+        self.done.lock().push(txn.id());
+        // widen the gap between a commit and whatever follows it, by a different amount for each transaction
+        std::thread::sleep(Duration::from_micros(txn.id() % 7 * 20));
+        Ok(())
+    }
+    fn rd(&self, prp: U64Prp) -> Result<U64Map, Self::Err> { self.inner.rd(prp) }
+    fn wr(&self, txn: &U64Txn, map: U64Map) -> Result<(), Self::Err> { self.inner.wr(txn, map) }
+}
+
+fn order() -> Order<Mem> {
+    Order { inner: mem(), done: Mutex::new(vec![]) }
+}
+
+#[test]
+fn is_standby_same_as_primary() {
+    let path = tmp_path("primary");
+    let standbys = [Arc::new(Standby::new(mem())), Arc::new(Standby::new(mem()))];
+    let primary = standbys.iter().fold(Replica::new(wal(&path), Ack::Async), |primary, standby| primary.with_link(standby.clone()));
+    // a plain run over the same workload gives the same outputs
+    assert!(run(primary, workload()) == run(mem(), workload()));
+    // the primary is dropped with the service, after its shippers deliver every queued record
+    let expect = state(&wal(&path));
+    assert!(!expect.is_empty());
+    for standby in standbys.iter() {
+        assert!(standby.applied() == standbys[0].applied());
+        assert!(state(standby.as_ref()) == expect);
+    }
+    // a standby refuses local transactions
+    let txn = workload()[0];
+    assert!(matches!(standbys[0].open(&txn), Err(ReplErr::Standby)));
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn is_promoted_standby_primary() {
+    let standby = Arc::new(Standby::new(mem()));
+    let primary = Replica::new(mem(), Ack::Sync).with_link(standby.clone());
+    let txns = workload();
+    for (k, txn) in txns.iter().take(3).enumerate() {
+        primary.open(txn).unwrap_or_else(|_| panic!("fail to open"));
+        primary.wr(txn, put(k as u64)).unwrap_or_else(|_| panic!("fail to write"));
+        primary.done(txn, End::Ready).unwrap_or_else(|_| panic!("fail to commit"));
+    }
+    assert!(standby.applied() == 3 && primary.stall().0 == 3);
+    // after promotion, the old primary cannot replicate to it anymore
+    assert!(standby.promote() == 3);
+    let txn = &txns[3];
+    primary.open(txn).unwrap_or_else(|_| panic!("fail to open"));
+    primary.wr(txn, put(3)).unwrap_or_else(|_| panic!("fail to write"));
+    assert!(matches!(primary.done(txn, End::Ready), Err(ReplErr::Broken)));
+    // the promoted standby takes local transactions as a new primary
+    drop(primary);
+    let standby = Arc::try_unwrap(standby).unwrap_or_else(|_| panic!("standby is still shared"));
+    let next = Replica::new(standby, Ack::Async);
+    let txn = &txns[4];
+    next.open(txn).unwrap_or_else(|_| panic!("fail to open"));
+    next.wr(txn, put(4)).unwrap_or_else(|_| panic!("fail to write"));
+    next.done(txn, End::Ready).unwrap_or_else(|_| panic!("fail to commit"));
+    assert!(state(&next) == (0..5).filter(|k| *k != 3).map(|k| (k, k)).collect::<Vec<_>>());
+}
+
+#[cfg(unix)]
+#[test]
+fn is_shipped_over_socket() {
+    let (sock, path, log) = (tmp_path("sock"), tmp_path("primary"), tmp_path("standby"));
+    let standby = Arc::new(Standby::new(wal(&log)));
+    let server = serve(standby.clone(), &sock).unwrap();
+    run(Replica::new(wal(&path), Ack::Sync).with_link(SocketLink::connect(&sock).unwrap()), workload());
+    // dropping the primary closes the connection, and the standby stops serving
+    assert!(server.join().unwrap().is_ok());
+    let expect = state(&wal(&path));
+    assert!(state(standby.as_ref()) == expect);
+    // the standby log recovers the replicated state
+    drop(standby);
+    assert!(state(&wal(&log)) == expect);
+    for path in [sock, path, log] {
+        std::fs::remove_file(path).unwrap();
+    }
+}
+
+#[test]
+fn is_sync_commit_stalled() {
+    let lat = Duration::from_millis(2);
+    let slow = || Arc::new(Standby::new(Latency::new(mem(), 0).with_done(Dist::Fixed(lat))));
+    let commit = |primary: &Replica<U64Tup, U64Txn, Mem>| for (k, txn) in workload().iter().take(10).enumerate() {
+        primary.open(txn).unwrap_or_else(|_| panic!("fail to open"));
+        primary.wr(txn, put(k as u64)).unwrap_or_else(|_| panic!("fail to write"));
+        primary.done(txn, End::Ready).unwrap_or_else(|_| panic!("fail to commit"));
+    };
+    // a synchronous commit waits for the standby to apply it
+    let primary = Replica::new(mem(), Ack::Sync).with_link(slow());
+    commit(&primary);
+    let (waits, stall) = primary.stall();
+    assert!(waits == 10 && stall >= lat * 10);
+    // an asynchronous commit does not, the standby catches up later
+    let primary = Replica::new(mem(), Ack::Async).with_link(slow());
+    commit(&primary);
+    assert!(primary.stall().0 == 0);
+    primary.drain().unwrap_or_else(|_| panic!("fail to drain"));
+    assert!(primary.lag() == 0);
+}
+
+#[test]
+fn is_inner_commit_concurrent() {
+    let lat = Duration::from_millis(20);
+    let standby = Arc::new(Standby::new(order()));
+    let primary = Replica::new(Latency::new(mem(), 0).with_done(Dist::Fixed(lat)), Ack::Async).with_link(standby.clone());
+    let txns = workload().into_iter().take(32).collect::<Vec<_>>();
+    let start = std::time::Instant::now();
+    std::thread::scope(|scope| for chunk in txns.chunks(4) {
+        let primary = &primary;
+        scope.spawn(move || for txn in chunk {
+            primary.open(txn).unwrap_or_else(|_| panic!("fail to open"));
+            primary.wr(txn, put(txn.id())).unwrap_or_else(|_| panic!("fail to write"));
+            primary.done(txn, End::Ready).unwrap_or_else(|_| panic!("fail to commit"));
+        });
+    });
+    // inner commits of independent transactions overlap, one after another they would take 32 times the latency
+    assert!(start.elapsed() < lat * 16);
+    primary.drain().unwrap_or_else(|_| panic!("fail to drain"));
+    assert!(primary.shipped() == 32);
+    let mut done = standby.inner().done.lock().clone();
+    done.sort();
+    assert!(done == txns.iter().map(|txn| txn.id()).collect::<Vec<_>>());
+}
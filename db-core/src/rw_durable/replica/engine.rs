@@ -0,0 +1,239 @@
+use super::error::*;
+use super::link::*;
+use super::record::*;
+use crate::rw_durable::snapshot::Dump;
+use dashmap::DashMap;
+use parking_lot::{Condvar, Mutex};
+use std::hash::Hash;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering::*};
+use std::sync::mpsc::{channel, Sender};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use typing::constraint::*;
+use typing::rw::*;
+use typing::tx::*;
+
+/// when a commit returns
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ack {
+    /// once the primary has it, standbys catch up in the background
+    Async,
+    /// once every standby has applied it as well
+    Sync,
+}
+
+// a shipped record payload, shared by the queues of all shippers
+type Rec = Arc<Vec<u8>>;
+
+// the acknowledgement progress of a standby, updated by its shipper thread
+#[derive(Default)]
+struct Progress {
+    // the last sequence number the standby applied
+    acked: AtomicU64,
+    // whether the link broke, then acked never moves again
+    broken: AtomicBool,
+}
+
+pub struct Replica<V, T, D>
+where
+    V: Codec,
+    T: Tx<V> + TxCmd,
+    T::I: Eq + Hash,
+    D: RWDurable<V, T>,
+{
+    inner: D,
+    ack: Ack,
+    // writes of open transactions
+    pending: DashMap<T::I, Writes<V>>,
+    // the last sequence number and the queues of shippers, locked right after an inner commit to number and queue its record
+    queue: Mutex<(u64, Vec<Sender<Rec>>)>,
+    // progress of every standby, with a condition notified whenever one moves
+    progs: Vec<Arc<Progress>>,
+    moved: Arc<(Mutex<()>, Condvar)>,
+    shippers: Vec<JoinHandle<()>>,
+    // the number of commits that waited for acknowledgements, and their total wait in nanoseconds
+    waits: AtomicU64,
+    stall: AtomicU64,
+    phant: PhantomData<T>,
+}
+
+impl<V, T, D> Replica<V, T, D>
+where
+    V: Codec,
+    T: Tx<V> + TxCmd,
+    T::I: Eq + Hash,
+    T::Map: Mapper<V::I, V>,
+    D: RWDurable<V, T>,
+{
+    /// a primary over an inner engine, links to standbys are added with with_link before the first commit
+    pub fn new(inner: D, ack: Ack) -> Self {
+        Replica {
+            inner, ack,
+            pending: DashMap::new(),
+            queue: Mutex::new((0, vec![])),
+            progs: vec![],
+            moved: Arc::new((Mutex::new(()), Condvar::new())),
+            shippers: vec![],
+            waits: AtomicU64::new(0),
+            stall: AtomicU64::new(0),
+            phant: PhantomData,
+        }
+    }
+    /// ship to a standby in the same state as the primary, on a shipper thread of its own
+    pub fn with_link(mut self, mut link: impl Link + 'static) -> Self {
+        let (tx, rx) = channel::<Rec>();
+        let prog = Arc::new(Progress::default());
+        let moved = self.moved.clone();
+        let shipper = {
+            let prog = prog.clone();
+            std::thread::spawn(move || {
+                while let Ok(rec) = rx.recv() {
+                    // records queued meanwhile go out together
+                    let mut recs = vec![rec];
+                    recs.extend(rx.try_iter());
+                    match link.ship(&recs) {
+                        Ok(seq) => prog.acked.store(seq, Release),
+                        Err(_) => prog.broken.store(true, Release),
+                    }
+                    let _lock = moved.0.lock();
+                    moved.1.notify_all();
+                    if prog.broken.load(Acquire) { break }
+                }
+            })
+        };
+        self.queue.get_mut().1.push(tx);
+        self.progs.push(prog);
+        self.shippers.push(shipper);
+        self
+    }
+    /// the inner durable engine
+    pub fn inner(&self) -> &D {
+        &self.inner
+    }
+    /// the sequence number of the last shipped record
+    pub fn shipped(&self) -> u64 {
+        self.queue.lock().0
+    }
+    /// the number of records the slowest standby is behind
+    pub fn lag(&self) -> u64 {
+        let acked = self.progs.iter().map(|prog| prog.acked.load(Acquire)).min();
+        acked.map_or(0, |acked| self.shipped().saturating_sub(acked))
+    }
+    /// the number of commits that waited for acknowledgements and their total wait
+    pub fn stall(&self) -> (u64, Duration) {
+        (self.waits.load(Acquire), Duration::from_nanos(self.stall.load(Acquire)))
+    }
+    /// wait until every standby has applied all shipped records, e.g. before promoting one
+    pub fn drain(&self) -> Result<(), ReplErr<D::Err>> {
+        self.wait(self.shipped())
+    }
+    // wait until every standby has applied a sequence number
+    fn wait(&self, seq: u64) -> Result<(), ReplErr<D::Err>> {
+        let mut lock = self.moved.0.lock();
+        loop {
+            if self.progs.iter().any(|prog| prog.broken.load(Acquire)) { return Err(ReplErr::Broken) }
+            if self.progs.iter().all(|prog| prog.acked.load(Acquire) >= seq) { return Ok(()) }
+            self.moved.1.wait(&mut lock);
+        }
+    }
+}
+
+impl<V, T, D> RWDurable<V, T> for Replica<V, T, D>
+where
+    V: Codec,
+    T: Tx<V> + TxCmd,
+    T::I: Eq + Hash,
+    T::Map: Mapper<V::I, V>,
+    D: RWDurable<V, T>,
+{
+    type Err = ReplErr<D::Err>;
+    fn open(&self, txn: &T) -> Result<(), Self::Err> {
+        self.inner.open(txn).map_err(ReplErr::Inner)
+    }
+    fn done(&self, txn: &T, end: End) -> Result<(), Self::Err> {
+        let map = self.pending.remove(&txn.id()).map(|(_, map)| map).unwrap_or_default();
+        // aborted and read-only transactions leave nothing to replicate
+        if matches!(end, End::Abort) || map.is_empty() || self.progs.is_empty() {
+            return self.inner.done(txn, end).map_err(ReplErr::Inner);
+        }
+        let mut rec = vec![];
+        put_ship::<V, T>(0, txn, &map, &mut rec);
+        // the inner commit runs outside the queue lock, so commits of other transactions go on meanwhile
+        // a protocol holds back a conflicting transaction until this done returns, so it gets a later number
+        self.inner.done(txn, end).map_err(ReplErr::Inner)?;
+        let seq = {
+            // numbered and queued under one lock, so every shipper gets records in sequence order
+            let mut queue = self.queue.lock();
+            queue.0 += 1;
+            set_seq(queue.0, &mut rec);
+            let rec = Arc::new(rec);
+            // a broken shipper has dropped its queue, the wait below reports it
+            for tx in queue.1.iter() { let _ = tx.send(rec.clone()); }
+            queue.0
+        };
+        if let Ack::Sync = self.ack {
+            let start = Instant::now();
+            self.wait(seq)?;
+            self.waits.fetch_add(1, AcqRel);
+            self.stall.fetch_add(start.elapsed().as_nanos() as u64, AcqRel);
+        }
+        Ok(())
+    }
+    fn rd(&self, prp: T::Prp) -> Result<T::Map, Self::Err> {
+        self.inner.rd(prp).map_err(ReplErr::Inner)
+    }
+    fn wr(&self, txn: &T, map: T::Map) -> Result<(), Self::Err> {
+        self.pending.entry(txn.id()).or_default().extend(map.into_mapping());
+        self.inner.wr(txn, map).map_err(ReplErr::Inner)
+    }
+    fn rd_batch(&self, prps: Vec<T::Prp>) -> Result<Vec<T::Map>, Self::Err> {
+        self.inner.rd_batch(prps).map_err(ReplErr::Inner)
+    }
+    fn wr_batch(&self, txn: &T, maps: Vec<T::Map>) -> Result<(), Self::Err> {
+        self.pending.entry(txn.id()).or_default().extend(maps.iter().flat_map(|map| map.into_mapping()));
+        self.inner.wr_batch(txn, maps).map_err(ReplErr::Inner)
+    }
+    fn epoch(&self) -> u64 {
+        self.inner.epoch()
+    }
+    fn durable_epoch(&self) -> u64 {
+        self.inner.durable_epoch()
+    }
+    fn submit(&self, prp: &T::Prp) -> Option<u64> {
+        self.inner.submit(prp)
+    }
+    fn arrived(&self, ticket: u64) -> bool {
+        self.inner.arrived(ticket)
+    }
+}
+
+impl<V, T, D> Dump<V> for Replica<V, T, D>
+where
+    V: Codec,
+    T: Tx<V> + TxCmd,
+    T::I: Eq + Hash,
+    D: RWDurable<V, T> + Dump<V>,
+{
+    type Err = <D as Dump<V>>::Err;
+    fn dump(&self, f: &mut dyn FnMut(&V::I, &V)) -> Result<(), Self::Err> {
+        self.inner.dump(f)
+    }
+}
+
+impl<V, T, D> Drop for Replica<V, T, D>
+where
+    V: Codec,
+    T: Tx<V> + TxCmd,
+    T::I: Eq + Hash,
+    D: RWDurable<V, T>,
+{
+    fn drop(&mut self) {
+        // closing the queues lets shippers deliver what is queued and exit
+        self.queue.get_mut().1.clear();
+        for shipper in self.shippers.drain(..) {
+            let _ = shipper.join();
+        }
+    }
+}
@@ -0,0 +1,23 @@
+#[derive(Debug)]
+pub enum ReplErr<E> {
+    // file system or socket error
+    Io(std::io::Error),
+    // primary or standby engine error
+    Inner(E),
+    // a shipped record cannot be decoded
+    Corrupt,
+    // a shipped record skips ahead of the next expected sequence number
+    Gap(u64),
+    // a standby refuses local transactions until it is promoted
+    Standby,
+    // a promoted standby refuses shipped records
+    Promoted,
+    // a link to a standby broke, later commits are not replicated to it
+    Broken,
+}
+
+impl<E> From<std::io::Error> for ReplErr<E> {
+    fn from(e: std::io::Error) -> Self {
+        ReplErr::Io(e)
+    }
+}
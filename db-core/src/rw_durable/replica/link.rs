@@ -0,0 +1,30 @@
+use super::standby::*;
+use std::io;
+use std::sync::Arc;
+use typing::constraint::*;
+use typing::rw::*;
+use typing::tx::*;
+
+/// a way to reach a standby, driven by one shipper thread
+pub trait Link: Send {
+    /// ship record payloads in sequence order, return the last sequence number the standby applied after them
+    fn ship(&mut self, recs: &[Arc<Vec<u8>>]) -> io::Result<u64>;
+}
+
+// a standby in the same process applies records on the shipper thread
+impl<V, T, D> Link for Arc<Standby<V, T, D>>
+where
+    V: Codec,
+    T: Tx<V> + TxCmd,
+    T::Map: Mapper<V::I, V>,
+    D: RWDurable<V, T>,
+    Standby<V, T, D>: Send + Sync,
+{
+    fn ship(&mut self, recs: &[Arc<Vec<u8>>]) -> io::Result<u64> {
+        let mut seq = self.applied();
+        for rec in recs {
+            seq = self.apply(rec).map_err(|_| io::Error::other("standby refused a record"))?;
+        }
+        Ok(seq)
+    }
+}
@@ -0,0 +1,34 @@
+//! ## Log-Shipping Replication
+//!
+//! > Alsberg, Peter A., and John D. Day. "A principle for resilient sharing of distributed resources." ICSE 1976.
+//!
+//! A wrapper around a primary durable engine that ships every committed transaction to standby engines, in the same process or, on unix, over a local socket.
+//! A shipped record carries a sequence number, the transaction command and its writes, standbys apply records in sequence order through their own engine and acknowledge them once done.
+//! A record is numbered once its inner commit returns, so inner commits of independent transactions overlap, and conflicting ones, held back by the protocol, are numbered in commit order.
+//! With asynchronous acknowledgement a commit returns once the primary has it, and standbys lag behind; with synchronous acknowledgement it also waits until every standby has applied it.
+//! The time commits spend waiting for acknowledgements is accounted, so the cost of synchronous replication can be measured on a workload.
+//! A standby serves reads but refuses local transactions until it is promoted, after which it refuses shipped records and can be wrapped as a new primary.
+
+// replication error
+mod error;
+// shipped record encoding
+mod record;
+// standby engine, applying shipped records
+mod standby;
+// links from a primary to its standbys
+mod link;
+// a link over a unix socket
+#[cfg(unix)]
+mod socket;
+// core replication wrapper
+mod engine;
+
+pub use error::*;
+pub use standby::*;
+pub use link::*;
+#[cfg(unix)]
+pub use socket::*;
+pub use engine::*;
+
+#[cfg(test)]
+mod check; // shipping, promotion and socket checks
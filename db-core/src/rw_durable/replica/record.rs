@@ -0,0 +1,32 @@
+use crate::rw_durable::wal::{get_record, put_record};
+use typing::constraint::*;
+
+/// the writes of a transaction, in order
+pub type Writes<V> = Vec<(<V as Id>::I, Option<V>)>;
+
+/// encode a shipped record payload
+///     [seq: u64] [command length: u32] [command] [write record]
+pub fn put_ship<V: Codec, T: TxCmd>(seq: u64, txn: &T, map: &[(V::I, Option<V>)], buf: &mut Vec<u8>) {
+    buf.extend(seq.to_le_bytes());
+    let mut cmd = vec![];
+    txn.put_cmd(&mut cmd);
+    buf.extend((cmd.len() as u32).to_le_bytes());
+    buf.extend(cmd);
+    put_record::<V>(map, buf);
+}
+
+/// set the sequence number of an encoded payload
+pub fn set_seq(seq: u64, buf: &mut [u8]) {
+    buf[..8].copy_from_slice(&seq.to_le_bytes());
+}
+
+/// decode a shipped record payload, none if the payload is malformed
+pub fn get_ship<V: Codec, T: TxCmd>(mut buf: &[u8]) -> Option<(u64, T, Writes<V>)> {
+    let buf = &mut buf;
+    let seq = get_u64(buf)?;
+    let len = get_u32(buf)? as usize;
+    let mut cmd = take(buf, len)?;
+    let txn = T::get_cmd(&mut cmd)?;
+    if !cmd.is_empty() { return None }
+    Some((seq, txn, get_record::<V>(buf)?))
+}
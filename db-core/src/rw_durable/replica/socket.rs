@@ -0,0 +1,73 @@
+use super::error::*;
+use super::link::*;
+use super::standby::*;
+use crate::utilities::*;
+use std::io::{self, Read, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::Path;
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use typing::constraint::*;
+use typing::rw::*;
+use typing::tx::*;
+
+/// a standby behind a unix socket, served by serve
+///     primary to standby: one frame per record
+///     standby to primary: the last applied sequence number (u64, little endian) per record
+pub struct SocketLink {
+    stream: UnixStream,
+}
+
+impl SocketLink {
+    pub fn connect(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(SocketLink { stream: UnixStream::connect(path)? })
+    }
+}
+
+impl Link for SocketLink {
+    fn ship(&mut self, recs: &[Arc<Vec<u8>>]) -> io::Result<u64> {
+        // records are pipelined, then acknowledgements are collected
+        let mut buf = vec![];
+        for rec in recs { put_frame(rec, &mut buf) }
+        self.stream.write_all(&buf)?;
+        let mut ack = [0u8; 8];
+        for _ in recs { self.stream.read_exact(&mut ack)? }
+        Ok(u64::from_le_bytes(ack))
+    }
+}
+
+/// listen on a unix socket and serve one primary on a thread until it disconnects
+/// the socket is bound before returning, so a primary can connect right away
+/// an error closes the connection, so the primary sees the link broken
+pub fn serve<V, T, D>(standby: Arc<Standby<V, T, D>>, path: impl AsRef<Path>)
+-> io::Result<JoinHandle<Result<(), ReplErr<D::Err>>>>
+where
+    V: Codec,
+    T: Tx<V> + TxCmd,
+    T::Map: Mapper<V::I, V>,
+    D: RWDurable<V, T>,
+    D::Err: Send + 'static,
+    Standby<V, T, D>: Send + Sync + 'static,
+{
+    let listener = UnixListener::bind(path)?;
+    Ok(std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept()?;
+        let mut frame = vec![0u8; FRAME_HEAD];
+        loop {
+            frame.resize(FRAME_HEAD, 0);
+            match stream.read_exact(&mut frame) {
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(()),
+                res => res?,
+            }
+            let len = u32::from_le_bytes(frame[..4].try_into().unwrap()) as usize;
+            frame.resize(FRAME_HEAD + len, 0);
+            stream.read_exact(&mut frame[FRAME_HEAD..])?;
+            let payload = match get_frame(&mut &frame[..]) {
+                Ok(Some(payload)) => payload,
+                Ok(None) | Err(_) => return Err(ReplErr::Corrupt),
+            };
+            let seq = standby.apply(payload)?;
+            stream.write_all(&seq.to_le_bytes())?;
+        }
+    }))
+}
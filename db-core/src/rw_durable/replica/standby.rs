@@ -0,0 +1,132 @@
+use super::error::*;
+use super::record::*;
+use crate::rw_durable::snapshot::Dump;
+use parking_lot::Mutex;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicBool, Ordering::*};
+use typing::constraint::*;
+use typing::rw::*;
+use typing::tx::*;
+
+pub struct Standby<V, T, D>
+where
+    V: Codec,
+    T: Tx<V> + TxCmd,
+    T::Map: Mapper<V::I, V>,
+    D: RWDurable<V, T>,
+{
+    inner: D,
+    // the sequence number of the last applied record, locked while a record is applied
+    applied: Mutex<u64>,
+    promoted: AtomicBool,
+    phant: PhantomData<(V, T)>,
+}
+
+impl<V, T, D> Standby<V, T, D>
+where
+    V: Codec,
+    T: Tx<V> + TxCmd,
+    T::Map: Mapper<V::I, V>,
+    D: RWDurable<V, T>,
+{
+    /// a standby over an engine in the same state as the primary before its first shipped record
+    pub fn new(inner: D) -> Self {
+        Standby { inner, applied: Mutex::new(0), promoted: AtomicBool::new(false), phant: PhantomData }
+    }
+    /// the inner durable engine
+    pub fn inner(&self) -> &D {
+        &self.inner
+    }
+    /// the sequence number of the last applied record
+    pub fn applied(&self) -> u64 {
+        *self.applied.lock()
+    }
+    /// whether the standby is promoted
+    pub fn is_promoted(&self) -> bool {
+        self.promoted.load(Acquire)
+    }
+    /// stop applying shipped records and accept local transactions, return the last applied sequence number
+    /// a record being applied finishes first
+    pub fn promote(&self) -> u64 {
+        let applied = self.applied.lock();
+        self.promoted.store(true, Release);
+        *applied
+    }
+    /// apply a shipped record payload, return the last applied sequence number afterwards
+    /// a record shipped again (e.g. after a reconnect) is skipped
+    pub fn apply(&self, payload: &[u8]) -> Result<u64, ReplErr<D::Err>> {
+        let (seq, txn, map) = get_ship::<V, T>(payload).ok_or(ReplErr::Corrupt)?;
+        let mut applied = self.applied.lock();
+        if self.is_promoted() { return Err(ReplErr::Promoted) }
+        if seq <= *applied { return Ok(*applied) }
+        if seq != *applied + 1 { return Err(ReplErr::Gap(*applied + 1)) }
+        // a mapping may carry a single entry, so each key is a map
+        let maps = map.into_iter().map(|kv| Mapper::from_mapping(std::iter::once(kv))).collect();
+        self.inner.open(&txn).map_err(ReplErr::Inner)?;
+        self.inner.wr_batch(&txn, maps).map_err(ReplErr::Inner)?;
+        self.inner.done(&txn, End::Ready).map_err(ReplErr::Inner)?;
+        *applied = seq;
+        Ok(seq)
+    }
+    // refuse local transactions until promoted
+    fn check(&self) -> Result<(), ReplErr<D::Err>> {
+        if self.is_promoted() { Ok(()) } else { Err(ReplErr::Standby) }
+    }
+}
+
+impl<V, T, D> RWDurable<V, T> for Standby<V, T, D>
+where
+    V: Codec,
+    T: Tx<V> + TxCmd,
+    T::Map: Mapper<V::I, V>,
+    D: RWDurable<V, T>,
+{
+    type Err = ReplErr<D::Err>;
+    fn open(&self, txn: &T) -> Result<(), Self::Err> {
+        self.check()?;
+        self.inner.open(txn).map_err(ReplErr::Inner)
+    }
+    fn done(&self, txn: &T, end: End) -> Result<(), Self::Err> {
+        self.check()?;
+        self.inner.done(txn, end).map_err(ReplErr::Inner)
+    }
+    fn rd(&self, prp: T::Prp) -> Result<T::Map, Self::Err> {
+        self.inner.rd(prp).map_err(ReplErr::Inner)
+    }
+    fn wr(&self, txn: &T, map: T::Map) -> Result<(), Self::Err> {
+        self.check()?;
+        self.inner.wr(txn, map).map_err(ReplErr::Inner)
+    }
+    fn rd_batch(&self, prps: Vec<T::Prp>) -> Result<Vec<T::Map>, Self::Err> {
+        self.inner.rd_batch(prps).map_err(ReplErr::Inner)
+    }
+    fn wr_batch(&self, txn: &T, maps: Vec<T::Map>) -> Result<(), Self::Err> {
+        self.check()?;
+        self.inner.wr_batch(txn, maps).map_err(ReplErr::Inner)
+    }
+    fn epoch(&self) -> u64 {
+        self.inner.epoch()
+    }
+    fn durable_epoch(&self) -> u64 {
+        self.inner.durable_epoch()
+    }
+    fn submit(&self, prp: &T::Prp) -> Option<u64> {
+        self.inner.submit(prp)
+    }
+    fn arrived(&self, ticket: u64) -> bool {
+        self.inner.arrived(ticket)
+    }
+}
+
+impl<V, T, D> Dump<V> for Standby<V, T, D>
+where
+    V: Codec,
+    T: Tx<V> + TxCmd,
+    T::Map: Mapper<V::I, V>,
+    D: RWDurable<V, T> + Dump<V>,
+{
+    type Err = <D as Dump<V>>::Err;
+    fn dump(&self, f: &mut dyn FnMut(&V::I, &V)) -> Result<(), Self::Err> {
+        self.inner.dump(f)
+    }
+}
@@ -0,0 +1,68 @@
+use super::*;
+use crate::rw_durable::check_util::*;
+use crate::rw_durable::cow_btree::CowBTree;
+use crate::rw_durable::null::Null;
+use crate::rw_durable::wal::Wal;
+use db_test::core_workload::int::unif::*;
+use typing::rw::*;
+use typing::tx::*;
+
+#[test]
+fn is_exported_and_imported() {
+    let (wal_path, cow_path) = (tmp_path("snapshot"), tmp_path("snapshot"));
+    let (snap_a, snap_b) = (tmp_path("snapshot"), tmp_path("snapshot"));
+    let txn = txns().get();
+    let wal = Wal::<U64Tup, U64Txn>::new(&wal_path).unwrap();
+    for k in 0..3000 {
+        wal.wr(&txn, U64Map(Some((k, Some(U64Tup(k, k * 3)))))).unwrap();
+    }
+    for k in (0..3000).step_by(7) {
+        wal.wr(&txn, U64Map(Some((k, None)))).unwrap();
+    }
+    wal.done(&txn, End::Ready).unwrap();
+    let count = export(&wal, &snap_a).unwrap();
+    assert!(count == 3000 - 429);
+    // another backend holding the same state exports the same bytes
+    let cow = CowBTree::<U64Tup, U64Txn>::new(&cow_path).unwrap();
+    assert!(import(&snap_a, &cow, &txn, 500).unwrap() == count);
+    assert!(export(&cow, &snap_b).unwrap() == count);
+    assert!(std::fs::read(&snap_a).unwrap() == std::fs::read(&snap_b).unwrap());
+    // e.g. a benchmark starts from a pre-populated in-memory state
+    let null = Null::<U64Tup, U64Txn>::new(0, 0, false);
+    assert!(import(&snap_b, &null, &txn, 500).unwrap() == count);
+    for k in [0, 1, 7, 2999] {
+        assert!(get(&null, k) == get(&wal, k) && get(&cow, k) == get(&wal, k));
+    }
+    for path in [wal_path, cow_path, snap_a, snap_b] {
+        std::fs::remove_file(path).unwrap();
+    }
+}
+
+#[test]
+fn is_corruption_detected() {
+    let (path, snap) = (tmp_path("snapshot"), tmp_path("snapshot"));
+    let txn = txns().get();
+    let wal = Wal::<U64Tup, U64Txn>::new(&path).unwrap();
+    for k in 0..3000 {
+        wal.wr(&txn, U64Map(Some((k, Some(U64Tup(k, k)))))).unwrap();
+    }
+    wal.done(&txn, End::Ready).unwrap();
+    export(&wal, &snap).unwrap();
+    let bytes = std::fs::read(&snap).unwrap();
+    let load = |bytes: &[u8]| {
+        std::fs::write(&snap, bytes).unwrap();
+        load::<U64Tup, ()>(&snap, |_, _| Ok(()))
+    };
+    assert!(matches!(load(&bytes), Ok(3000)));
+    // a flipped bit in a frame
+    let mut flip = bytes.clone();
+    flip[100] ^= 1;
+    assert!(matches!(load(&flip), Err(SnapErr::Corrupt(12))));
+    // a truncated file misses its end frame
+    assert!(matches!(load(&bytes[..bytes.len() - 20]), Err(SnapErr::Corrupt(_))));
+    let mut version = bytes.clone();
+    version[8] = 9;
+    assert!(matches!(load(&version), Err(SnapErr::Version(9))));
+    std::fs::remove_file(&path).unwrap();
+    std::fs::remove_file(&snap).unwrap();
+}
@@ -0,0 +1,8 @@
+use typing::constraint::*;
+
+/// a durable engine that can visit all data items of a state it holds
+pub trait Dump<V: Id> {
+    type Err;
+    /// visit every data item once, in any order
+    fn dump(&self, f: &mut dyn FnMut(&V::I, &V)) -> Result<(), Self::Err>;
+}
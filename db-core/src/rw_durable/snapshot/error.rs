@@ -0,0 +1,17 @@
+#[derive(Debug)]
+pub enum SnapErr<E> {
+    // file system error
+    Io(std::io::Error),
+    // durable engine error
+    External(E),
+    // a frame at a given offset is torn or cannot be decoded, or the end frame is missing
+    Corrupt(u64),
+    // the file is written by an unknown version of the format
+    Version(u32),
+}
+
+impl<E> From<std::io::Error> for SnapErr<E> {
+    fn from(e: std::io::Error) -> Self {
+        SnapErr::Io(e)
+    }
+}
@@ -0,0 +1,115 @@
+use super::dump::*;
+use super::error::*;
+use crate::rw_durable::wal::{get_record, put_record};
+use crate::utilities::*;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use typing::constraint::*;
+use typing::rw::*;
+use typing::tx::*;
+
+// header: [magic] [version: u32]
+const MAGIC: &[u8; 8] = b"DBSNAPSH";
+const VERSION: u32 = 1;
+const HEAD: usize = 8 + 4;
+
+// tags of a frame payload: rows as a log record, or the end with [count: u64]
+const TAG_ROWS: u8 = 0;
+const TAG_END: u8 = 1;
+
+// rows per frame
+const CHUNK: usize = 1024;
+
+/// export all data items of an engine into a snapshot file, return the number of rows
+/// the file is written aside and renamed into place, so a crash never leaves a partial snapshot
+pub fn export<V: Codec + Clone, D: Dump<V>>(dur: &D, path: impl AsRef<Path>) -> Result<u64, SnapErr<D::Err>>
+where
+    V::I: Clone,
+{
+    let mut rows = vec![];
+    dur.dump(&mut |i, v| {
+        let mut key = vec![];
+        V::put_id(i, &mut key);
+        rows.push((key, i.clone(), Some(v.clone())));
+    }).map_err(SnapErr::External)?;
+    rows.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+    let rows = rows.into_iter().map(|(_, i, v)| (i, v)).collect::<Vec<_>>();
+    let mut data = vec![];
+    data.extend(MAGIC);
+    data.extend(VERSION.to_le_bytes());
+    for chunk in rows.chunks(CHUNK) {
+        let mut payload = vec![TAG_ROWS];
+        put_record(chunk, &mut payload);
+        put_frame(&payload, &mut data);
+    }
+    let mut payload = vec![TAG_END];
+    payload.extend((rows.len() as u64).to_le_bytes());
+    put_frame(&payload, &mut data);
+    let path = path.as_ref();
+    let tmp = path.with_extension("tmp");
+    {
+        let mut file = File::create(&tmp)?;
+        file.write_all(&data)?;
+        file.sync_all()?;
+    }
+    std::fs::rename(tmp, path)?;
+    Ok(rows.len() as u64)
+}
+
+/// read rows of a snapshot file in key order, return the number of rows
+/// the whole file is checked before any row is handed out, so a corrupt file has no effect
+pub fn load<V: Codec, E>(path: impl AsRef<Path>, mut f: impl FnMut(V::I, V) -> Result<(), E>) -> Result<u64, SnapErr<E>> {
+    let bytes = std::fs::read(path)?;
+    if bytes.len() < HEAD || &bytes[..8] != MAGIC { return Err(SnapErr::Corrupt(0)) }
+    let version = u32::from_le_bytes(bytes[8..12].try_into().unwrap());
+    if version != VERSION { return Err(SnapErr::Version(version)) }
+    let mut cur = &bytes[HEAD..];
+    let mut rows = vec![];
+    loop {
+        let at = (bytes.len() - cur.len()) as u64;
+        let corrupt = || SnapErr::Corrupt(at);
+        let payload = get_frame(&mut cur).map_err(|_| corrupt())?.ok_or_else(corrupt)?;
+        let (tag, body) = payload.split_first().ok_or_else(corrupt)?;
+        match *tag {
+            TAG_ROWS => {
+                let record = get_record::<V>(body).ok_or_else(corrupt)?;
+                // a snapshot never holds deletions
+                if record.iter().any(|(_, v)| v.is_none()) { return Err(corrupt()) }
+                rows.extend(record);
+            }
+            TAG_END => {
+                let count = get_u64(&mut &body[..]).ok_or_else(corrupt)?;
+                if count != rows.len() as u64 || !cur.is_empty() { return Err(corrupt()) }
+                break;
+            }
+            _ => return Err(corrupt()),
+        }
+    }
+    let count = rows.len() as u64;
+    for (i, v) in rows {
+        f(i, v.expect("deletions are rejected above")).map_err(SnapErr::External)?;
+    }
+    Ok(count)
+}
+
+/// import a snapshot file into an engine through a transaction, committed every batch rows
+pub fn import<V: Codec, T: Tx<V>, D: RWDurable<V, T>>(path: impl AsRef<Path>, dur: &D, txn: &T, batch: usize) -> Result<u64, SnapErr<D::Err>>
+where
+    T::Map: Mapper<V::I, V>,
+{
+    let mut n = 0;
+    dur.open(txn).map_err(SnapErr::External)?;
+    let count = load::<V, D::Err>(path, |i, v| {
+        // a mapping may hold only one entry, so rows are written one by one
+        dur.wr(txn, Mapper::from_mapping(std::iter::once((i, Some(v)))))?;
+        n += 1;
+        if n % batch.max(1) == 0 {
+            dur.done(txn, End::Ready)?;
+            dur.open(txn)?;
+        }
+        Ok(())
+    })?;
+    dur.done(txn, End::Ready).map_err(SnapErr::External)?;
+    Ok(count)
+}
@@ -0,0 +1,21 @@
+//! ## Snapshot Export and Import
+//!
+//! A portable file format for the state of a durable engine, to start benchmarks from a pre-populated state and to cross-check engines.
+//! Engines that can visit all their data items implement Dump; copy-on-write b-tree and mpt dump a committed state while transactions run, others only when no transaction is running.
+//! A snapshot file is a versioned header followed by checksummed frames of sorted rows and an end frame with the row count.
+//! Rows are sorted by encoded keys, so engines holding the same state export byte-identical files.
+//! Import writes rows into any durable engine through a transaction, committing in batches.
+
+// snapshot error
+mod error;
+// visiting all data items of an engine
+mod dump;
+// snapshot file format, export and import
+mod file;
+
+pub use error::*;
+pub use dump::*;
+pub use file::*;
+
+#[cfg(test)]
+mod check; // round trip and corruption checks
@@ -0,0 +1,236 @@
+use super::*;
+use crate::rw_durable::check_util::*;
+use db_test::core_workload::int::unif::*;
+use std::io::Write;
+use typing::rw::*;
+use typing::tx::*;
+
+#[test]
+fn is_recoverable() {
+    let path = tmp_path("wal");
+    let txn = txns().get();
+    {
+        let wal = Wal::<U64Tup, U64Txn>::new(&path).unwrap();
+        for k in 0..100u64 {
+            wal.wr(&txn, U64Map(Some((k, Some(U64Tup(k, k * 2)))))).unwrap();
+        }
+        wal.wr(&txn, U64Map(Some((7, None)))).unwrap();
+        wal.done(&txn, End::Ready).unwrap();
+    }
+    let wal = Wal::<U64Tup, U64Txn>::new(&path).unwrap();
+    for k in 0..100u64 {
+        assert!(get(&wal, k) == if k == 7 { None } else { Some(k * 2) });
+    }
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn is_batch_one_record() {
+    let path = tmp_path("wal");
+    let txn = txns().get();
+    {
+        let wal = Wal::<U64Tup, U64Txn>::new(&path).unwrap();
+        let maps = (0..10u64).map(|k| U64Map(Some((k, Some(U64Tup(k, k + 1)))))).chain([U64Map(Some((3, None)))]).collect();
+        wal.wr_batch(&txn, maps).unwrap();
+        wal.done(&txn, End::Ready).unwrap();
+        let maps = wal.rd_batch(vec![U64Prp(2), U64Prp(3), U64Prp(4)]).unwrap();
+        assert!(maps.iter().map(|U64Map(map)| map.is_some()).collect::<Vec<_>>() == vec![true, false, true]);
+    }
+    let mut records = 0;
+    replay::<U64Tup>(&std::fs::read(&path).unwrap(), |_| records += 1).unwrap();
+    assert!(records == 1);
+    let wal = Wal::<U64Tup, U64Txn>::new(&path).unwrap();
+    assert!((0..10u64).all(|k| get(&wal, k) == if k == 3 { None } else { Some(k + 1) }));
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn is_unfinished_txn_lost() {
+    let path = tmp_path("wal");
+    let mut workload = txns();
+    let (txn1, txn2) = (workload.get(), workload.get());
+    {
+        let wal = Wal::<U64Tup, U64Txn>::new(&path).unwrap();
+        wal.wr(&txn1, U64Map(Some((1, Some(U64Tup(1, 10)))))).unwrap();
+        wal.done(&txn1, End::Ready).unwrap();
+        // crash between two writes of a transaction, the log is still flushed when dropped
+        wal.wr(&txn2, U64Map(Some((1, Some(U64Tup(1, 11)))))).unwrap();
+        wal.wr(&txn2, U64Map(Some((2, Some(U64Tup(2, 20)))))).unwrap();
+    }
+    let wal = Wal::<U64Tup, U64Txn>::new(&path).unwrap();
+    assert!(get(&wal, 1) == Some(10));
+    assert!(get(&wal, 2).is_none());
+    // an aborted transaction leaves nothing either
+    wal.wr(&txn2, U64Map(Some((2, Some(U64Tup(2, 20)))))).unwrap();
+    wal.done(&txn2, End::Abort).unwrap();
+    assert!(get(&wal, 2).is_none());
+    drop(wal);
+    let wal = Wal::<U64Tup, U64Txn>::new(&path).unwrap();
+    assert!(get(&wal, 2).is_none());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn is_torn_tail_dropped() {
+    let path = tmp_path("wal");
+    let txn = txns().get();
+    {
+        let wal = Wal::<U64Tup, U64Txn>::new(&path).unwrap();
+        wal.wr(&txn, U64Map(Some((1, Some(U64Tup(1, 10)))))).unwrap();
+        wal.done(&txn, End::Ready).unwrap();
+    }
+    let len = std::fs::metadata(&path).unwrap().len();
+    // a crash in the middle of appending a record
+    std::fs::OpenOptions::new().append(true).open(&path).unwrap()
+        .write_all(&[0xff, 0x00, 0x00, 0x00, 0x12, 0x34]).unwrap();
+    {
+        let wal = Wal::<U64Tup, U64Txn>::new(&path).unwrap();
+        assert!(get(&wal, 1) == Some(10));
+        assert!(std::fs::metadata(&path).unwrap().len() == len);
+        wal.wr(&txn, U64Map(Some((2, Some(U64Tup(2, 20)))))).unwrap();
+        wal.done(&txn, End::Ready).unwrap();
+    }
+    let wal = Wal::<U64Tup, U64Txn>::new(&path).unwrap();
+    assert!(get(&wal, 1) == Some(10));
+    assert!(get(&wal, 2) == Some(20));
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn is_group_durable() {
+    let path = tmp_path("wal");
+    let txn = txns().get();
+    let group = GroupCommit { size: 4, wait: std::time::Duration::from_millis(1) };
+    {
+        let wal = Wal::<U64Tup, U64Txn>::with_group(&path, group).unwrap();
+        wal.wr(&txn, U64Map(Some((3, Some(U64Tup(3, 30)))))).unwrap();
+        wal.done(&txn, End::Ready).unwrap();
+        // a single transaction doesn't fill a group, the flusher closes the epoch
+        let epoch = wal.epoch();
+        while wal.durable_epoch() < epoch { std::thread::yield_now() }
+    }
+    let wal = Wal::<U64Tup, U64Txn>::new(&path).unwrap();
+    assert!(get(&wal, 3) == Some(30));
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn is_failure_sticky() {
+    let path = tmp_path("wal");
+    let mut workload = txns();
+    let group = GroupCommit { size: 1 << 20, wait: std::time::Duration::from_millis(1) };
+    let wal = Wal::<U64Tup, U64Txn>::with_group(&path, group).unwrap();
+    let txn = workload.get();
+    wal.wr(&txn, U64Map(Some((1, Some(U64Tup(1, 10)))))).unwrap();
+    wal.done(&txn, End::Ready).unwrap();
+    // sealing the active segment fails, since a non-empty directory takes its name
+    let seg = std::path::PathBuf::from(format!("{}.seg.0", path.display()));
+    std::fs::create_dir(&seg).unwrap();
+    std::fs::write(seg.join("block"), b"").unwrap();
+    assert!(wal.checkpoint().is_err());
+    let durable = wal.durable_epoch();
+    // every later transaction sees the failure, and no epoch becomes durable anymore
+    for _ in 0..3 {
+        let txn = workload.get();
+        wal.wr(&txn, U64Map(Some((2, Some(U64Tup(2, 20)))))).unwrap();
+        assert!(matches!(wal.done(&txn, End::Ready), Err(WalErr::Io(_))));
+    }
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    assert!(wal.durable_epoch() == durable && durable < wal.epoch());
+    drop(wal);
+    std::fs::remove_dir_all(&seg).unwrap();
+    remove_all(&path);
+}
+
+fn remove_all(path: &std::path::Path) {
+    let name = path.file_name().unwrap().to_string_lossy().to_string();
+    for entry in std::fs::read_dir(path.parent().unwrap()).unwrap() {
+        let entry = entry.unwrap();
+        let file = entry.file_name().to_string_lossy().to_string();
+        if file == name || file.starts_with(&format!("{name}.")) {
+            std::fs::remove_file(entry.path()).unwrap();
+        }
+    }
+}
+
+fn count_files(path: &std::path::Path, tag: &str) -> usize {
+    let prefix = format!("{}.{tag}.", path.file_name().unwrap().to_string_lossy());
+    std::fs::read_dir(path.parent().unwrap()).unwrap()
+        .filter(|entry| entry.as_ref().unwrap().file_name().to_string_lossy().starts_with(&prefix))
+        .count()
+}
+
+#[test]
+fn is_checkpoint_truncating() {
+    let path = tmp_path("wal");
+    let txn = txns().get();
+    {
+        let wal = Wal::<U64Tup, U64Txn>::new(&path).unwrap();
+        for round in 0..4u64 {
+            for k in 0..50u64 {
+                wal.wr(&txn, U64Map(Some((k, Some(U64Tup(k, k + round)))))).unwrap();
+            }
+            wal.done(&txn, End::Ready).unwrap();
+            wal.checkpoint().unwrap();
+        }
+        wal.wr(&txn, U64Map(Some((7, None)))).unwrap();
+        wal.done(&txn, End::Ready).unwrap();
+        // only the latest checkpoint is kept, and segments before it are removed
+        assert!(count_files(&path, "ckpt") == 1);
+        assert!(count_files(&path, "seg") == 0);
+        assert!(std::fs::metadata(&path).unwrap().len() < 100);
+    }
+    let wal = Wal::<U64Tup, U64Txn>::new(&path).unwrap();
+    for k in 0..50u64 {
+        assert!(get(&wal, k) == if k == 7 { None } else { Some(k + 3) });
+    }
+    drop(wal);
+    remove_all(&path);
+}
+
+#[test]
+fn is_torn_checkpoint_skipped() {
+    let path = tmp_path("wal");
+    let txn = txns().get();
+    {
+        let wal = Wal::<U64Tup, U64Txn>::new(&path).unwrap();
+        wal.wr(&txn, U64Map(Some((1, Some(U64Tup(1, 10)))))).unwrap();
+        wal.done(&txn, End::Ready).unwrap();
+        wal.checkpoint().unwrap();
+        wal.wr(&txn, U64Map(Some((2, Some(U64Tup(2, 20)))))).unwrap();
+        wal.done(&txn, End::Ready).unwrap();
+    }
+    // a crash in the middle of writing a later checkpoint
+    let mut torn = path.as_os_str().to_owned();
+    torn.push(".ckpt.9");
+    std::fs::write(&torn, [0x40, 0x00, 0x00, 0x00, 0x12, 0x34]).unwrap();
+    let wal = Wal::<U64Tup, U64Txn>::new(&path).unwrap();
+    assert!(get(&wal, 1) == Some(10));
+    assert!(get(&wal, 2) == Some(20));
+    // the torn checkpoint is removed, pruning only removes checkpoints before the valid one
+    assert!(!std::path::Path::new(&torn).exists());
+    assert!(count_files(&path, "ckpt") == 1);
+    drop(wal);
+    remove_all(&path);
+}
+
+#[test]
+fn is_background_checkpoint_recoverable() {
+    let path = tmp_path("wal");
+    let txn = txns().get();
+    let ckpt = Checkpoint { size: 256, wait: std::time::Duration::from_millis(1) };
+    {
+        let wal = Wal::<U64Tup, U64Txn>::new(&path).unwrap().with_checkpoint(ckpt);
+        for k in 0..1000u64 {
+            wal.wr(&txn, U64Map(Some((k % 100, Some(U64Tup(k % 100, k)))))).unwrap();
+            wal.done(&txn, End::Ready).unwrap();
+        }
+        while count_files(&path, "ckpt") == 0 { std::thread::yield_now() }
+    }
+    let wal = Wal::<U64Tup, U64Txn>::new(&path).unwrap();
+    for k in 0..100u64 {
+        assert!(get(&wal, k) == Some(k + 900));
+    }
+    drop(wal);
+    remove_all(&path);
+}
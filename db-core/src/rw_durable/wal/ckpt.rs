@@ -0,0 +1,97 @@
+use super::error::*;
+use super::record::*;
+use crate::utilities::*;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use typing::constraint::*;
+
+/// fuzzy checkpoint configuration, a checkpoint is taken once the active log segment grows past a size
+#[derive(Debug, Clone, Copy)]
+pub struct Checkpoint {
+    // the size of the active log segment in bytes that triggers a checkpoint
+    pub size: u64,
+    // the interval of checking the size
+    pub wait: Duration,
+}
+
+// files next to a log file are named by a tag and a generation, e.g. {log}.seg.3 or {log}.ckpt.3
+//     seg.g:  a sealed log segment, it was the active log file in generation g
+//     ckpt.g: a table snapshot, replaying segments from generation g on top of it rebuilds the table
+pub(super) fn tagged(base: &Path, tag: &str, gen: u64) -> PathBuf {
+    let mut path = base.as_os_str().to_owned();
+    path.push(format!(".{tag}.{gen}"));
+    path.into()
+}
+
+fn parent(base: &Path) -> &Path {
+    match base.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    }
+}
+
+/// generations of files with a tag next to a log file, in ascending order
+pub(super) fn listed(base: &Path, tag: &str) -> std::io::Result<Vec<u64>> {
+    let prefix = format!("{}.{tag}.", base.file_name().unwrap_or_default().to_string_lossy());
+    let mut gens = vec![];
+    for entry in std::fs::read_dir(parent(base))? {
+        let name = entry?.file_name();
+        if let Some(gen) = name.to_string_lossy().strip_prefix(&prefix).and_then(|g| g.parse().ok()) {
+            gens.push(gen);
+        }
+    }
+    gens.sort();
+    Ok(gens)
+}
+
+/// make renaming and creating files next to a log file durable
+pub(super) fn sync_dir(base: &Path) -> std::io::Result<()> {
+    File::open(parent(base))?.sync_all()
+}
+
+// checkpoint file: a checksummed frame of [generation: u64] [snapshot as a log record]
+pub(super) fn write_ckpt<V: Codec>(base: &Path, gen: u64, snap: &[(V::I, Option<V>)]) -> Result<(), WalErr> {
+    let mut payload = vec![];
+    payload.extend(gen.to_le_bytes());
+    put_record(snap, &mut payload);
+    let mut frame = vec![];
+    put_frame(&payload, &mut frame);
+    let mut file = File::create(tagged(base, "ckpt", gen))?;
+    file.write_all(&frame)?;
+    file.sync_data()?;
+    sync_dir(base)?;
+    Ok(())
+}
+
+// decode a checkpoint file of a generation, none if it is torn
+fn get_ckpt<V: Codec>(bytes: &[u8], gen: u64) -> Option<Mapping<V>> {
+    let Ok(Some(mut payload)) = get_frame(&mut &bytes[..]) else { return None };
+    if get_u64(&mut payload) != Some(gen) { return None }
+    get_record::<V>(payload)
+}
+
+/// read the latest valid checkpoint, return its generation and snapshot
+/// a checkpoint torn by a crash is removed, and an earlier one is used, whose segments are still kept
+pub(super) fn read_ckpt<V: Codec>(base: &Path) -> Result<(u64, Mapping<V>), WalErr> {
+    for gen in listed(base, "ckpt")?.into_iter().rev() {
+        let path = tagged(base, "ckpt", gen);
+        if let Some(snap) = get_ckpt::<V>(&std::fs::read(&path)?, gen) {
+            return Ok((gen, snap));
+        }
+        std::fs::remove_file(path)?;
+        sync_dir(base)?;
+    }
+    Ok((0, vec![]))
+}
+
+/// remove segments and checkpoints before a generation, they are covered by the checkpoint of this generation
+pub(super) fn prune(base: &Path, gen: u64) -> std::io::Result<()> {
+    for tag in ["seg", "ckpt"] {
+        for old in listed(base, tag)?.into_iter().filter(|g| *g < gen) {
+            std::fs::remove_file(tagged(base, tag, old))?;
+        }
+    }
+    Ok(())
+}
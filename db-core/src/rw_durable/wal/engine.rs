@@ -0,0 +1,404 @@
+use super::ckpt::*;
+use super::error::*;
+use super::record::*;
+use crate::rw_durable::snapshot::Dump;
+use crate::utilities::*;
+use parking_lot::{Mutex, RwLock};
+use std::fs::{File, OpenOptions};
+use std::hash::Hash;
+use std::io::{BufWriter, Read, Write};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering::*};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use typing::constraint::*;
+use typing::rw::*;
+use typing::tx::*;
+
+/// group commit configuration, the log is synced once a group is full or an epoch elapses
+#[derive(Debug, Clone, Copy)]
+pub struct GroupCommit {
+    // the number of ready transactions in a group
+    pub size: usize,
+    // the length of an epoch
+    pub wait: Duration,
+}
+
+// a log file, shared with the group commit flusher and the checkpointer
+struct LogFile {
+    // the path of the active segment
+    base: PathBuf,
+    // buffered appending handle
+    buf: Mutex<BufWriter<File>>,
+    // a cloned handle, so syncing doesn't block appending, replaced when the segment is sealed
+    file: RwLock<File>,
+    // the generation of the active segment
+    gen: AtomicU64,
+    // the size of the active segment
+    size: AtomicU64,
+    // only one checkpoint runs at a time
+    ckpt: Mutex<()>,
+    // the epoch that new records belong to
+    epoch: AtomicU64,
+    // records in epochs up to this one are durable
+    synced: AtomicU64,
+    // (generation, length) of the active segment that is durable
+    durable: Mutex<(u64, u64)>,
+    // ready transactions in current epoch
+    count: AtomicUsize,
+    // the first error that syncing or checkpointing encounters, it sticks until the log is reopened
+    error: Mutex<Option<WalErr>>,
+}
+
+impl LogFile {
+    /// push buffered records to disk, close current epoch
+    /// a failed sync is sticky, no later epoch becomes durable, because records in the failed one may be lost
+    fn commit(&self) -> Result<(), WalErr> {
+        self.failed()?;
+        self.sync().map_err(|e| self.fail(e.into()))
+    }
+    fn sync(&self) -> std::io::Result<()> {
+        let (epoch, gen, len) = {
+            let mut buf = self.buf.lock();
+            buf.flush()?;
+            self.count.store(0, Relaxed);
+            (self.epoch.fetch_add(1, AcqRel), self.gen.load(Relaxed), self.size.load(Relaxed))
+        };
+        self.file.read().sync_data()?;
+        self.synced.fetch_max(epoch, AcqRel);
+        // the segment may be sealed meanwhile, then its length no longer matters
+        let mut durable = self.durable.lock();
+        if durable.0 == gen { durable.1 = durable.1.max(len) }
+        Ok(())
+    }
+    /// the error that the log has run into, every caller gets a copy of it
+    fn failed(&self) -> Result<(), WalErr> {
+        match &*self.error.lock() {
+            Some(e) => Err(e.copied()),
+            None => Ok(()),
+        }
+    }
+    /// keep the first error, return a copy of the given one
+    fn fail(&self, e: WalErr) -> WalErr {
+        let copy = e.copied();
+        self.error.lock().get_or_insert(e);
+        copy
+    }
+    /// close current epoch like commit, seal the active segment and start a new one
+    /// return the generation of the new segment, i.e. the checkpoint lsn
+    fn rotate(&self) -> std::io::Result<u64> {
+        // lock order: buf, file
+        let mut buf = self.buf.lock();
+        buf.flush()?;
+        self.count.store(0, Relaxed);
+        let epoch = self.epoch.fetch_add(1, AcqRel);
+        let mut file = self.file.write();
+        file.sync_data()?;
+        self.synced.fetch_max(epoch, AcqRel);
+        let gen = self.gen.load(Relaxed);
+        std::fs::rename(&self.base, tagged(&self.base, "seg", gen))?;
+        let new = OpenOptions::new()
+            .read(true).append(true).create(true)
+            .open(&self.base)?;
+        sync_dir(&self.base)?;
+        *file = new.try_clone()?;
+        *buf = BufWriter::new(new);
+        self.size.store(0, Relaxed);
+        self.gen.store(gen + 1, Relaxed);
+        *self.durable.lock() = (gen + 1, 0);
+        Ok(gen + 1)
+    }
+}
+
+// take a fuzzy checkpoint, transactions keep writing the table and the log meanwhile
+fn checkpoint<V: Codec + Clone>(log: &LogFile, table: &dashmap::DashMap<V::I, V>) -> Result<(), WalErr>
+where
+    V::I: Eq + Hash + Clone,
+{
+    let _ckpt = log.ckpt.lock();
+    log.failed()?;
+    let gen = log.rotate().map_err(|e| log.fail(e.into()))?;
+    let snap = table.iter()
+        .map(|e| (e.key().clone(), Some(e.value().clone())))
+        .collect::<Vec<_>>();
+    // the snapshot may contain values written after rotation, make their records durable first
+    log.commit()?;
+    write_ckpt::<V>(&log.base, gen, &snap)?;
+    prune(&log.base, gen)?;
+    Ok(())
+}
+
+pub struct Wal<V: Id, T: Tx<V>>
+where
+    V: Sync + Clone + Codec,
+    V::I: Eq + Hash + Sync + Clone,
+    T::I: Eq + Hash,
+    T::Prp: Filter<V>,
+    T::Map: Mapper<V::I, V>,
+{
+    table: Arc<dashmap::DashMap<V::I, V>>,
+    log: Arc<LogFile>,
+    // written mappings of each open transaction, logged as one record when it is ready
+    pending: dashmap::DashMap<T::I, Mapping<V>>,
+    // group commit configuration, flusher kill signal and join handle
+    group: Option<(GroupCommit, Arc<AtomicBool>, JoinHandle<()>)>,
+    // checkpointer kill signal and join handle
+    ckpt: Option<(Arc<AtomicBool>, JoinHandle<()>)>,
+    phant: PhantomData<T>,
+}
+
+impl<V: Id, T: Tx<V>> Wal<V, T>
+where
+    V: Sync + Clone + Codec,
+    V::I: Eq + Hash + Sync + Clone,
+    T::I: Eq + Hash,
+    T::Prp: Filter<V>,
+    T::Map: Mapper<V::I, V>,
+{
+    /// open a log file (create it if absent) and rebuild the table by replaying it
+    /// replay starts from the latest valid checkpoint, then goes through sealed segments and the log file
+    /// the log is synced on every ready transaction
+    pub fn new(path: impl AsRef<Path>) -> Result<Self, WalErr> {
+        let base = path.as_ref().to_path_buf();
+        let table = dashmap::DashMap::new();
+        let apply = |map: Vec<(V::I, Option<V>)>| for (i, v) in map {
+            match v {
+                Some(v) => {table.insert(i, v);},
+                None => {table.remove(&i);},
+            }
+        };
+        let (ckpt, snap) = read_ckpt::<V>(&base)?;
+        apply(snap);
+        let mut gen = ckpt;
+        for seg in listed(&base, "seg")?.into_iter().filter(|g| *g >= ckpt) {
+            replay::<V>(&std::fs::read(tagged(&base, "seg", seg))?, apply)?;
+            gen = gen.max(seg + 1);
+        }
+        prune(&base, ckpt)?;
+        let mut file = OpenOptions::new()
+            .read(true).append(true).create(true)
+            .open(&base)?;
+        let mut bytes = vec![];
+        file.read_to_end(&mut bytes)?;
+        let valid = replay::<V>(&bytes, apply)?;
+        // drop a torn tail, so new records are appended right after the last valid one
+        file.set_len(valid)?;
+        file.sync_all()?;
+        let log = Arc::new(LogFile {
+            base,
+            file: RwLock::new(file.try_clone()?),
+            buf: Mutex::new(BufWriter::new(file)),
+            gen: AtomicU64::new(gen),
+            size: AtomicU64::new(valid),
+            ckpt: Mutex::new(()),
+            epoch: AtomicU64::new(1),
+            synced: AtomicU64::new(0),
+            durable: Mutex::new((gen, valid)),
+            count: AtomicUsize::new(0),
+            error: Mutex::new(None),
+        });
+        Ok(Self { table: Arc::new(table), log, pending: dashmap::DashMap::new(), group: None, ckpt: None, phant: PhantomData })
+    }
+    /// open a log file like Wal::new, but sync the log with group commit
+    /// transactions are acknowledged through RWDurable::durable_epoch instead of done
+    pub fn with_group(path: impl AsRef<Path>, group: GroupCommit) -> Result<Self, WalErr> {
+        let mut this = Self::new(path)?;
+        let sigterm = Arc::new(AtomicBool::new(false));
+        let cpyterm = Arc::clone(&sigterm);
+        let log = Arc::clone(&this.log);
+        // the flusher closes an epoch if there is anything to sync
+        let flusher = move || while !sigterm.load(Relaxed) {
+            std::thread::sleep(group.wait);
+            if log.count.load(Relaxed) == 0 { continue }
+            // the error sticks in the log, and is reported by later calls of done
+            if log.commit().is_err() { return }
+        };
+        this.group = Some((group, cpyterm, std::thread::spawn(flusher)));
+        Ok(this)
+    }
+    /// take checkpoints in background, so the log is truncated once the active segment grows past ckpt.size
+    pub fn with_checkpoint(mut self, ckpt: Checkpoint) -> Self
+    where
+        V: Send + 'static,
+        V::I: Send + 'static,
+    {
+        let sigterm = Arc::new(AtomicBool::new(false));
+        let cpyterm = Arc::clone(&sigterm);
+        let log = Arc::clone(&self.log);
+        let table = Arc::clone(&self.table);
+        let checkpointer = move || while !sigterm.load(Relaxed) {
+            std::thread::sleep(ckpt.wait);
+            if log.size.load(Relaxed) < ckpt.size { continue }
+            if let Err(e) = checkpoint(&log, &table) {
+                log.fail(e);
+                return
+            }
+        };
+        self.ckpt = Some((cpyterm, std::thread::spawn(checkpointer)));
+        self
+    }
+    /// take a checkpoint now
+    pub fn checkpoint(&self) -> Result<(), WalErr> {
+        checkpoint(&self.log, &self.table)
+    }
+    /// simulate a power loss, unlike dropping, nothing is flushed
+    /// records that are not synced are thrown away, and the active segment is cut to its durable length
+    pub fn crash(mut self) -> Result<(), WalErr> {
+        // the log fails from now on, so no background thread or drop syncs it
+        self.log.fail(WalErr::Io(std::io::Error::other("crashed")));
+        self.stop();
+        let mut buf = self.log.buf.lock();
+        let file = self.log.file.read();
+        // taking the buffer apart drops buffered records without writing them
+        drop(std::mem::replace(&mut *buf, BufWriter::new(file.try_clone()?)).into_parts());
+        // a file that is already shorter (e.g. torn by a fault) is not extended
+        file.set_len(file.metadata()?.len().min(self.log.durable.lock().1))?;
+        Ok(())
+    }
+    // stop the checkpointer and the flusher
+    fn stop(&mut self) {
+        if let Some((sigterm, checkpointer)) = self.ckpt.take() {
+            sigterm.store(true, Relaxed);
+            checkpointer.join().unwrap_or(());
+        }
+        if let Some((_, sigterm, flusher)) = self.group.take() {
+            sigterm.store(true, Relaxed);
+            flusher.join().unwrap_or(());
+        }
+    }
+    // append written mappings of a transaction as a log record and apply them to the table
+    // a record is one checksummed frame, so replay recovers either all writes of a transaction or none
+    fn append(&self, map: Vec<(V::I, Option<V>)>) -> Result<(), WalErr> {
+        if map.is_empty() { return Ok(()) }
+        let mut payload = vec![];
+        put_record(&map, &mut payload);
+        let mut frame = vec![];
+        put_frame(&payload, &mut frame);
+        // hold the log while applying, so the log order is the table order
+        let mut buf = self.log.buf.lock();
+        buf.write_all(&frame)?;
+        self.log.size.fetch_add(frame.len() as u64, Relaxed);
+        for (i, v) in map {
+            match v {
+                Some(v) => {self.table.insert(i, v);},
+                None => {self.table.remove(&i);},
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<V: Id, T: Tx<V>> Drop for Wal<V, T>
+where
+    V: Sync + Clone + Codec,
+    V::I: Eq + Hash + Sync + Clone,
+    T::I: Eq + Hash,
+    T::Prp: Filter<V>,
+    T::Map: Mapper<V::I, V>,
+{
+    fn drop(&mut self) {
+        self.stop();
+        self.log.commit().unwrap_or(());
+    }
+}
+
+/// replay all valid records in a log, return the length of the valid prefix
+/// a record that fails its checksum is treated as a torn tail, and replay stops there
+pub fn replay<V: Codec>(bytes: &[u8], mut apply: impl FnMut(Vec<(V::I, Option<V>)>)) -> Result<u64, WalErr> {
+    let mut cur = bytes;
+    loop {
+        let offset = (bytes.len() - cur.len()) as u64;
+        match get_frame(&mut cur) {
+            Ok(Some(payload)) => apply(get_record::<V>(payload).ok_or(WalErr::Corrupt(offset))?),
+            Ok(None) | Err(_) => return Ok(offset),
+        }
+    }
+}
+
+impl<V: Id, T: Tx<V>> RWDurable<V, T> for Wal<V, T>
+where
+    V: Sync + Clone + Codec,
+    V::I: Eq + Hash + Sync + Clone,
+    T::I: Eq + Hash,
+    T::Prp: Filter<V> + MaybeIndexer<V::I>,
+    T::Map: Mapper<V::I, V>,
+{
+    type Err = WalErr;
+    fn done(&self, txn: &T, end: End) -> Result<(), Self::Err> {
+        let writes = self.pending.remove(&txn.id()).map(|(_, writes)| writes);
+        if matches!(end, End::Abort) { return Ok(()) }
+        self.log.failed()?;
+        self.append(writes.unwrap_or_default())?;
+        match &self.group {
+            None => self.log.commit()?,
+            Some((group, ..)) => {
+                // the last one in a group closes the epoch, others leave it to the flusher
+                if self.log.count.fetch_add(1, Relaxed) + 1 >= group.size {
+                    self.log.commit()?
+                }
+            }
+        }
+        Ok(())
+    }
+    fn open(&self, _txn: &T) -> Result<(), Self::Err> {
+        Ok(())
+    }
+    fn rd(&self, prp: T::Prp) -> Result<T::Map, Self::Err> {
+        let mut map = vec![];
+        if let Some(prp_iter) = prp.tryc_indexer() {
+            for i in prp_iter {
+                self.table.view(&i, |i, v| {
+                    map.push((i.clone(), Some(v.clone())))
+                });
+            }
+        } else {
+            let prp = prp.into_filter();
+            self.table.alter_all(|i, v| {
+                if (prp)(&v) {
+                    map.push((i.clone(), Some(v.clone())));
+                }
+                v
+            });
+        }
+        Ok(Mapper::from_mapping(map.into_iter()))
+    }
+    // writes are buffered until the transaction is done, and they are visible to readers after that
+    fn wr(&self, txn: &T, map: T::Map) -> Result<(), Self::Err> {
+        self.pending.entry(txn.id()).or_default().extend(map.into_mapping());
+        Ok(())
+    }
+    fn wr_batch(&self, txn: &T, maps: Vec<T::Map>) -> Result<(), Self::Err> {
+        self.pending.entry(txn.id()).or_default().extend(maps.iter().flat_map(|map| map.into_mapping()));
+        Ok(())
+    }
+    fn epoch(&self) -> u64 {
+        match self.group {
+            None => 0,
+            Some(_) => self.log.epoch.load(Acquire),
+        }
+    }
+    // it stops advancing once syncing fails, so outputs after that are never released
+    fn durable_epoch(&self) -> u64 {
+        match self.group {
+            None => u64::MAX,
+            Some(_) => self.log.synced.load(Acquire),
+        }
+    }
+}
+
+impl<V: Id, T: Tx<V>> Dump<V> for Wal<V, T>
+where
+    V: Sync + Clone + Codec,
+    V::I: Eq + Hash + Sync + Clone,
+    T::I: Eq + Hash,
+    T::Prp: Filter<V>,
+    T::Map: Mapper<V::I, V>,
+{
+    type Err = WalErr;
+    fn dump(&self, f: &mut dyn FnMut(&V::I, &V)) -> Result<(), Self::Err> {
+        for e in self.table.iter() { f(e.key(), e.value()) }
+        Ok(())
+    }
+}
@@ -0,0 +1,23 @@
+#[derive(Debug)]
+pub enum WalErr {
+    // file system error
+    Io(std::io::Error),
+    // a record at a given offset passes its checksum but cannot be decoded
+    Corrupt(u64),
+}
+
+impl From<std::io::Error> for WalErr {
+    fn from(e: std::io::Error) -> Self {
+        WalErr::Io(e)
+    }
+}
+
+impl WalErr {
+    // an io error is not cloneable, so its copy keeps the kind and the message
+    pub(super) fn copied(&self) -> Self {
+        match self {
+            WalErr::Io(e) => WalErr::Io(std::io::Error::new(e.kind(), e.to_string())),
+            WalErr::Corrupt(offset) => WalErr::Corrupt(*offset),
+        }
+    }
+}
@@ -0,0 +1,26 @@
+//! ## Redo Write-Ahead Log
+//!
+//! A durable engine that keeps the table in memory and logs written mappings to a log file.
+//! Writes of a transaction are buffered, and appended as one checksummed frame when it is ready to commit, then the log is synced to disk.
+//! So a transaction is either fully in the log or not at all, there is no need for commit records.
+//! On startup, the table is rebuilt by replaying the log, a torn tail left by a crash is truncated.
+//!
+//! Fuzzy checkpoints keep the log bounded. A checkpoint seals the active log file as a segment, snapshots the table while transactions go on,
+//! and removes segments before it once the snapshot is durable. Recovery starts from the latest valid checkpoint and replays the segments after it.
+
+// wal error
+mod error;
+// log record encoding
+mod record;
+// checkpoint and segment files
+mod ckpt;
+// core wal engine implementation
+mod engine;
+
+pub use error::*;
+pub use record::*;
+pub use ckpt::Checkpoint;
+pub use engine::*;
+
+#[cfg(test)]
+mod check; // recovery checks
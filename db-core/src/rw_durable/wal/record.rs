@@ -0,0 +1,43 @@
+use typing::constraint::*;
+
+// tags of an entry in a log record
+const TAG_DEL: u8 = 0;
+const TAG_PUT: u8 = 1;
+
+/// a written mapping, ids with their values, none stands for deletion
+pub type Mapping<V> = Vec<(<V as Id>::I, Option<V>)>;
+
+/// encode a mapping as a log record payload
+///     [count: u32] ([tag: u8] [id] [value if tag is put])*
+pub fn put_record<V: Codec>(map: &[(V::I, Option<V>)], buf: &mut Vec<u8>) {
+    buf.extend((map.len() as u32).to_le_bytes());
+    for (i, v) in map {
+        match v {
+            None => {
+                buf.push(TAG_DEL);
+                V::put_id(i, buf);
+            }
+            Some(v) => {
+                buf.push(TAG_PUT);
+                V::put_id(i, buf);
+                v.put(buf);
+            }
+        }
+    }
+}
+
+/// decode a log record payload into a mapping, none if the payload is malformed
+pub fn get_record<V: Codec>(mut buf: &[u8]) -> Option<Mapping<V>> {
+    let buf = &mut buf;
+    let cnt = get_u32(buf)?;
+    let mut map = Vec::with_capacity(cnt as usize);
+    for _ in 0..cnt {
+        match take(buf, 1)?[0] {
+            TAG_DEL => map.push((V::get_id(buf)?, None)),
+            TAG_PUT => map.push((V::get_id(buf)?, Some(V::get(buf)?))),
+            _ => return None,
+        }
+    }
+    if !buf.is_empty() { return None }
+    Some(map)
+}
@@ -0,0 +1,119 @@
+use super::*;
+use crate::rw_control::Serial;
+use crate::rw_durable::null::Null;
+use db_test::core_workload::int::unif::*;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering::*};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use typing::rw::*;
+use typing::tx::*;
+
+// a durable engine whose reads are all fetched, they arrive once the gate opens
+struct Gate {
+    inner: Null<U64Tup, U64Txn>,
+    // whether the gate is open, and the number of submitted reads
+    state: Arc<(AtomicBool, AtomicUsize)>,
+}
+
+impl RWDurable<U64Tup, U64Txn> for Gate {
+    type Err = ();
+    fn rd(&self, prp: U64Prp) -> Result<U64Map, Self::Err> {
+        assert!(self.state.0.load(Acquire), "a read is served before it arrives");
+        self.inner.rd(prp)
+    }
+    fn wr(&self, txn: &U64Txn, map: U64Map) -> Result<(), Self::Err> {
+        self.inner.wr(txn, map)
+    }
+    fn open(&self, txn: &U64Txn) -> Result<(), Self::Err> {
+        self.inner.open(txn)
+    }
+    fn done(&self, txn: &U64Txn, end: End) -> Result<(), Self::Err> {
+        self.inner.done(txn, end)
+    }
+    fn submit(&self, _prp: &U64Prp) -> Option<u64> {
+        Some(self.state.1.fetch_add(1, AcqRel) as u64)
+    }
+    fn arrived(&self, _ticket: u64) -> bool {
+        self.state.0.load(Acquire)
+    }
+}
+
+// a durable engine whose writes all belong to epoch 1, which becomes durable when told to
+struct Epoch {
+    inner: Null<U64Tup, U64Txn>,
+    durable: Arc<AtomicU64>,
+}
+
+impl RWDurable<U64Tup, U64Txn> for Epoch {
+    type Err = ();
+    fn rd(&self, prp: U64Prp) -> Result<U64Map, Self::Err> {
+        self.inner.rd(prp)
+    }
+    fn wr(&self, txn: &U64Txn, map: U64Map) -> Result<(), Self::Err> {
+        self.inner.wr(txn, map)
+    }
+    fn open(&self, txn: &U64Txn) -> Result<(), Self::Err> {
+        self.inner.open(txn)
+    }
+    fn done(&self, txn: &U64Txn, end: End) -> Result<(), Self::Err> {
+        self.inner.done(txn, end)
+    }
+    fn epoch(&self) -> u64 { 1 }
+    fn durable_epoch(&self) -> u64 { self.durable.load(Acquire) }
+}
+
+fn workload() -> Vec<U64Txn> {
+    let mut workload = U64Gen::new(1145141919810, (15, 15, 1, 4), 64);
+    // transaction ids start from 1
+    (0..=200).map(|_| workload.get()).filter(|txn| txn.id() != 0).collect()
+}
+
+fn outputs(service: &impl TxService<U64Txn, U64Tup>) -> Vec<Option<u64>> {
+    workload().into_iter().map(|txn| loop {
+        if let Ok(out) = service.get(txn.id()) { break out }
+        std::thread::yield_now();
+    }).collect()
+}
+
+#[test]
+fn is_parked_read_resumed() {
+    let mut service = MThreadService::new(1, |x| x, Serial::<U64Txn, U64Tup>::new(), Null::<U64Tup, U64Txn>::new(0, 0, false));
+    service.start().unwrap();
+    for txn in workload() { service.put(txn).unwrap(); }
+    let expect = outputs(&service);
+    service.close().unwrap();
+    let state = Arc::new((AtomicBool::new(false), AtomicUsize::new(0)));
+    let gate = Gate { inner: Null::new(0, 0, false), state: state.clone() };
+    let mut service = MThreadService::new(1, |x| x, Serial::<U64Txn, U64Tup>::new(), gate);
+    service.start().unwrap();
+    for txn in workload() { service.put(txn).unwrap(); }
+    // a single worker parks a read and goes on with other transactions, until each of them is parked
+    let start = Instant::now();
+    while state.1.load(Acquire) < 50 {
+        assert!(start.elapsed() < Duration::from_secs(10), "the worker blocks on a parked read");
+        std::thread::yield_now();
+    }
+    state.0.store(true, Release);
+    assert!(outputs(&service) == expect);
+    service.close().unwrap();
+}
+
+#[test]
+fn is_output_withheld() {
+    let mut service = MThreadService::new(2, |x| x, Serial::<U64Txn, U64Tup>::new(), Null::<U64Tup, U64Txn>::new(0, 0, false));
+    service.start().unwrap();
+    for txn in workload() { service.put(txn).unwrap(); }
+    let expect = outputs(&service);
+    service.close().unwrap();
+    let durable = Arc::new(AtomicU64::new(0));
+    let dur = Epoch { inner: Null::new(0, 0, false), durable: durable.clone() };
+    let mut service = MThreadService::new(2, |x| x, Serial::<U64Txn, U64Tup>::new(), dur);
+    service.start().unwrap();
+    for txn in workload() { service.put(txn).unwrap(); }
+    // transactions are done, but no output is released before their epoch is durable
+    std::thread::sleep(Duration::from_millis(50));
+    assert!(workload().iter().all(|txn| service.get(txn.id()).is_err()));
+    durable.store(1, Release);
+    assert!(outputs(&service) == expect);
+    service.close().unwrap();
+}
@@ -4,4 +4,7 @@ mod service;
 
 pub use service::*;
 pub use handle::*;
-pub use error::*;
\ No newline at end of file
+pub use error::*;
+
+#[cfg(test)]
+mod check; // parked read checks
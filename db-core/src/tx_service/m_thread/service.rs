@@ -5,12 +5,19 @@ use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
 use std::thread::JoinHandle;
 use std::collections::BTreeMap;
+use std::time::Duration;
 use flume::{Receiver, Sender};
 use typing::tx::*;
 use typing::rw::*;
 use super::error::*;
 use super::MThreadHandle;
 
+// outputs waiting for their durability epoch, released to output list once the epoch is durable
+type PendingList<I, O> = parking_lot::Mutex<BTreeMap<u64, Vec<(I, Option<O>)>>>;
+
+// transactions parked on submitted reads with their tickets, resumed by the same worker once the data arrives
+type ParkedList<T, P> = Vec<(u64, T, P)>;
+
 // we use this macro to avoid writing the same trait bounds for multiple times
 macro_rules! ellipsis_trait_bag {
     ({$T: ty, $V: ty, $Dur: ty, $Con: ty, $InnerT: ty}
@@ -66,6 +73,9 @@ where ...
     // registered output
     output_list: Arc<dashmap::DashMap<T::I, Option<T::Out>>>,
 
+    // output that is not durable yet
+    pending_list: Arc<PendingList<T::I, T::Out>>,
+
     // internal transaction type
     inner_transaction_marker: PhantomData<InnerT>,
 }
@@ -100,6 +110,7 @@ where ...
             killer_list: vec![],
             worker_list: vec![],
             output_list: Arc::new(dashmap::DashMap::new()),
+            pending_list: Arc::new(parking_lot::Mutex::new(BTreeMap::new())),
             con: Arc::new(con),
             dur: Arc::new(dur),
             inner_transaction_marker: PhantomData,
@@ -145,15 +156,34 @@ where ...
         // return error collection
         return error_collection;
     }
+    /// move outputs in durable epochs from pending list (pen) to output list (ols)
+    fn release(
+        dur: &Dur,
+        pen: &PendingList<T::I, T::Out>,
+        ols: &dashmap::DashMap<T::I, Option<T::Out>>,
+    ) {
+        let durable = dur.durable_epoch();
+        let mut pen = pen.lock();
+        while let Some(entry) = pen.first_entry() {
+            if *entry.key() > durable { break }
+            for (tid, out) in entry.remove() {
+                ols.insert(tid, out);
+            }
+        }
+    }
     /// process one transaction (txn) with
     ///     a durability controller (dur),
     ///     a concurrency controller (con), and
-    ///     finally an output list (ols) to write results to.
+    ///     finally an output list (ols) to write results to,
+    ///     or a pending list (pen) if the results are not durable yet.
+    /// a transaction whose read has to be fetched first is parked in a parked list (pak).
     fn handle_tx(
         txn: InnerT,
         dur: &Dur,
         con: &Con,
         ols: &dashmap::DashMap<T::I, Option<T::Out>>,
+        pen: &PendingList<T::I, T::Out>,
+        pak: &mut ParkedList<InnerT, T::Prp>,
     ) -> Option<InnerT> {
         use RWClosure::*;
         // record transaction id by copying
@@ -163,7 +193,14 @@ where ...
             // move a transaction forward with an internal operation
             Op(txn) => Some(txn.op()),
             // handle read requests, panic on any internal component error
-            Rd(txn, prp) => con.rd(txn, prp, dur).unwrap(),
+            // if the data has to be fetched, park the transaction and let the worker go on with others
+            Rd(txn, prp) => match dur.submit(&prp) {
+                None => con.rd(txn, prp, dur).unwrap(),
+                Some(ticket) => {
+                    pak.push((ticket, txn, prp));
+                    None
+                }
+            },
             // handle write requests, panic on any internal component error
             Wr(txn, map) => con.wr(txn, map, dur).unwrap(),
             // close transaction with a given ending
@@ -172,9 +209,15 @@ where ...
                 let (txn, out) = con.done(txn, end, dur).unwrap();
                 // register output if there is some
                 match out {
-                    // if output is some, insert it into output_list.
+                    // if output is some and durable, insert it into output_list.
+                    // writes are done before this point, so they belong to current epoch or earlier ones
                     Some(out) => {
-                        ols.insert(tid, out);
+                        let epoch = dur.epoch();
+                        if epoch <= dur.durable_epoch() {
+                            ols.insert(tid, out);
+                        } else {
+                            pen.lock().entry(epoch).or_default().push((tid, out));
+                        }
                     }
                     // if output is none, the transaction is not really done.
                     None => {}
@@ -190,6 +233,7 @@ where ...
         let con = Arc::clone(&self.con);
         let dur = Arc::clone(&self.dur);
         let ols = Arc::clone(&self.output_list);
+        let pen = Arc::clone(&self.pending_list);
         let sigterm = Arc::new(AtomicBool::new(false));
         let cpyterm = Arc::clone(&sigterm);
         let wrapper = self.wrapper;
@@ -198,12 +242,31 @@ where ...
             use std::sync::atomic::Ordering::*;
             // a local pool for transactions
             let mut pooling = BTreeMap::new();
+            // transactions waiting for their reads
+            let mut parked = ParkedList::new();
             let core_ls = core_affinity::get_core_ids().unwrap();
             let core_id = core_ls[(_i+1) % core_ls.len()];
             core_affinity::set_for_current(core_id);
             while !sigterm.load(Relaxed) {
+                Self::release(&dur, &pen, &ols);
+                // resume transactions whose reads have arrived, the read itself is served by the concurrency control
+                let mut i = 0;
+                while i < parked.len() {
+                    if !dur.arrived(parked[i].0) { i += 1; continue }
+                    let (_, txn, prp) = parked.swap_remove(i);
+                    let mut txn = con.rd(txn, prp, &dur).unwrap();
+                    while let Some(next) = txn {
+                        txn = Self::handle_tx(next, &dur, &con, &ols, &pen, &mut parked);
+                    }
+                }
                 if rand::random::<usize>() % (pooling.len() + 1) == 0 {
-                    let txn = match recv_handle.recv() {
+                    // don't block forever if some outputs are waiting for durability or some reads are in flight
+                    let recv = if pen.lock().is_empty() && parked.is_empty() {
+                        recv_handle.recv().map_err(|_| ())
+                    } else {
+                        recv_handle.recv_timeout(Duration::from_micros(100)).map_err(|_| ())
+                    };
+                    let txn = match recv {
                         Ok(txn) => (wrapper)(txn),
                         Err(_) => continue,
                     };
@@ -219,7 +282,7 @@ where ...
                 };
                 loop {
                     // handle transaction with predefined handler
-                    txn = match Self::handle_tx(txn, &dur, &con, &ols) {
+                    txn = match Self::handle_tx(txn, &dur, &con, &ols, &pen, &mut parked) {
                         Some(txn) => txn,
                         None => break,
                     };
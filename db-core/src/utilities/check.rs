@@ -0,0 +1,119 @@
+use super::*;
+
+type Cands = Vec<(u64, u64)>;
+
+// candidates (tid, stamp) in two different iteration orders
+fn cands() -> (Cands, Cands) {
+    let fwd = (1..=16).map(|tid| (tid, 100 - tid)).collect::<Cands>();
+    let rev = fwd.iter().rev().copied().collect();
+    (fwd, rev)
+}
+
+fn picks(policy: &dyn SchedPolicy<u64>, cand: &[(u64, u64)]) -> Vec<Option<u64>> {
+    (0..256).map(|_| policy.pick(&mut cand.iter().copied())).collect()
+}
+
+#[test]
+fn is_seeded_random_replayable() {
+    let (fwd, rev) = cands();
+    let picked = picks(&SeededRandom::new(7), &fwd);
+    // the same seed reproduces the same picks, whatever order candidates come in
+    assert!(picked == picks(&SeededRandom::new(7), &fwd));
+    assert!(picked == picks(&SeededRandom::new(7), &rev));
+    // both waiting and picking happen, and only candidates are picked
+    assert!(picked.iter().any(|x| x.is_none()));
+    assert!(picked.iter().flatten().all(|tid| (1..=16).contains(tid)));
+    assert!(picked != picks(&SeededRandom::new(8), &fwd));
+    assert!(SchedPolicy::<u64>::pick(&SeededRandom::new(7), &mut std::iter::empty()).is_none());
+}
+
+#[test]
+fn is_lowest_tid_picked() {
+    let (fwd, rev) = cands();
+    assert!(LowestTid.pick(&mut fwd.into_iter()) == Some(1));
+    assert!(LowestTid.pick(&mut rev.into_iter()) == Some(1));
+}
+
+#[test]
+fn is_earliest_stamp_picked() {
+    let (fwd, rev) = cands();
+    assert!(Fifo.pick(&mut fwd.into_iter()) == Some(16));
+    assert!(Fifo.pick(&mut rev.into_iter()) == Some(16));
+}
+
+#[test]
+fn is_least_recent_reset_picked() {
+    let (fwd, _) = cands();
+    let policy = LeastRecentReset::default();
+    // never reset transactions come first, ties broken by lower id
+    assert!(policy.pick(&mut fwd.iter().copied()) == Some(1));
+    policy.reset(1);
+    assert!(policy.pick(&mut fwd.iter().copied()) == Some(2));
+    // among reset transactions, the one reset longest ago wins
+    let few = [(1, 0), (3, 0), (5, 0)];
+    policy.reset(5);
+    policy.reset(3);
+    assert!(policy.pick(&mut few.iter().copied()) == Some(1));
+    policy.reset(1);
+    assert!(policy.pick(&mut few.iter().copied()) == Some(5));
+    // a retired transaction forgets its resets
+    policy.retire(3);
+    assert!(policy.pick(&mut few.iter().copied()) == Some(3));
+}
+
+// key "a" is locked by 3, 1 reads "a" from durable storage, 2 reads "b" written by 1
+fn graph() -> DepGraph<u64, &'static str> {
+    use std::collections::BTreeSet;
+    DepGraph {
+        locks: vec![("a", 3)],
+        vers: vec![("a", 0, BTreeSet::from([1])), ("b", 1, BTreeSet::from([2]))],
+    }
+}
+
+#[test]
+fn is_dot_rendered() {
+    let dot = graph().to_dot();
+    assert!(dot == concat!(
+        "digraph kv_table {\n",
+        "    \"key \\\"a\\\"\" [shape=box];\n",
+        "    \"3\" -> \"key \\\"a\\\"\" [style=dashed, label=\"lock\"];\n",
+        "    \"durable\" -> \"1\" [label=\"\\\"a\\\"\"];\n",
+        "    \"1\" -> \"2\" [label=\"\\\"b\\\"\"];\n",
+        "}\n",
+    ));
+}
+
+#[test]
+fn is_json_rendered() {
+    let json = graph().to_json();
+    assert!(json == concat!(
+        "{\"locks\":[{\"key\":\"\\\"a\\\"\",\"holder\":3}],",
+        "\"versions\":[{\"key\":\"\\\"a\\\"\",\"writer\":0,\"readers\":[1]},",
+        "{\"key\":\"\\\"b\\\"\",\"writer\":1,\"readers\":[2]}]}",
+    ));
+}
+
+#[test]
+fn is_cascade_followed() {
+    let sub = graph().cascade(1);
+    // 1 and 2 are reset, the durable version and the lock of 3 are untouched
+    assert!(sub.locks.is_empty());
+    assert!(sub.vers.len() == 1 && sub.vers[0].0 == "b");
+    assert!(sub.edges().map(|(w, r, _)| (w, r)).collect::<Vec<_>>() == vec![(1, 2)]);
+}
+
+#[test]
+fn is_positioned_io_exact() {
+    let path = std::env::temp_dir().join(format!("db-core-pos-io-{}", rand::random::<u64>()));
+    let file = std::fs::OpenOptions::new().read(true).write(true).create(true).truncate(true).open(&path).unwrap();
+    file.write_all_at(b"world", 6).unwrap();
+    file.write_all_at(b"hello ", 0).unwrap();
+    let mut buf = [0u8; 11];
+    file.read_exact_at(&mut buf, 0).unwrap();
+    assert!(&buf == b"hello world");
+    // reading past the end fails instead of returning a short buffer
+    let err = file.read_exact_at(&mut [0u8; 4], 9).unwrap_err();
+    assert!(err.kind() == std::io::ErrorKind::UnexpectedEof);
+    drop(file);
+    std::fs::remove_file(&path).unwrap();
+}
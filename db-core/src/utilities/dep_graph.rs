@@ -0,0 +1,85 @@
+use crate::constraint::Nat;
+use std::collections::{BTreeSet, VecDeque};
+use std::fmt::{Debug, Display, Write};
+
+/// a snapshot of speculative dependencies in a kv table
+/// the snapshot is taken shard by shard, so it is not atomic across keys
+#[derive(Debug, Clone)]
+pub struct DepGraph<N, K> {
+    /// write locks, (key, holder)
+    pub locks: Vec<(K, N)>,
+    /// versions, (key, writer, readers), writer zero stands for durable storage
+    pub vers: Vec<(K, N, BTreeSet<N>)>,
+}
+
+impl<N, K> DepGraph<N, K>
+where
+    N: Copy + Ord + Nat + Debug,
+    K: Clone + Debug,
+{
+    /// all dependency edges, (writer, reader, key)
+    pub fn edges(&self) -> impl Iterator<Item = (N, N, &K)> + '_ {
+        self.vers.iter().flat_map(|(key, wid, deps)| deps.iter().map(move |rid| (*wid, *rid, key)))
+    }
+    /// the sub-graph of resets that cascade from a given transaction
+    /// a reset transaction unwrites its versions, so all their readers are reset, and so on
+    pub fn cascade(&self, root: N) -> Self {
+        let mut seen = BTreeSet::from([root]);
+        let mut todo = VecDeque::from([root]);
+        while let Some(wid) = todo.pop_front() {
+            for (_, w, deps) in &self.vers {
+                if *w != wid { continue }
+                for rid in deps {
+                    if seen.insert(*rid) { todo.push_back(*rid) }
+                }
+            }
+        }
+        DepGraph {
+            locks: self.locks.iter().filter(|(_, h)| seen.contains(h)).cloned().collect(),
+            vers: self.vers.iter().filter(|(_, w, _)| seen.contains(w) && *w != N::zero()).cloned().collect(),
+        }
+    }
+}
+
+impl<N, K> DepGraph<N, K>
+where
+    N: Copy + Ord + Nat + Display + Debug,
+    K: Clone + Debug,
+{
+    /// render as a graphviz digraph, edges go from writer to reader
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph kv_table {\n");
+        let node = |n: &N| if *n == N::zero() { "durable".to_string() } else { n.to_string() };
+        for (key, holder) in &self.locks {
+            let key = escape(&format!("{key:?}"));
+            writeln!(out, "    \"key {key}\" [shape=box];").unwrap();
+            writeln!(out, "    \"{}\" -> \"key {key}\" [style=dashed, label=\"lock\"];", node(holder)).unwrap();
+        }
+        for (wid, rid, key) in self.edges() {
+            let key = escape(&format!("{key:?}"));
+            writeln!(out, "    \"{}\" -> \"{}\" [label=\"{key}\"];", node(&wid), node(&rid)).unwrap();
+        }
+        out.push_str("}\n");
+        out
+    }
+    /// render as a json object {"locks": [...], "versions": [...]}, keys are debug strings and ids are numbers
+    pub fn to_json(&self) -> String {
+        let locks = self.locks.iter()
+            .map(|(key, holder)| format!(
+                "{{\"key\":\"{}\",\"holder\":{holder}}}",
+                escape(&format!("{key:?}"))))
+            .collect::<Vec<_>>().join(",");
+        let vers = self.vers.iter()
+            .map(|(key, wid, deps)| format!(
+                "{{\"key\":\"{}\",\"writer\":{wid},\"readers\":[{}]}}",
+                escape(&format!("{key:?}")),
+                deps.iter().map(|rid| rid.to_string()).collect::<Vec<_>>().join(",")))
+            .collect::<Vec<_>>().join(",");
+        format!("{{\"locks\":[{locks}],\"versions\":[{vers}]}}")
+    }
+}
+
+// escape a string for both dot and json string literals
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
@@ -0,0 +1,35 @@
+use super::crc32c;
+use typing::constraint::*;
+
+// frame header: payload length (u32) and payload checksum (u32), little endian
+pub const FRAME_HEAD: usize = 8;
+
+/// append a checksummed frame of payload to a buffer
+pub fn put_frame(payload: &[u8], buf: &mut Vec<u8>) {
+    buf.extend((payload.len() as u32).to_le_bytes());
+    buf.extend(crc32c(payload).to_le_bytes());
+    buf.extend(payload);
+}
+
+/// why a frame cannot be taken from a buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameErr {
+    // the buffer ends in the middle of a frame
+    Torn,
+    // the payload mismatches its checksum
+    Checksum,
+}
+
+/// take a frame from the front of a buffer, return its payload
+///     Ok(None) if the buffer is empty
+///     Err(_) if the frame is torn or its checksum mismatches
+pub fn get_frame<'a>(buf: &mut &'a [u8]) -> Result<Option<&'a [u8]>, FrameErr> {
+    if buf.is_empty() { return Ok(None) }
+    let mut cur = *buf;
+    let len = get_u32(&mut cur).ok_or(FrameErr::Torn)? as usize;
+    let crc = get_u32(&mut cur).ok_or(FrameErr::Torn)?;
+    let payload = take(&mut cur, len).ok_or(FrameErr::Torn)?;
+    if crc32c(payload) != crc { return Err(FrameErr::Checksum) }
+    *buf = cur;
+    Ok(Some(payload))
+}
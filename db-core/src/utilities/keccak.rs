@@ -0,0 +1,64 @@
+// keccak-f[1600] round constants
+const RC: [u64; 24] = [
+    0x0000000000000001, 0x0000000000008082, 0x800000000000808a, 0x8000000080008000,
+    0x000000000000808b, 0x0000000080000001, 0x8000000080008081, 0x8000000000008009,
+    0x000000000000008a, 0x0000000000000088, 0x0000000080008009, 0x000000008000000a,
+    0x000000008000808b, 0x800000000000008b, 0x8000000000008089, 0x8000000000008003,
+    0x8000000000008002, 0x8000000000000080, 0x000000000000800a, 0x800000008000000a,
+    0x8000000080008081, 0x8000000000008080, 0x0000000080000001, 0x8000000080008008,
+];
+
+// rotation offsets and lane positions of the rho and pi steps
+const ROT: [u32; 24] = [1, 3, 6, 10, 15, 21, 28, 36, 45, 55, 2, 14, 27, 41, 56, 8, 25, 43, 62, 18, 39, 61, 20, 44];
+const PI: [usize; 24] = [10, 7, 11, 17, 18, 3, 5, 16, 8, 21, 24, 4, 15, 23, 19, 13, 12, 2, 20, 14, 22, 9, 6, 1];
+
+// the rate of keccak-256 in bytes
+const RATE: usize = 136;
+
+fn keccak_f(st: &mut [u64; 25]) {
+    for rc in RC {
+        // theta
+        let mut bc = [0u64; 5];
+        for i in 0..5 { bc[i] = st[i] ^ st[i + 5] ^ st[i + 10] ^ st[i + 15] ^ st[i + 20] }
+        for i in 0..5 {
+            let t = bc[(i + 4) % 5] ^ bc[(i + 1) % 5].rotate_left(1);
+            for j in (0..25).step_by(5) { st[j + i] ^= t }
+        }
+        // rho and pi
+        let mut t = st[1];
+        for i in 0..24 {
+            let tmp = st[PI[i]];
+            st[PI[i]] = t.rotate_left(ROT[i]);
+            t = tmp;
+        }
+        // chi
+        for j in (0..25).step_by(5) {
+            let row = [st[j], st[j + 1], st[j + 2], st[j + 3], st[j + 4]];
+            for i in 0..5 { st[j + i] = row[i] ^ (!row[(i + 1) % 5] & row[(i + 2) % 5]) }
+        }
+        // iota
+        st[0] ^= rc;
+    }
+}
+
+/// keccak-256 hash of a byte slice, with the original keccak padding used by ethereum (not sha3-256)
+pub fn keccak256(bytes: &[u8]) -> [u8; 32] {
+    let mut st = [0u64; 25];
+    let absorb = |st: &mut [u64; 25], block: &[u8]| {
+        for (lane, word) in st.iter_mut().zip(block.chunks(8)) {
+            *lane ^= u64::from_le_bytes(word.try_into().unwrap());
+        }
+        keccak_f(st);
+    };
+    let mut chunks = bytes.chunks_exact(RATE);
+    for block in &mut chunks { absorb(&mut st, block) }
+    let rest = chunks.remainder();
+    let mut last = [0u8; RATE];
+    last[..rest.len()].copy_from_slice(rest);
+    last[rest.len()] ^= 0x01;
+    last[RATE - 1] ^= 0x80;
+    absorb(&mut st, &last);
+    let mut out = [0u8; 32];
+    for (word, lane) in out.chunks_mut(8).zip(st) { word.copy_from_slice(&lane.to_le_bytes()) }
+    out
+}
@@ -32,6 +32,7 @@ pub enum KVTableErr {
 }
 
 use crate::constraint::Nat;
+use super::DepGraph;
 use std::fmt::Debug;
 use KVTableErr::*;
 
@@ -169,6 +170,21 @@ where
             });
         return out;
     }
+    /// take a snapshot of write locks and version dependencies
+    pub fn dep_graph(&self) -> DepGraph<N, K> {
+        let mut graph = DepGraph { locks: vec![], vers: vec![] };
+        for entry in self.inner.iter() {
+            let (key, Entry { lock, vals }) = entry.pair();
+            if let Some(holder) = lock {
+                graph.locks.push((key.clone(), *holder));
+            }
+            for (wid, (_val, deps)) in vals {
+                graph.vers.push((key.clone(), *wid, deps.clone()));
+            }
+        }
+        graph.vers.sort_by_key(|(_, wid, _)| *wid);
+        graph
+    }
     /// prune an entry, only keeps information after cutter id.
     pub fn prune(&self, key: &K, cut: &N) {
         let upd = |_key: &K, mut entry: Entry<N, V>| -> Entry<N, V> {
@@ -4,4 +4,19 @@
 mod wrap;
 pub use wrap::*;
 mod kv_table;
-pub use kv_table::*;
\ No newline at end of file
+pub use kv_table::*;
+mod sched;
+pub use sched::*;
+mod dep_graph;
+pub use dep_graph::*;
+mod crc;
+pub use crc::*;
+mod frame;
+pub use frame::*;
+mod keccak;
+pub use keccak::*;
+mod pos_io;
+pub use pos_io::*;
+
+#[cfg(test)]
+mod check; // widget checks
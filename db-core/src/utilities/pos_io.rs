@@ -0,0 +1,73 @@
+use std::fs::File;
+use std::io::Result;
+
+/// positioned reads and writes on a file, pread / pwrite on unix and seek_read / seek_write on windows
+/// on windows the file cursor is moved, so cursor-based i/o on the same handle shouldn't rely on it
+pub trait PosIo {
+    /// read exactly buf.len() bytes at an offset, fail with UnexpectedEof if the file ends early
+    fn read_exact_at(&self, buf: &mut [u8], at: u64) -> Result<()>;
+    /// write the whole buffer at an offset
+    fn write_all_at(&self, buf: &[u8], at: u64) -> Result<()>;
+}
+
+#[cfg(unix)]
+impl PosIo for File {
+    fn read_exact_at(&self, buf: &mut [u8], at: u64) -> Result<()> {
+        std::os::unix::fs::FileExt::read_exact_at(self, buf, at)
+    }
+    fn write_all_at(&self, buf: &[u8], at: u64) -> Result<()> {
+        std::os::unix::fs::FileExt::write_all_at(self, buf, at)
+    }
+}
+
+#[cfg(windows)]
+impl PosIo for File {
+    fn read_exact_at(&self, mut buf: &mut [u8], mut at: u64) -> Result<()> {
+        use std::io::ErrorKind;
+        use std::os::windows::fs::FileExt;
+        while !buf.is_empty() {
+            match self.seek_read(buf, at) {
+                Ok(0) => return Err(ErrorKind::UnexpectedEof.into()),
+                Ok(n) => { buf = &mut buf[n..]; at += n as u64; }
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+    fn write_all_at(&self, mut buf: &[u8], mut at: u64) -> Result<()> {
+        use std::io::ErrorKind;
+        use std::os::windows::fs::FileExt;
+        while !buf.is_empty() {
+            match self.seek_write(buf, at) {
+                Ok(0) => return Err(ErrorKind::WriteZero.into()),
+                Ok(n) => { buf = &buf[n..]; at += n as u64; }
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// a positioned file that can be sized and synced, the surface of a file that on-disk engines run over
+pub trait FileIo: PosIo {
+    /// the length in bytes
+    fn size(&self) -> Result<u64>;
+    /// truncate or extend with zeros
+    fn set_len(&self, len: u64) -> Result<()>;
+    /// make written bytes and the length durable
+    fn sync_data(&self) -> Result<()>;
+}
+
+impl FileIo for File {
+    fn size(&self) -> Result<u64> {
+        Ok(self.metadata()?.len())
+    }
+    fn set_len(&self, len: u64) -> Result<()> {
+        File::set_len(self, len)
+    }
+    fn sync_data(&self) -> Result<()> {
+        File::sync_data(self)
+    }
+}
@@ -0,0 +1,98 @@
+use parking_lot::Mutex;
+use rand::{Rng, SeedableRng};
+use rand_xoshiro::Xoshiro256PlusPlus;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::atomic::{AtomicU64, Ordering::*};
+
+/// a scheduling policy decides which suspended transaction to run
+/// when the next-in-order transaction is not available
+pub trait SchedPolicy<I> {
+    /// a transaction is reset and will be put back to the pool
+    fn reset(&self, _tid: I) {}
+    /// a transaction is finalized and will never come back
+    fn retire(&self, _tid: I) {}
+    /// pick one from candidates (tid, enqueue stamp), None means waiting for the next-in-order one
+    fn pick(&self, cand: &mut dyn Iterator<Item = (I, u64)>) -> Option<I>;
+}
+
+/// always pick the transaction with the lowest id
+pub struct LowestTid;
+
+impl<I: Ord> SchedPolicy<I> for LowestTid {
+    fn pick(&self, cand: &mut dyn Iterator<Item = (I, u64)>) -> Option<I> {
+        cand.map(|(tid, _)| tid).min()
+    }
+}
+
+/// pick the transaction that is suspended for the longest time
+pub struct Fifo;
+
+impl<I> SchedPolicy<I> for Fifo {
+    fn pick(&self, cand: &mut dyn Iterator<Item = (I, u64)>) -> Option<I> {
+        cand.min_by_key(|(_, stamp)| *stamp).map(|(tid, _)| tid)
+    }
+}
+
+/// a conflict-aware policy, pick the transaction that is reset least recently
+/// transactions that are never reset come first, ties are broken by lower id
+pub struct LeastRecentReset<I> {
+    // logical clock, ticks on every reset
+    clock: AtomicU64,
+    // the last reset time of each transaction
+    last: Mutex<HashMap<I, u64>>,
+}
+
+impl<I: Eq + Hash> LeastRecentReset<I> {
+    pub fn new() -> Self {
+        Self {
+            clock: AtomicU64::new(1),
+            last: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl<I: Eq + Hash> Default for LeastRecentReset<I> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<I: Eq + Hash + Ord + Copy> SchedPolicy<I> for LeastRecentReset<I> {
+    fn reset(&self, tid: I) {
+        let now = self.clock.fetch_add(1, Relaxed);
+        self.last.lock().insert(tid, now);
+    }
+    fn retire(&self, tid: I) {
+        self.last.lock().remove(&tid);
+    }
+    fn pick(&self, cand: &mut dyn Iterator<Item = (I, u64)>) -> Option<I> {
+        let last = self.last.lock();
+        cand.map(|(tid, _)| (last.get(&tid).copied().unwrap_or(0), tid))
+            .min().map(|(_, tid)| tid)
+    }
+}
+
+/// flip a seeded coin to decide whether to pick, then pick uniformly
+/// this is the original behaviour of transaction pool, but replayable
+/// candidates are sorted by id first, so a seed gives the same schedule regardless of pool iteration order
+pub struct SeededRandom {
+    rng: Mutex<Xoshiro256PlusPlus>,
+}
+
+impl SeededRandom {
+    pub fn new(seed: u64) -> Self {
+        Self { rng: Mutex::new(Xoshiro256PlusPlus::seed_from_u64(seed)) }
+    }
+}
+
+impl<I: Ord> SchedPolicy<I> for SeededRandom {
+    fn pick(&self, cand: &mut dyn Iterator<Item = (I, u64)>) -> Option<I> {
+        let mut cand = cand.map(|(tid, _)| tid).collect::<Vec<_>>();
+        cand.sort_unstable();
+        let mut rng = self.rng.lock();
+        if cand.is_empty() || rng.gen::<bool>() { return None }
+        let n = rng.gen_range(0..cand.len());
+        Some(cand.swap_remove(n))
+    }
+}
@@ -1,4 +1,5 @@
 use crate::tx::*;
+use crate::constraint::TxCmd;
 
 // pack a transaction auxiliary information
 #[derive(Debug, Clone)]
@@ -44,4 +45,17 @@ where
     fn cl(self) -> Option<T::Out> {
         self.tx.cl()
     }
-}
\ No newline at end of file
+}
+// only the transaction is a command, auxiliary information starts over on decoding
+impl<T, A> TxCmd for Wrap<T, A>
+where
+    T: TxCmd,
+    A: Default,
+{
+    fn put_cmd(&self, buf: &mut Vec<u8>) {
+        self.tx.put_cmd(buf)
+    }
+    fn get_cmd(buf: &mut &[u8]) -> Option<Self> {
+        Some(Wrap { tx: T::get_cmd(buf)?, ax: A::default() })
+    }
+}
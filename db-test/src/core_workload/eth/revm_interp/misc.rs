@@ -1,5 +1,6 @@
 use revm_primitives::*;
-use typing::constraint::{Filter, Mapper, MaybeIndexer, Id};
+use std::ops::Bound;
+use typing::constraint::{Filter, Mapper, MaybeIndexer, MaybeRanger, MaybeLookup, Id, Codec, TrieLeaf, get_u64};
 
 #[derive(Debug, Clone)]
 pub struct EVMU256Tup(pub U256, pub U256);
@@ -17,6 +18,42 @@ impl Id for EVMU256Tup {
     }
 }
 
+// u256 is encoded as four little endian limbs
+fn put_u256(x: &U256, buf: &mut Vec<u8>) {
+    for limb in x.as_limbs() { buf.extend(limb.to_le_bytes()) }
+}
+
+fn get_u256(buf: &mut &[u8]) -> Option<U256> {
+    Some(U256::from_limbs([get_u64(buf)?, get_u64(buf)?, get_u64(buf)?, get_u64(buf)?]))
+}
+
+impl Codec for EVMU256Tup {
+    fn put_id(id: &U256, buf: &mut Vec<u8>) {
+        put_u256(id, buf)
+    }
+    fn get_id(buf: &mut &[u8]) -> Option<U256> {
+        get_u256(buf)
+    }
+    fn put(&self, buf: &mut Vec<u8>) {
+        put_u256(&self.0, buf);
+        put_u256(&self.1, buf);
+    }
+    fn get(buf: &mut &[u8]) -> Option<Self> {
+        Some(EVMU256Tup(get_u256(buf)?, get_u256(buf)?))
+    }
+}
+
+// storage slots are hashed as 32-byte big endian, values are big endian without leading zeros
+impl TrieLeaf for EVMU256Tup {
+    fn trie_key(id: &U256) -> Vec<u8> {
+        id.to_be_bytes::<32>().to_vec()
+    }
+    fn trie_val(&self) -> Vec<u8> {
+        let val = self.1.to_be_bytes::<32>();
+        val[self.1.leading_zeros() / 8..].to_vec()
+    }
+}
+
 impl Mapper<U256, EVMU256Tup> for EVMU256Map {
     fn from_mapping<Iter: Iterator<Item = (U256, Option<EVMU256Tup>)>>(mut iter: Iter) -> Self {
         EVMU256Map(match iter.next() {
@@ -59,4 +96,12 @@ impl Filter<EVMU256Tup> for EVMU256Prp {
     fn into_filter(&self) -> Box<dyn Fn(&EVMU256Tup) -> bool + '_> {
         Box::new(|x| &self.0 == &x.0)
     }
-}
\ No newline at end of file
+}
+
+impl MaybeRanger<U256> for EVMU256Prp {
+    fn tryc_ranger(&self) -> Option<(Bound<U256>, Bound<U256>)> {
+        Some((Bound::Included(self.0), Bound::Included(self.0)))
+    }
+}
+
+impl MaybeLookup for EVMU256Prp {}
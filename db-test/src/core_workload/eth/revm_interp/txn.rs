@@ -3,7 +3,7 @@ use super::misc::*;
 use revm_interpreter::*;
 use revm_primitives::*;
 use typing::tx::*;
-use typing::constraint::TxCkpt;
+use typing::constraint::{TxCkpt, TxCmd, take, get_u64, get_u32};
 
 pub struct REVMInterpTxnInner {
     id: usize,
@@ -37,6 +37,29 @@ impl TxCkpt for REVMInterpTxn {
     }
 }
 
+// command: [id: u64] [bytecode length: u32] [bytecode] [input length: u32] [input]
+impl TxCmd for REVMInterpTxn {
+    fn put_cmd(&self, buf: &mut Vec<u8>) {
+        let contract = &self.0.interp.contract;
+        let bytecode = contract.bytecode.original_bytecode_slice();
+        buf.extend((self.0.id as u64).to_le_bytes());
+        buf.extend((bytecode.len() as u32).to_le_bytes());
+        buf.extend(bytecode);
+        buf.extend((contract.input.len() as u32).to_le_bytes());
+        buf.extend(&contract.input[..]);
+    }
+    fn get_cmd(buf: &mut &[u8]) -> Option<Self> {
+        let id = get_u64(buf)? as usize;
+        let len = get_u32(buf)? as usize;
+        let bytecode = Bytes::from(take(buf, len)?.to_vec());
+        let bytecode = analysis::to_analysed(Bytecode::new_raw(bytecode));
+        let bytecode = BytecodeLocked::try_from(bytecode).ok()?;
+        let len = get_u32(buf)? as usize;
+        let input = Bytes::from(take(buf, len)?.to_vec());
+        Some(REVMInterpTxn(Box::new(REVMInterpTxnInner::new(id, bytecode, input))))
+    }
+}
+
 impl REVMInterpTxnInner {
     pub fn new(id: usize, bytecode: BytecodeLocked, input: Bytes) 
     -> Self {
@@ -1,3 +1,4 @@
+use std::ops::Bound;
 use typing::constraint::*;
 
 #[derive(Debug, Clone, Copy)]
@@ -16,6 +17,35 @@ impl Id for U64Tup {
     }
 }
 
+impl Codec for U64Tup {
+    fn put_id(id: &u64, buf: &mut Vec<u8>) {
+        buf.extend(id.to_le_bytes());
+    }
+    fn get_id(buf: &mut &[u8]) -> Option<u64> {
+        get_u64(buf)
+    }
+    fn put(&self, buf: &mut Vec<u8>) {
+        buf.extend(self.0.to_le_bytes());
+        buf.extend(self.1.to_le_bytes());
+    }
+    fn get(buf: &mut &[u8]) -> Option<Self> {
+        Some(U64Tup(get_u64(buf)?, get_u64(buf)?))
+    }
+}
+
+// keys are 32-byte big endian slots and values are big endian without leading zeros, like evm storage
+impl TrieLeaf for U64Tup {
+    fn trie_key(id: &u64) -> Vec<u8> {
+        let mut key = vec![0u8; 24];
+        key.extend(id.to_be_bytes());
+        key
+    }
+    fn trie_val(&self) -> Vec<u8> {
+        let val = self.1.to_be_bytes();
+        val[self.1.leading_zeros() as usize / 8..].to_vec()
+    }
+}
+
 impl Mapper<u64, U64Tup> for U64Map {
     fn from_mapping<Iter: Iterator<Item = (u64, Option<U64Tup>)>>(mut iter: Iter) -> Self {
         return U64Map(iter.next());
@@ -56,4 +86,12 @@ impl MaybeIndexer<u64> for U64Prp {
             }
         })))
     }
-}
\ No newline at end of file
+}
+
+impl MaybeRanger<u64> for U64Prp {
+    fn tryc_ranger(&self) -> Option<(Bound<u64>, Bound<u64>)> {
+        Some((Bound::Included(self.0), Bound::Included(self.0)))
+    }
+}
+
+impl MaybeLookup for U64Prp {}
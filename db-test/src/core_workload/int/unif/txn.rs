@@ -32,6 +32,27 @@ impl TxCkpt for U64Txn {
     }
 }
 
+impl TxCmd for U64Txn {
+    fn put_cmd(&self, buf: &mut Vec<u8>) {
+        let U64Txn { id, seed, rwac, vrng, vmsk, voff, rcnt, wcnt } = *self;
+        for x in [id, seed, rwac.0, rwac.1, rwac.2, rwac.3, vrng, vmsk, voff, rcnt, wcnt] {
+            buf.extend(x.to_le_bytes());
+        }
+    }
+    fn get_cmd(buf: &mut &[u8]) -> Option<Self> {
+        Some(U64Txn {
+            id: get_u64(buf)?,
+            seed: get_u64(buf)?,
+            rwac: (get_u64(buf)?, get_u64(buf)?, get_u64(buf)?, get_u64(buf)?),
+            vrng: get_u64(buf)?,
+            vmsk: get_u64(buf)?,
+            voff: get_u64(buf)?,
+            rcnt: get_u64(buf)?,
+            wcnt: get_u64(buf)?,
+        })
+    }
+}
+
 impl U64Txn {
     // get the random number
     fn num(&mut self) -> u64 {
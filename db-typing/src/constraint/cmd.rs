@@ -0,0 +1,6 @@
+/// byte encoding of a transaction input, taken before the transaction runs
+/// a deterministic protocol can rebuild its state by re-executing decoded transactions in order
+pub trait TxCmd: Sized {
+    fn put_cmd(&self, buf: &mut Vec<u8>);
+    fn get_cmd(buf: &mut &[u8]) -> Option<Self>;
+}
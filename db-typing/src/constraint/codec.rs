@@ -0,0 +1,28 @@
+use super::Id;
+
+/// byte encoding of a data item and its identity, so that it can be stored in files
+/// identities are encoded through the data item type, since they are often foreign types
+pub trait Codec: Id + Sized {
+    fn put_id(id: &Self::I, buf: &mut Vec<u8>);
+    fn get_id(buf: &mut &[u8]) -> Option<Self::I>;
+    fn put(&self, buf: &mut Vec<u8>);
+    fn get(buf: &mut &[u8]) -> Option<Self>;
+}
+
+/// take n bytes from the front of a buffer, none if there are not enough bytes
+pub fn take<'a>(buf: &mut &'a [u8], n: usize) -> Option<&'a [u8]> {
+    if buf.len() < n { return None }
+    let (head, tail) = buf.split_at(n);
+    *buf = tail;
+    Some(head)
+}
+
+/// take a little endian u64 from the front of a buffer
+pub fn get_u64(buf: &mut &[u8]) -> Option<u64> {
+    Some(u64::from_le_bytes(take(buf, 8)?.try_into().ok()?))
+}
+
+/// take a little endian u32 from the front of a buffer
+pub fn get_u32(buf: &mut &[u8]) -> Option<u32> {
+    Some(u32::from_le_bytes(take(buf, 4)?.try_into().ok()?))
+}
@@ -0,0 +1,12 @@
+use std::ops::Bound;
+
+/// the n-th declared index, and a range of its secondary keys
+pub type Lookup = (usize, Bound<Vec<u8>>, Bound<Vec<u8>>);
+
+/// a proposition that only holds on data items whose secondary key, taken by the n-th declared index, is inside a range
+/// secondary keys are bytes compared in order, so a composite key is encoded big endian field by field
+pub trait MaybeLookup {
+    fn tryc_lookup(&self) -> Option<Lookup> {
+        None
+    }
+}
@@ -2,6 +2,10 @@
 mod ckpt;
 pub use ckpt::*;
 
+// transaction input as a command
+mod cmd;
+pub use cmd::*;
+
 // natural number
 mod nat;
 pub use nat::*;
@@ -11,6 +15,10 @@ mod filter; // the most loose contraints on set proposition
 pub use filter::*;
 mod indexer; // directly index an element
 pub use indexer::*;
+mod ranger; // bound elements by a key range
+pub use ranger::*;
+mod lookup; // look up elements through a secondary index
+pub use lookup::*;
 
 // mapping
 mod mapper; // 
@@ -23,3 +31,11 @@ pub use lap::*;
 // identity on a data item
 mod identity;
 pub use identity::*;
+
+// byte encoding of a data item
+mod codec;
+pub use codec::*;
+
+// merkle patricia trie leaf of a data item
+mod trie;
+pub use trie::*;
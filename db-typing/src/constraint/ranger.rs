@@ -0,0 +1,8 @@
+use std::ops::Bound;
+
+/// a proposition that only holds on keys inside a range, so ordered storage can skip keys outside it
+pub trait MaybeRanger<I> {
+    fn tryc_ranger(&self) -> Option<(Bound<I>, Bound<I>)> {
+        None
+    }
+}
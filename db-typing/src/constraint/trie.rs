@@ -0,0 +1,9 @@
+use super::Id;
+
+/// a data item as a leaf of a merkle patricia trie
+/// the trie path is the keccak hash of trie_key, the leaf holds the rlp encoding of trie_val
+/// an empty trie_val means the item is absent from the trie, like a zero storage slot in ethereum
+pub trait TrieLeaf: Id {
+    fn trie_key(id: &Self::I) -> Vec<u8>;
+    fn trie_val(&self) -> Vec<u8>;
+}
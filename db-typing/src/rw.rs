@@ -10,6 +10,25 @@ pub trait RWDurable<V, T: Tx<V>> {
     fn wr(&self, txn: &T, map: T::Map) -> Result<(), Self::Err>;
     fn open(&self, txn: &T) -> Result<(), Self::Err>;
     fn done(&self, txn: &T, end: End) -> Result<(), Self::Err>;
+    /// read many propositions in one call, maps are in proposition order
+    /// engines that pay a cost per call (latency, round trips, syncs) override it to pay once
+    fn rd_batch(&self, prps: Vec<T::Prp>) -> Result<Vec<T::Map>, Self::Err> {
+        prps.into_iter().map(|prp| self.rd(prp)).collect()
+    }
+    /// apply many maps of a transaction in one call, in order
+    fn wr_batch(&self, txn: &T, maps: Vec<T::Map>) -> Result<(), Self::Err> {
+        for map in maps { self.wr(txn, map)? }
+        Ok(())
+    }
+    /// the current epoch, everything written before this call belongs to this epoch or earlier ones
+    fn epoch(&self) -> u64 { 0 }
+    /// the latest durable epoch, outputs of transactions done in epochs up to it are safe to release
+    fn durable_epoch(&self) -> u64 { u64::MAX }
+    /// submit a read ahead of rd, return a ticket if its data has to be fetched, none if rd can serve it at once
+    /// a submission only fetches data, rd is still called for the result once the ticket arrives
+    fn submit(&self, _prp: &T::Prp) -> Option<u64> { None }
+    /// whether the data of a submitted read has arrived, it is polled by the worker that holds the ticket
+    fn arrived(&self, _ticket: u64) -> bool { true }
 }
 
 // a read-write control inteface